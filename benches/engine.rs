@@ -0,0 +1,229 @@
+//! Benchmarks of move generation and search. Run with `cargo bench`.
+
+use azul::{
+    random_move, search, search_with_options, BoardEvaluation, DeterministicGameState,
+    SearchOptions, State,
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::{rngs::StdRng, SeedableRng};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+// the system allocator, counting allocations
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+// a state some random moves into the game
+fn midgame(seed: u64, moves: usize) -> State {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    for _ in 0..moves {
+        state = random_move(&state, &mut rng);
+    }
+    state
+}
+
+// positions early, mid and late in the first rounds
+fn positions() -> Vec<State> {
+    [(1, 0), (2, 4), (3, 12), (4, 22)]
+        .into_iter()
+        .map(|(seed, moves)| midgame(seed, moves))
+        .collect()
+}
+
+// a new game dealt with two pairs of equal factories
+fn equal_factories() -> State {
+    State::from_notation(
+        "2BW/2BW/AYRR/AYRR/WAYR - - 0:-/-/-/-/-:...../...../...../...../.....:- 0:-/-/-/-/-:...../...../...../...../.....:- 0",
+    )
+    .unwrap()
+}
+
+// a state where any move ends the round
+fn round_end() -> State {
+    let mut rng = StdRng::seed_from_u64(5);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    while !state
+        .legal_moves()
+        .iter()
+        .all(|m| state.play(m).needs_deal())
+    {
+        state = random_move(&state, &mut rng);
+    }
+    state
+}
+
+fn move_generation(c: &mut Criterion) {
+    let positions = positions();
+    c.bench_function("legal_moves", |b| {
+        b.iter(|| {
+            for state in &positions {
+                black_box(state.legal_moves());
+            }
+        })
+    });
+    c.bench_function("clone", |b| {
+        b.iter(|| {
+            for state in &positions {
+                black_box(state.clone());
+            }
+        })
+    });
+    c.bench_function("children", |b| {
+        b.iter(|| {
+            for state in &positions {
+                black_box(state.children());
+            }
+        })
+    });
+    c.bench_function("apply_in_place", |b| {
+        let mut positions = positions.clone();
+        b.iter(|| {
+            for state in &mut positions {
+                for m in state.legal_moves() {
+                    let undo = state.apply_in_place(&m);
+                    black_box(&state);
+                    state.undo(undo);
+                }
+            }
+        })
+    });
+}
+
+// making a move that ends the round, which scores every board
+fn round_scoring(c: &mut Criterion) {
+    let mut state = round_end();
+    let m = state.legal_moves()[0];
+    c.bench_function("prepare_next_round", |b| {
+        b.iter(|| {
+            let undo = state.apply_in_place(&m);
+            black_box(&state);
+            state.undo(undo);
+        })
+    });
+}
+
+fn hashing(c: &mut Criterion) {
+    let positions = positions();
+    c.bench_function("key", |b| {
+        b.iter(|| {
+            for state in &positions {
+                black_box(state.key());
+            }
+        })
+    });
+    c.bench_function("hash", |b| {
+        b.iter(|| {
+            let mut hasher = DefaultHasher::new();
+            for state in &positions {
+                state.hash(&mut hasher);
+            }
+            hasher.finish()
+        })
+    });
+}
+
+fn searching(c: &mut Criterion) {
+    let positions = positions();
+    let mut group = c.benchmark_group("search");
+    group.sample_size(10);
+    group.bench_function("depth 4", |b| {
+        b.iter(|| {
+            let mut rng = StdRng::seed_from_u64(0);
+            let mut evaluation = BoardEvaluation::default();
+            for state in &positions {
+                black_box(search(state, &mut evaluation, &mut rng, 4));
+            }
+        })
+    });
+    // the same search, with moves to the same state searched once
+    let state = equal_factories();
+    let options = |deduplicate| SearchOptions {
+        depth: 3,
+        deduplicate,
+        ..SearchOptions::default()
+    };
+    let nodes = |options: &SearchOptions| {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut evaluation = BoardEvaluation::default();
+        search_with_options(&state, &mut evaluation, &mut rng, options)
+            .1
+            .nodes
+    };
+    println!(
+        "depth 3 from equal factories searches {} nodes, {} deduplicated",
+        nodes(&options(false)),
+        nodes(&options(true))
+    );
+    for deduplicate in [false, true] {
+        let options = options(deduplicate);
+        let name = match deduplicate {
+            true => "equal factories deduplicated",
+            false => "equal factories",
+        };
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut rng = StdRng::seed_from_u64(0);
+                let mut evaluation = BoardEvaluation::default();
+                black_box(search_with_options(
+                    &state,
+                    &mut evaluation,
+                    &mut rng,
+                    &options,
+                ))
+            })
+        });
+    }
+    // searching through the end of the round samples deals at chance nodes
+    let state = round_end();
+    let options = SearchOptions {
+        depth: 3,
+        ..SearchOptions::default()
+    };
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut evaluation = BoardEvaluation::default();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let nodes = search_with_options(&state, &mut evaluation, &mut rng, &options)
+        .1
+        .nodes;
+    println!(
+        "depth 3 through the round end searches {} nodes with {} allocations",
+        nodes,
+        ALLOCATIONS.load(Ordering::Relaxed) - before
+    );
+    group.bench_function("round end", |b| {
+        b.iter(|| {
+            let mut rng = StdRng::seed_from_u64(0);
+            let mut evaluation = BoardEvaluation::default();
+            black_box(search_with_options(
+                &state,
+                &mut evaluation,
+                &mut rng,
+                &options,
+            ))
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, move_generation, round_scoring, hashing, searching);
+criterion_main!(benches);
@@ -0,0 +1,21 @@
+// Generates the C header of the ffi module when the ffi feature is enabled.
+// The header is written to OUT_DIR, and tests/ffi.rs checks that the one in
+// include/ is the same.
+fn main() {
+    #[cfg(feature = "ffi")]
+    {
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        let out_dir = std::env::var("OUT_DIR").unwrap();
+        let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir))
+            .expect("could not read cbindgen.toml");
+        // only the ffi module, so that nothing else in the crate is declared
+        cbindgen::Builder::new()
+            .with_config(config)
+            .with_src(format!("{}/src/ffi.rs", crate_dir))
+            .generate()
+            .expect("could not generate the C header")
+            .write_to_file(format!("{}/azul.h", out_dir));
+    }
+}
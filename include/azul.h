@@ -0,0 +1,134 @@
+#ifndef AZUL_H
+#define AZUL_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Result of calls that can fail.
+typedef enum AzulStatus {
+  AzulStatus_Ok,
+  // A null pointer or a move with out of range values
+  AzulStatus_InvalidArgument,
+  AzulStatus_GameOver,
+  AzulStatus_NoSuchFactory,
+  AzulStatus_NoSuchRow,
+  AzulStatus_NoSuchColumn,
+  AzulStatus_TileNotAvailable,
+  AzulStatus_RowNotAvailable,
+  // The position breaks the rules
+  AzulStatus_InvalidState,
+} AzulStatus;
+
+// How a game stands, see `azul_result`.
+typedef enum AzulResult {
+  AzulResult_Ongoing,
+  AzulResult_Won,
+  // The victory is shared
+  AzulResult_Draw,
+} AzulResult;
+
+// A game in progress.
+typedef struct AzulGame AzulGame;
+
+// A move. `origin` is a factory index or -1 for the center, `tile` is
+// 0 black, 1 white, 2 azul, 3 yellow or 4 red and `destination` is a
+// pattern row index or -1 for the floor line. `column` is the wall column
+// chosen when completing a row in the free variant, otherwise -1. Indices
+// start at 0.
+typedef struct AzulMove {
+  int32_t origin;
+  int32_t tile;
+  int32_t destination;
+  int32_t column;
+} AzulMove;
+
+// A new, dealt game. Deals are random from `seed`. Null unless there are
+// two to four players.
+struct AzulGame *azul_new(uintptr_t players, uint64_t seed);
+
+// A game from a position in notation, or null if the notation is invalid.
+//
+// # Safety
+// `notation` must be a valid, nul terminated string.
+struct AzulGame *azul_from_notation(const char *notation, uint64_t seed);
+
+// Releases a game. Null is ignored.
+//
+// # Safety
+// `game` must come from `azul_new` or `azul_from_notation` and not be
+// used afterwards.
+void azul_free(struct AzulGame *game);
+
+// Writes up to `capacity` legal moves to `moves` and returns the number of
+// legal moves, which may be more than `capacity`. Pass a null `moves` to
+// only count them.
+//
+// # Safety
+// `game` must be a valid game and `moves` must have room for `capacity`
+// moves.
+uintptr_t azul_legal_moves(const struct AzulGame *game, struct AzulMove *moves, uintptr_t capacity);
+
+// Makes a move, dealing a new round if needed.
+//
+// # Safety
+// `game` must be a valid game.
+enum AzulStatus azul_apply(struct AzulGame *game, struct AzulMove m);
+
+// Searches `depth` moves ahead and writes the engine's move to `best`,
+// without making it.
+//
+// # Safety
+// `game` must be a valid game and `best` point to a move.
+enum AzulStatus azul_best_move(struct AzulGame *game, uintptr_t depth, struct AzulMove *best);
+
+// The player to move.
+//
+// # Safety
+// `game` must be a valid game.
+uintptr_t azul_current_player(const struct AzulGame *game);
+
+// The winning player, or -1 while the game is in progress and after a
+// draw.
+//
+// # Safety
+// `game` must be a valid game.
+int32_t azul_winner(const struct AzulGame *game);
+
+// Whether the game goes on, was won or ended in a draw.
+//
+// # Safety
+// `game` must be a valid game.
+enum AzulResult azul_result(const struct AzulGame *game);
+
+// Writes up to `capacity` of the players sharing the victory to `winners`
+// and returns their number, which may be more than `capacity` and is 0
+// while the game is in progress. Pass a null `winners` to only count them.
+//
+// # Safety
+// `game` must be a valid game and `winners` must have room for `capacity`
+// players.
+uintptr_t azul_winners(const struct AzulGame *game, uintptr_t *winners, uintptr_t capacity);
+
+// Points of a player so far.
+//
+// # Safety
+// `game` must be a valid game.
+uintptr_t azul_points(const struct AzulGame *game, uintptr_t player);
+
+// The position in notation. Release it with `azul_free_string`.
+//
+// # Safety
+// `game` must be a valid game.
+char *azul_notation(const struct AzulGame *game);
+
+// Releases a string returned by the library. Null is ignored.
+//
+// # Safety
+// `string` must come from the library and not be used afterwards.
+void azul_free_string(char *string);
+
+#endif  /* AZUL_H */
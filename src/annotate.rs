@@ -0,0 +1,79 @@
+//! Blunder checks of recorded games. Every position is searched again, and
+//! moves that are much worse than the engine's choice by its evaluation
+//! are marked `?!` or `??` in the record, with the engine's move.
+
+use crate::{
+    board_evaluation::BoardEvaluation,
+    minmax::{search_move, search_with_options, SearchOptions},
+    record::{Annotation, GameRecord, Mark, RecordError},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// How much worse than the engine's move a move must be to get a mark, in
+/// hundredths of a point.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Thresholds {
+    pub dubious: i32,
+    pub blunder: i32,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            dubious: 200,
+            blunder: 500,
+        }
+    }
+}
+
+/// The record with every move checked by a search `depth` moves ahead,
+/// replacing any earlier annotations.
+pub fn annotate(
+    record: &GameRecord,
+    depth: usize,
+    thresholds: &Thresholds,
+) -> Result<GameRecord, RecordError> {
+    let states = record.replay()?;
+    let mut rng = StdRng::seed_from_u64(record.seed.unwrap_or(0));
+    let mut evaluation = BoardEvaluation::default();
+    let options = SearchOptions::depth(depth);
+    let mut annotated = record.clone();
+    annotated.annotations.clear();
+    for (index, (m, before)) in record.moves.iter().zip(&states).enumerate() {
+        // the record replayed, so the move is valid
+        let played = m.parse().unwrap();
+        // both searches sample the same deals, so that they compare
+        let seed = rng.gen();
+        let (_, best) = search_with_options(
+            before,
+            &mut evaluation,
+            &mut StdRng::seed_from_u64(seed),
+            &options,
+        );
+        let Some(&better) = best.pv.first().filter(|better| **better != played) else {
+            continue;
+        };
+        let info = search_move(
+            before,
+            &mut evaluation,
+            &mut StdRng::seed_from_u64(seed),
+            &options,
+            played,
+        );
+        let loss = best.score.saturating_sub(info.score);
+        let mark = if loss >= thresholds.blunder {
+            Mark::Blunder
+        } else if loss >= thresholds.dubious {
+            Mark::Dubious
+        } else {
+            continue;
+        };
+        annotated.annotations.push(Annotation {
+            index,
+            mark,
+            better: better.to_string(),
+            loss,
+        });
+    }
+    Ok(annotated)
+}
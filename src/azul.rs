@@ -1,21 +1,27 @@
-use crate::minmax::{Evaluation, GameState};
-use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
-use std::{
-    collections::HashMap,
-    hash::Hash,
-    iter, mem,
-    ops::{Index, IndexMut},
+//! The rules of Azul: tiles, factories, player boards and scoring.
+
+use crate::{
+    minmax::{DeterministicGameState, Evaluation, GameResult, StochasticGameState},
+    zobrist,
 };
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{cell::Cell, fmt, hash::Hash, iter, mem};
 
+/// The five tile colors.
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-enum Tile {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Tile {
     BLACK,
     WHITE,
     AZUL,
     YELLOW,
     RED,
 }
-const TILES: [Tile; 5] = [
+pub(crate) const TILES: [Tile; 5] = [
     Tile::BLACK,
     Tile::WHITE,
     Tile::AZUL,
@@ -23,6 +29,23 @@ const TILES: [Tile; 5] = [
     Tile::RED,
 ];
 
+impl Tile {
+    /// Single letter name, as used in notation.
+    pub fn letter(self) -> char {
+        match self {
+            Tile::BLACK => 'B',
+            Tile::WHITE => 'W',
+            Tile::AZUL => 'A',
+            Tile::YELLOW => 'Y',
+            Tile::RED => 'R',
+        }
+    }
+    /// Inverse of `letter`.
+    pub fn from_letter(letter: char) -> Option<Tile> {
+        TILES.into_iter().find(|tile| tile.letter() == letter)
+    }
+}
+
 impl TryFrom<usize> for Tile {
     type Error = ();
 
@@ -38,8 +61,18 @@ impl TryFrom<usize> for Tile {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-struct TileSet {
+// Tile counts packed in a u64, one byte per color with black in the lowest
+// byte. A nibble can't count the 20 tiles of a color, and with bytes whole
+// sets are added with a single addition.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "Counts", into = "Counts"))]
+pub(crate) struct TileSet(u64);
+
+// the serialized form of a tile set
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct Counts {
     black: usize,
     white: usize,
     azul: usize,
@@ -47,42 +80,59 @@ struct TileSet {
     red: usize,
 }
 
-impl Hash for TileSet {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.black.hash(state);
-        self.white.hash(state);
-        self.azul.hash(state);
-        self.yellow.hash(state);
-        self.red.hash(state);
+#[cfg(feature = "serde")]
+impl From<TileSet> for Counts {
+    fn from(tiles: TileSet) -> Self {
+        Counts {
+            black: tiles.get(Tile::BLACK),
+            white: tiles.get(Tile::WHITE),
+            azul: tiles.get(Tile::AZUL),
+            yellow: tiles.get(Tile::YELLOW),
+            red: tiles.get(Tile::RED),
+        }
     }
 }
 
-impl Index<Tile> for TileSet {
-    type Output = usize;
+#[cfg(feature = "serde")]
+impl TryFrom<Counts> for TileSet {
+    type Error = String;
 
-    fn index(&self, tile: Tile) -> &Self::Output {
-        match tile {
-            Tile::BLACK => &self.black,
-            Tile::WHITE => &self.white,
-            Tile::AZUL => &self.azul,
-            Tile::YELLOW => &self.yellow,
-            Tile::RED => &self.red,
+    fn try_from(counts: Counts) -> Result<Self, Self::Error> {
+        let counts = [
+            counts.black,
+            counts.white,
+            counts.azul,
+            counts.yellow,
+            counts.red,
+        ];
+        let mut tiles = TileSet::new();
+        for (tile, count) in TILES.into_iter().zip(counts) {
+            if count > 20 {
+                return Err(format!("more than 20 {} tiles", tile));
+            }
+            tiles.add(tile, count);
         }
+        Ok(tiles)
     }
 }
 
-impl IndexMut<Tile> for TileSet {
-    fn index_mut(&mut self, tile: Tile) -> &mut Self::Output {
-        match tile {
-            Tile::BLACK => &mut self.black,
-            Tile::WHITE => &mut self.white,
-            Tile::AZUL => &mut self.azul,
-            Tile::YELLOW => &mut self.yellow,
-            Tile::RED => &mut self.red,
-        }
+impl fmt::Debug for TileSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map()
+            .entries(TILES.iter().map(|tile| (tile, self.get(*tile))))
+            .finish()
     }
 }
 
+// there are no tiles left to draw
+#[derive(Debug)]
+struct EmptyBag;
+
+// n choose k
+fn binomial(n: usize, k: usize) -> f64 {
+    (0..k).map(|i| (n - i) as f64 / (i + 1) as f64).product()
+}
+
 impl FromIterator<Tile> for TileSet {
     fn from_iter<T: IntoIterator<Item = Tile>>(iter: T) -> Self {
         let mut tileset = TileSet::new();
@@ -93,60 +143,179 @@ impl FromIterator<Tile> for TileSet {
     }
 }
 
+// bit offset of the count of a color
+fn shift(tile: Tile) -> u32 {
+    8 * tile as u32
+}
+
 impl TileSet {
-    fn new() -> Self {
-        Self {
-            black: 0,
-            white: 0,
-            azul: 0,
-            yellow: 0,
-            red: 0,
-        }
+    pub(crate) fn new() -> Self {
+        Self(0)
+    }
+    /// Number of tiles of the color.
+    pub(crate) fn get(&self, tile: Tile) -> usize {
+        (self.0 >> shift(tile)) as usize & 0xff
+    }
+    pub(crate) fn add(&mut self, tile: Tile, count: usize) {
+        self.0 += (count as u64) << shift(tile);
+    }
+    pub(crate) fn remove(&mut self, tile: Tile, count: usize) {
+        debug_assert!(count <= self.get(tile), "not enough {} tiles", tile);
+        self.0 -= (count as u64) << shift(tile);
     }
     fn drain(&mut self, tile: Tile) -> usize {
-        let count = self[tile];
-        self[tile] = 0;
+        let count = self.get(tile);
+        self.0 &= !(0xff << shift(tile));
         count
     }
 
-    fn draw_one<R: Rng>(&mut self, rng: &mut R) -> Tile {
-        let weights = [self.black, self.white, self.azul, self.yellow, self.red];
-        let distribution = WeightedIndex::new(&weights).unwrap();
-        let tile: Tile = distribution.sample(rng).try_into().unwrap();
-        self[tile] = self[tile].saturating_sub(1);
-        tile
+    fn draw_one<R: Rng>(&mut self, rng: &mut R) -> Result<Tile, EmptyBag> {
+        let weights = TILES.map(|tile| self.get(tile));
+        let distribution = WeightedIndex::new(weights).or(Err(EmptyBag))?;
+        let tile = TILES[distribution.sample(rng)];
+        self.remove(tile, 1);
+        Ok(tile)
     }
+    // up to count tiles, fewer if the bag runs out
     fn draw<R: Rng>(&mut self, rng: &mut R, count: usize) -> TileSet {
         let mut tileset = TileSet::new();
         for _ in 0..count {
-            let tile = self.draw_one(rng);
-            tileset.push(tile);
+            match self.draw_one(rng) {
+                Ok(tile) => tileset.push(tile),
+                Err(EmptyBag) => break,
+            }
         }
         tileset
     }
 
-    fn push(&mut self, tile: Tile) {
-        self[tile] += 1;
+    // every way to draw `count` tiles, with its probability
+    fn draws(&self, count: usize) -> Vec<(f64, TileSet)> {
+        let count = count.min(self.len());
+        let total = binomial(self.len(), count);
+        let mut draws = vec![TileSet::new()];
+        for tile in TILES {
+            draws = draws
+                .into_iter()
+                .flat_map(|drawn| {
+                    let left = count - drawn.len();
+                    (0..=self.get(tile).min(left)).map(move |n| {
+                        let mut drawn = drawn;
+                        drawn.add(tile, n);
+                        drawn
+                    })
+                })
+                .collect();
+        }
+        draws
+            .into_iter()
+            .filter(|drawn| drawn.len() == count)
+            .map(|drawn| {
+                let ways: f64 = TILES
+                    .iter()
+                    .map(|tile| binomial(self.get(*tile), drawn.get(*tile)))
+                    .product();
+                (ways / total, drawn)
+            })
+            .collect()
     }
 
-    fn extend(&mut self, tileset: TileSet) {
-        self.black += tileset.black;
-        self.white += tileset.white;
-        self.azul += tileset.azul;
-        self.yellow += tileset.yellow;
-        self.red += tileset.red;
+    pub(crate) fn push(&mut self, tile: Tile) {
+        self.add(tile, 1);
     }
 
-    fn len(&self) -> usize {
-        self.black + self.white + self.azul + self.yellow + self.red
+    pub(crate) fn extend(&mut self, tileset: TileSet) {
+        // there are at most 20 tiles of a color, so bytes never overflow
+        self.0 += tileset.0;
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        // sums the five bytes into the fifth
+        (self.0.wrapping_mul(0x01_0101_0101) >> 32) as usize & 0xff
+    }
+
+    fn zobrist(&self, location: u64) -> u64 {
+        TILES
+            .iter()
+            .filter(|tile| self.get(**tile) > 0)
+            .map(|tile| zobrist::key(location, *tile as usize, self.get(*tile)))
+            .fold(0, |hash, key| hash ^ key)
     }
 }
 
-#[derive(Clone, PartialEq, Eq)]
-struct Wall {
+// The wall as bitmasks, where bit 5 * row + column is set when the cell
+// holds a tile, in total and per color.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "Cells", into = "Cells"))]
+pub(crate) struct Wall {
+    cells: u32,
+    colors: [u32; 5],
+}
+
+// the serialized form of a wall. The colors are only written for walls
+// not following the colored pattern, as in the free variant.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct Cells {
     rows: [[bool; 5]; 5],
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    colors: Option<[[Option<Tile>; 5]; 5]>,
 }
-const WALL: [[Tile; 5]; 5] = [
+
+#[cfg(feature = "serde")]
+impl From<Wall> for Cells {
+    fn from(wall: Wall) -> Self {
+        Cells {
+            rows: wall.to_rows(),
+            colors: (!wall.is_patterned()).then(|| wall.to_colors()),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Cells> for Wall {
+    fn from(cells: Cells) -> Self {
+        match cells.colors {
+            Some(colors) => colors.into(),
+            None => cells.rows.into(),
+        }
+    }
+}
+
+// tiles on the cells of the colored pattern
+impl From<[[bool; 5]; 5]> for Wall {
+    fn from(rows: [[bool; 5]; 5]) -> Self {
+        let mut wall = Wall::new();
+        for (row_index, row) in rows.iter().enumerate() {
+            for (colum_index, cell) in row.iter().enumerate() {
+                if *cell {
+                    wall.set(row_index, colum_index, WALL[row_index][colum_index]);
+                }
+            }
+        }
+        wall
+    }
+}
+
+impl From<[[Option<Tile>; 5]; 5]> for Wall {
+    fn from(rows: [[Option<Tile>; 5]; 5]) -> Self {
+        let mut wall = Wall::new();
+        for (row_index, row) in rows.iter().enumerate() {
+            for (colum_index, cell) in row.iter().enumerate() {
+                if let Some(tile) = cell {
+                    wall.set(row_index, colum_index, *tile);
+                }
+            }
+        }
+        wall
+    }
+}
+
+// the cells of a row and of the first column
+const ROW: u32 = 0b11111;
+const COLUMN: u32 = 0b00001_00001_00001_00001_00001;
+
+pub(crate) const WALL: [[Tile; 5]; 5] = [
     [
         Tile::AZUL,
         Tile::YELLOW,
@@ -183,236 +352,1140 @@ const WALL: [[Tile; 5]; 5] = [
         Tile::AZUL,
     ],
 ];
+// the column of the tile on the row. Each row is the one above shifted one
+// step to the right, see WALL.
+fn column_of(row_index: usize, tile: Tile) -> usize {
+    (tile as usize + 3 + row_index) % 5
+}
 impl Wall {
     fn new() -> Self {
         Wall {
-            rows: Default::default(),
+            cells: 0,
+            colors: [0; 5],
         }
     }
-    fn len(&self) -> usize {
-        self.rows.as_flattened().iter().filter(|p| **p).count()
+    /// Whether the cell holds a tile.
+    pub(crate) fn has(&self, row_index: usize, colum_index: usize) -> bool {
+        self.cells & 1 << (5 * row_index + colum_index) != 0
     }
-    fn has_tile(&self, row_index: usize, tile: &Tile) -> bool {
-        let colum_index = WALL[row_index]
-            .iter()
-            .position(|cell| cell == tile)
-            .unwrap();
-        self.rows[row_index][colum_index]
+    /// The tile on the cell, if any.
+    pub(crate) fn tile(&self, row_index: usize, colum_index: usize) -> Option<Tile> {
+        let cell = 1 << (5 * row_index + colum_index);
+        TILES
+            .into_iter()
+            .find(|tile| self.colors[*tile as usize] & cell != 0)
     }
-    fn count(&self, (y, x): (usize, usize), dx: i32, dy: i32) -> usize {
-        let mut points: usize = 0;
-        let (mut y, mut x) = (y, x);
-        while (0..5).contains(&y) && (0..5).contains(&x) && self.rows[y][x] {
-            points += 1;
-            x = (x as i32 + dx) as usize;
-            y = (y as i32 + dy) as usize;
+    fn set(&mut self, row_index: usize, colum_index: usize, tile: Tile) {
+        let cell = 1 << (5 * row_index + colum_index);
+        self.cells |= cell;
+        self.colors[tile as usize] |= cell;
+    }
+    // whether every tile is on its cell of the colored pattern
+    #[cfg(feature = "serde")]
+    fn is_patterned(&self) -> bool {
+        TILES.into_iter().all(|tile| {
+            (0..5).all(|row_index| {
+                self.colors[tile as usize] & ROW << (5 * row_index)
+                    == self.colors[tile as usize]
+                        & 1 << (5 * row_index + column_of(row_index, tile))
+            })
+        })
+    }
+    #[cfg(feature = "serde")]
+    fn to_colors(self) -> [[Option<Tile>; 5]; 5] {
+        let mut rows = [[None; 5]; 5];
+        for (row_index, row) in rows.iter_mut().enumerate() {
+            for (colum_index, cell) in row.iter_mut().enumerate() {
+                *cell = self.tile(row_index, colum_index);
+            }
         }
-        points
+        rows
     }
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    fn to_rows(self) -> [[bool; 5]; 5] {
+        let mut rows = [[false; 5]; 5];
+        for (row_index, row) in rows.iter_mut().enumerate() {
+            for (colum_index, cell) in row.iter_mut().enumerate() {
+                *cell = self.has(row_index, colum_index);
+            }
+        }
+        rows
+    }
+    pub(crate) fn len_row(&self, row_index: usize) -> usize {
+        (self.cells & ROW << (5 * row_index)).count_ones() as usize
+    }
+    pub(crate) fn len_column(&self, colum_index: usize) -> usize {
+        (self.cells & COLUMN << colum_index).count_ones() as usize
+    }
+    pub(crate) fn len_color(&self, tile: &Tile) -> usize {
+        self.colors[*tile as usize].count_ones() as usize
+    }
+    // points the tile would score on the free cell, without placing it
+    fn points_for(&self, row_index: usize, colum_index: usize, tile: Tile) -> usize {
+        let mut wall = *self;
+        wall.set(row_index, colum_index, tile);
+        wall.points_at(colum_index, row_index)
+    }
+    fn has_tile(&self, row_index: usize, tile: &Tile) -> bool {
+        self.colors[*tile as usize] & ROW << (5 * row_index) != 0
+    }
+    fn column_has_tile(&self, colum_index: usize, tile: Tile) -> bool {
+        self.colors[tile as usize] & COLUMN << colum_index != 0
+    }
+    // points of the tile on the cell: the tiles in line with it across and
+    // down, found by growing a mask of the cell through neighbouring tiles
+    // a step at a time rather than walking the wall cell by cell
     fn points_at(&self, colum_index: usize, row_index: usize) -> usize {
-        let mut points: usize = 0;
-        let position = (row_index, colum_index);
-        points += self.count(position, 1, 0); // right
-        points += self.count(position, -1, 0); // left
-        points += self.count(position, 0, -1); // up
-        points += self.count(position, 0, 1); // down
-        points -= 3; // center is counted thrice
-        points
-    }
-    fn bonus_points_at(&self, colum_index: usize, row_index: usize) -> usize {
-        [
-            (0..5)
-                .all(|column| self.rows[row_index][column])
-                .then_some(2),
-            (0..5).all(|row| self.rows[row][colum_index]).then_some(7),
-            // TODO: Bonus points if all tiles placed.
-        ]
-        .iter()
-        .flatten()
-        .sum()
+        let cell = 1 << (5 * row_index + colum_index);
+        let (mut across, mut down) = (cell, cell);
+        for _ in 0..4 {
+            // the first and last columns don't continue on the next row
+            let left = (across & !COLUMN) >> 1;
+            let right = (across & !(COLUMN << 4)) << 1;
+            across |= (left | right) & self.cells;
+            down |= (down >> 5 | down << 5) & self.cells;
+        }
+        // the tile itself is in both lines
+        (across.count_ones() + down.count_ones()) as usize - 1
+    }
+    fn is_row_complete(&self, row_index: usize) -> bool {
+        self.len_row(row_index) == 5
     }
-    fn add_tile(&mut self, row_index: usize, tile: Tile) -> usize {
-        let colum_index = WALL[row_index]
+    fn is_column_complete(&self, colum_index: usize) -> bool {
+        self.len_column(colum_index) == 5
+    }
+    fn is_color_complete(&self, tile: &Tile) -> bool {
+        self.len_color(tile) == 5
+    }
+    // end of game bonus: 2 per complete row, 7 per complete column and 10
+    // per color with all five tiles on the wall
+    fn final_bonus(&self) -> usize {
+        let rows = (0..5).filter(|row| self.is_row_complete(*row)).count();
+        let columns = (0..5)
+            .filter(|column| self.is_column_complete(*column))
+            .count();
+        let colors = TILES
             .iter()
-            .position(|cell| cell == &tile)
-            .unwrap();
-        assert!(
-            !self.rows[row_index][colum_index],
-            "Tile was already assigned!"
-        );
-        self.rows[row_index][colum_index] = true;
-        self.points_at(colum_index, row_index) + self.bonus_points_at(colum_index, row_index)
+            .filter(|tile| self.is_color_complete(tile))
+            .count();
+        2 * rows + 7 * columns + 10 * colors
+    }
+    fn add_tile(
+        &mut self,
+        row_index: usize,
+        colum_index: usize,
+        tile: Tile,
+    ) -> Result<usize, AzulError> {
+        if self.has(row_index, colum_index) {
+            return Err(AzulError::CellTaken {
+                row: row_index,
+                column: colum_index,
+            });
+        }
+        self.set(row_index, colum_index, tile);
+        Ok(self.points_at(colum_index, row_index))
     }
 }
-impl Hash for Wall {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.rows.hash(state);
+
+/// A change of the points of a player at the end of a round or game, to
+/// explain scoring.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ScoreEvent {
+    /// The tile of a complete pattern row placed on the wall, scoring a
+    /// point for itself and each tile in line with it without a gap
+    Placed {
+        row: usize,
+        column: usize,
+        tile: Tile,
+        points: usize,
+    },
+    /// The tiles of a complete pattern row without a place on the wall went
+    /// to the floor line
+    Discarded { row: usize, tile: Tile },
+    /// The floor line penalty, as far as there were points to lose
+    Floor { penalty: usize },
+    /// End of game bonus for a complete horizontal line of the wall
+    RowBonus { row: usize },
+    /// End of game bonus for a complete vertical line of the wall
+    ColumnBonus { column: usize },
+    /// End of game bonus for all five tiles of a color on the wall
+    ColorBonus { tile: Tile },
+}
+
+impl ScoreEvent {
+    /// Points gained, or lost for the floor line.
+    pub fn points(&self) -> i32 {
+        match self {
+            ScoreEvent::Placed { points, .. } => *points as i32,
+            ScoreEvent::Discarded { .. } => 0,
+            ScoreEvent::Floor { penalty } => -(*penalty as i32),
+            ScoreEvent::RowBonus { .. } => 2,
+            ScoreEvent::ColumnBonus { .. } => 7,
+            ScoreEvent::ColorBonus { .. } => 10,
+        }
+    }
+}
+
+/// Which side of the player boards is played.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum WallVariant {
+    /// The colored side, where every color has its own cell on each row
+    #[default]
+    Colored,
+    /// The gray side, where the move completing a pattern row chooses the
+    /// column, with at most one tile of each color per row and column
+    Free,
+}
+
+impl WallVariant {
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    pub(crate) fn is_colored(&self) -> bool {
+        *self == WallVariant::Colored
     }
 }
 
-fn discard_points(count: usize) -> usize {
-    const SLOTS: [usize; 5] = [1, 1, 2, 2, 2];
-    (0..count)
-        .map(|i| if i < SLOTS.len() { SLOTS[i] } else { 3 })
-        .sum()
+// Where the tiles of complete pattern rows go on the wall. Columns are
+// passed around as five bit masks.
+pub(crate) trait WallRules {
+    // whether the move completing a pattern row chooses the column
+    fn chooses_column(&self) -> bool;
+    // the columns the tile may go to on the row
+    fn columns(&self, player: &Player, row_index: usize, tile: Tile) -> u32;
+    // the column the complete row is tiled to, or None if its tiles go to
+    // the floor line
+    fn column(&self, player: &Player, row_index: usize, tile: Tile) -> Option<usize>;
+}
+
+impl WallRules for WallVariant {
+    fn chooses_column(&self) -> bool {
+        *self == WallVariant::Free
+    }
+    fn columns(&self, player: &Player, row_index: usize, tile: Tile) -> u32 {
+        if player.wall.has_tile(row_index, &tile) {
+            return 0;
+        }
+        match self {
+            WallVariant::Colored => 1 << column_of(row_index, tile),
+            WallVariant::Free => {
+                // columns already holding the color, or promised to it by
+                // another complete pattern row
+                let taken = |colum_index: usize| {
+                    player.wall.has(row_index, colum_index)
+                        || player.wall.column_has_tile(colum_index, tile)
+                        || (0..5).any(|other| {
+                            player.columns[other] == Some(colum_index)
+                                && player.rows[other].is_some_and(|(t, _)| t == tile)
+                        })
+                };
+                (0..5)
+                    .filter(|colum_index| !taken(*colum_index))
+                    .fold(0, |columns, colum_index| columns | 1 << colum_index)
+            }
+        }
+    }
+    fn column(&self, player: &Player, row_index: usize, tile: Tile) -> Option<usize> {
+        match self {
+            WallVariant::Colored => Some(column_of(row_index, tile)),
+            WallVariant::Free => player.columns[row_index],
+        }
+    }
+}
+
+// the columns set in a mask
+fn columns_in(columns: u32) -> impl Iterator<Item = usize> {
+    (0..5).filter(move |colum_index| columns & 1 << colum_index != 0)
+}
+
+// penalty for each of the seven floor line slots
+const FLOOR: [usize; 7] = [1, 1, 2, 2, 2, 3, 3];
+
+// The floor line. Holds at most seven tiles, any further tiles go
+// straight to the lid without adding to the penalty.
+#[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct Floor {
+    pub(crate) tiles: TileSet,
+}
+impl Floor {
+    fn new() -> Self {
+        Self {
+            tiles: TileSet::new(),
+        }
+    }
+    pub(crate) fn len(&self) -> usize {
+        self.tiles.len()
+    }
+    fn push(&mut self, tile: Tile, count: usize, lid: &mut TileSet) {
+        let free = FLOOR.len() - self.len();
+        let overflow = count.saturating_sub(free);
+        self.tiles.add(tile, count - overflow);
+        lid.add(tile, overflow);
+    }
+    pub(crate) fn penalty(&self) -> usize {
+        FLOOR[..self.len()].iter().sum()
+    }
+    // empty the floor line into the lid
+    fn clear(&mut self, lid: &mut TileSet) {
+        let mut tmp = TileSet::new();
+        mem::swap(&mut tmp, &mut self.tiles);
+        lid.extend(tmp);
+    }
 }
 
+/// A player board: pattern rows, wall, floor line and score.
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Player {
-    rows: [Option<(Tile, usize)>; 5],
+    pub(crate) rows: [Option<(Tile, usize)>; 5],
+    // wall columns chosen for complete pattern rows, in the free variant
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "no_columns"))]
+    pub(crate) columns: [Option<usize>; 5],
+    /// Points scored so far
     pub points: usize,
-    wall: Wall,
-    discard: TileSet,
+    pub(crate) wall: Wall,
+    pub(crate) floor: Floor,
+    // wall points of the complete pattern rows, kept up to date as rows
+    // are completed
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pending: usize,
 }
+#[cfg(feature = "serde")]
+fn no_columns(columns: &[Option<usize>; 5]) -> bool {
+    columns.iter().all(Option::is_none)
+}
+
 impl Player {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             rows: Default::default(),
+            columns: Default::default(),
             points: 0,
             wall: Wall::new(),
-            discard: TileSet::new(),
+            floor: Floor::new(),
+            pending: 0,
         }
     }
 
-    fn maybe_place(&mut self, tile: Tile, count: usize, row_index: usize) -> bool {
+    /// Rows of the wall with all five tiles, which break ties at the end
+    /// of the game.
+    pub fn complete_rows(&self) -> usize {
+        (0..5).filter(|row| self.wall.is_row_complete(*row)).count()
+    }
+
+    /// Wall points of the complete pattern rows, that will be scored at the
+    /// end of the round. The floor line penalty is not included.
+    pub fn pending_points(&self) -> usize {
+        self.pending
+    }
+
+    // scores the complete pattern rows on a copy of the wall, top row
+    // first like at the end of the round
+    fn score_pending(&self, rules: &impl WallRules) -> Result<usize, AzulError> {
+        let mut wall = self.wall;
+        let mut points = 0;
+        for (row_index, row) in self.rows.iter().enumerate() {
+            if let Some((tile, count)) = *row {
+                if count == row_index + 1 {
+                    if let Some(colum_index) = rules.column(self, row_index, tile) {
+                        points += wall.add_tile(row_index, colum_index, tile)?;
+                    }
+                }
+            }
+        }
+        Ok(points)
+    }
+
+    // can the tile be placed on the given pattern row?
+    fn can_place(&self, tile: Tile, row_index: usize) -> bool {
         if self.wall.has_tile(row_index, &tile) {
             return false;
         }
-        let row_size = row_index + 1;
-        if let Some((current_tile, ref mut current_count)) = self.rows[row_index] {
-            if current_tile != tile {
-                // another tile is used - we can't place here at all
-                return false;
-            }
-            // some spaces used. return how many are left
-            let space_left = row_size - *current_count;
-            // we got space left - add what we can, discard rest
-            let discard_count = count.saturating_sub(space_left);
-            *current_count += count - discard_count;
-            self.discard[tile] += discard_count;
-        } else {
-            // unoccupied row - we can use the entire row
-            let space_left = row_size;
-            // we got space left - add what we can, discard rest
-            let discard_count = count.saturating_sub(space_left);
-            self.rows[row_index] = Some((tile, count - discard_count));
-            self.discard[tile] += discard_count;
+        match self.rows[row_index] {
+            // another tile is used - we can't place here at all
+            Some((current_tile, count)) => current_tile == tile && count < row_index + 1,
+            None => true,
         }
+    }
+
+    // would the tiles fill the pattern row?
+    fn completes(&self, count: usize, row_index: usize) -> bool {
+        let current_count = self.rows[row_index].map_or(0, |(_, count)| count);
+        current_count + count > row_index
+    }
+
+    // tiles falling to the floor line when placing count tiles on the row
+    fn overflow(&self, count: usize, row_index: usize) -> usize {
+        let current_count = self.rows[row_index].map_or(0, |(_, count)| count);
+        (current_count + count).saturating_sub(row_index + 1)
+    }
+
+    fn maybe_place(
+        &mut self,
+        tile: Tile,
+        count: usize,
+        row_index: usize,
+        column: Option<usize>,
+        lid: &mut TileSet,
+    ) -> bool {
+        if !self.can_place(tile, row_index) {
+            return false;
+        }
+        let row_size = row_index + 1;
+        let current_count = self.rows[row_index].map_or(0, |(_, count)| count);
+        // add what we can, discard rest
+        let space_left = row_size - current_count;
+        let discard_count = count.saturating_sub(space_left);
+        self.rows[row_index] = Some((tile, current_count + count - discard_count));
+        self.columns[row_index] = column;
+        self.floor.push(tile, discard_count, lid);
         true
     }
 
-    fn prepare_next_round(&mut self, tray: &mut TileSet) {
+    // wall points of the tile on the pattern row, on the best column it may
+    // still go to
+    pub(crate) fn points_for(&self, rules: &impl WallRules, row_index: usize, tile: Tile) -> usize {
+        let columns = match self.columns[row_index] {
+            Some(colum_index) => 1 << colum_index,
+            None => rules.columns(self, row_index, tile),
+        };
+        columns_in(columns)
+            .map(|colum_index| self.wall.points_for(row_index, colum_index, tile))
+            .max()
+            .unwrap_or(0)
+    }
+
+    // scores the round, telling event about every change of points
+    fn prepare_next_round(
+        &mut self,
+        rules: &impl WallRules,
+        lid: &mut TileSet,
+        event: &mut impl FnMut(ScoreEvent),
+    ) -> Result<(), AzulError> {
         // start by going through rows and award points for filled rows
-        for (row_index, row) in self.rows.iter_mut().enumerate() {
+        for row_index in 0..5 {
             let row_size = row_index + 1;
-            if let Some((tile, count)) = row.clone() {
+            if let Some((tile, count)) = self.rows[row_index] {
                 if count == row_size {
-                    let points = self.wall.add_tile(row_index, tile); // add one tile to wall
-                    self.points += points;
-                    tray[tile] += count - 1; // add rest back to tray
-                    *row = None; // clear row
+                    match rules.column(self, row_index, tile) {
+                        Some(colum_index) => {
+                            // add one tile to wall
+                            let points = self.wall.add_tile(row_index, colum_index, tile)?;
+                            self.points += points;
+                            lid.add(tile, count - 1); // add rest to the lid
+                            event(ScoreEvent::Placed {
+                                row: row_index,
+                                column: colum_index,
+                                tile,
+                                points,
+                            });
+                        }
+                        // there is no place for the tile, discard the row
+                        None => {
+                            self.floor.push(tile, count, lid);
+                            event(ScoreEvent::Discarded {
+                                row: row_index,
+                                tile,
+                            });
+                        }
+                    }
+                    self.rows[row_index] = None; // clear row
+                    self.columns[row_index] = None;
                 }
             }
         }
-        // subtract tiles in discard
-        self.points = self
-            .points
-            .saturating_sub(discard_points(self.discard.len()));
-        // move discard into tray
-        let mut tmp = TileSet::new();
-        mem::swap(&mut tmp, &mut self.discard);
-        tray.extend(tmp);
+        // subtract floor line penalty
+        let penalty = self.floor.penalty().min(self.points);
+        if penalty > 0 {
+            self.points -= penalty;
+            event(ScoreEvent::Floor { penalty });
+        }
+        // move floor line into lid
+        self.floor.clear(lid);
+        self.pending = 0;
+        Ok(())
     }
 
-    fn tile_count(&self) -> usize {
-        [
-            self.rows
-                .iter()
-                .flat_map(|r| r)
-                .map(|(_, count)| count)
-                .sum(),
-            self.wall.len(),
-            self.discard.len(),
-        ]
-        .iter()
-        .sum()
+    fn row_zobrist(&self, index: usize, row_index: usize) -> u64 {
+        // the chosen column, if any, is hashed with the count
+        let column = self.columns[row_index].map_or(0, |colum_index| colum_index + 1);
+        self.rows[row_index].map_or(0, |(tile, count)| {
+            zobrist::key(
+                zobrist::ROW + 5 * index as u64 + row_index as u64,
+                tile as usize,
+                count + 8 * column,
+            )
+        })
+    }
+    fn floor_zobrist(&self, index: usize) -> u64 {
+        self.floor.tiles.zobrist(zobrist::FLOOR + index as u64)
+    }
+    // hash of the player board, where index is the player number
+    fn zobrist(&self, index: usize) -> u64 {
+        let mut hash = self.floor_zobrist(index);
+        hash ^= zobrist::key(zobrist::POINTS + index as u64, self.points, 0);
+        for row_index in 0..5 {
+            hash ^= self.row_zobrist(index, row_index);
+            for column_index in 0..5 {
+                if let Some(tile) = self.wall.tile(row_index, column_index) {
+                    let cell = 5 * row_index + column_index;
+                    hash ^= zobrist::key(zobrist::WALL + index as u64, cell, tile as usize);
+                }
+            }
+        }
+        hash
+    }
+
+    // add end of game bonus points, telling event about each
+    fn final_score(&mut self, event: &mut impl FnMut(ScoreEvent)) {
+        for row in (0..5).filter(|row| self.wall.is_row_complete(*row)) {
+            event(ScoreEvent::RowBonus { row });
+        }
+        for column in (0..5).filter(|column| self.wall.is_column_complete(*column)) {
+            event(ScoreEvent::ColumnBonus { column });
+        }
+        for tile in TILES
+            .into_iter()
+            .filter(|tile| self.wall.is_color_complete(tile))
+        {
+            event(ScoreEvent::ColorBonus { tile });
+        }
+        self.points += self.wall.final_bonus();
+    }
+
+    // every tile on the board
+    fn tiles(&self) -> TileSet {
+        let mut tiles = self.floor.tiles;
+        for (tile, count) in self.rows.iter().flatten() {
+            tiles.add(*tile, *count);
+        }
+        for tile in TILES {
+            tiles.add(tile, self.wall.len_color(&tile));
+        }
+        tiles
     }
 }
 
-#[derive(Clone, PartialEq, Eq)]
+/// Where the tiles of a move are taken from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Origin {
+    Factory(usize),
+    Center,
+}
+
+/// Where the tiles of a move are placed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Destination {
+    /// Pattern row, zero indexed from the top (one tile) row
+    Row(usize),
+    /// Pattern row and the wall column its tile goes to, for moves
+    /// completing a row in the free variant
+    Wall(usize, usize),
+    Floor,
+}
+
+/// A single action: take all tiles of one color from an origin and put
+/// them on a destination.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Move {
+    pub origin: Origin,
+    pub tile: Tile,
+    pub destination: Destination,
+}
+
+/// Reasons a move can't be applied to a state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveError {
+    GameOver,
+    NoSuchFactory,
+    NoSuchRow,
+    NoSuchColumn,
+    TileNotAvailable,
+    RowNotAvailable,
+    /// A move description of another player than the one to move
+    NotYourTurn,
+    /// A move description with another count than the tiles taken
+    WrongCount,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            MoveError::GameOver => "the game is over",
+            MoveError::NoSuchFactory => "no such factory",
+            MoveError::NoSuchRow => "no such row",
+            MoveError::NoSuchColumn => "no such column",
+            MoveError::TileNotAvailable => "tile is not available there",
+            MoveError::RowNotAvailable => "tile can't be placed on that row",
+            MoveError::NotYourTurn => "it's another player's turn",
+            MoveError::WrongCount => "that's not how many tiles the move takes",
+        };
+        f.write_str(message)
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+/// Reasons a state can't be played from: an illegal move, or a state that
+/// breaks the rules, e.g. built from bad notation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AzulError {
+    Move(MoveError),
+    /// A complete pattern row goes to a wall cell that already holds a tile
+    CellTaken {
+        row: usize,
+        column: usize,
+    },
+    /// The tiles of a color don't add up to 20
+    TileCount {
+        tile: Tile,
+        count: usize,
+    },
+    /// The incrementally updated hash is out of date
+    StaleHash,
+    /// The pending points of the player are out of date
+    PendingPoints {
+        player: usize,
+    },
+    /// A pattern row holds a color that's already on its wall row
+    RowOnWall {
+        player: usize,
+        row: usize,
+    },
+    /// More factories than a game of four players has
+    FactoryCount {
+        count: usize,
+    },
+}
+
+impl fmt::Display for AzulError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AzulError::Move(error) => write!(f, "{}", error),
+            AzulError::CellTaken { row, column } => write!(
+                f,
+                "wall cell on row {} column {} is already taken",
+                row + 1,
+                column + 1
+            ),
+            AzulError::TileCount { tile, count } => {
+                write!(f, "{} {} tiles, expected 20", count, tile)
+            }
+            AzulError::StaleHash => f.write_str("bad zobrist hash"),
+            AzulError::PendingPoints { player } => {
+                write!(f, "bad pending points of player {}", player + 1)
+            }
+            AzulError::RowOnWall { player, row } => write!(
+                f,
+                "pattern row {} of player {} holds a color already on the wall",
+                row + 1,
+                player + 1
+            ),
+            AzulError::FactoryCount { count } => {
+                write!(f, "{} factories, expected at most {}", count, MAX_FACTORIES)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AzulError {}
+
+impl From<MoveError> for AzulError {
+    fn from(error: MoveError) -> Self {
+        AzulError::Move(error)
+    }
+}
+
+/// A move together with who made it and how many tiles it took, for
+/// presenting to humans.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MoveDescription {
+    pub player: usize,
+    pub origin: Origin,
+    pub tile: Tile,
+    pub count: usize,
+    pub destination: Destination,
+}
+
+impl MoveDescription {
+    /// The move described.
+    pub fn to_move(&self) -> Move {
+        Move {
+            origin: self.origin,
+            tile: self.tile,
+            destination: self.destination,
+        }
+    }
+}
+
+// factories of a game of four players, the most there are
+pub(crate) const MAX_FACTORIES: usize = 9;
+
+// The factories of a state in a fixed array, so that states clone without
+// allocating in search. Derefs to the slice of the factories in use.
+#[derive(Clone, Copy)]
+pub(crate) struct Factories {
+    factories: [TileSet; MAX_FACTORIES],
+    len: usize,
+}
+
+impl Factories {
+    pub(crate) fn new() -> Self {
+        Self {
+            factories: [TileSet::new(); MAX_FACTORIES],
+            len: 0,
+        }
+    }
+    // panics if all factories are in use
+    pub(crate) fn push(&mut self, factory: TileSet) {
+        self.factories[self.len] = factory;
+        self.len += 1;
+    }
+    pub(crate) fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl TryFrom<&[TileSet]> for Factories {
+    type Error = AzulError;
+
+    fn try_from(slice: &[TileSet]) -> Result<Self, Self::Error> {
+        if slice.len() > MAX_FACTORIES {
+            return Err(AzulError::FactoryCount { count: slice.len() });
+        }
+        let mut factories = Self::new();
+        factories.factories[..slice.len()].copy_from_slice(slice);
+        factories.len = slice.len();
+        Ok(factories)
+    }
+}
+
+impl std::ops::Deref for Factories {
+    type Target = [TileSet];
+
+    fn deref(&self) -> &[TileSet] {
+        &self.factories[..self.len]
+    }
+}
+
+impl std::ops::DerefMut for Factories {
+    fn deref_mut(&mut self) -> &mut [TileSet] {
+        &mut self.factories[..self.len]
+    }
+}
+
+impl<'a> IntoIterator for &'a Factories {
+    type Item = &'a TileSet;
+    type IntoIter = std::slice::Iter<'a, TileSet>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// factories past the ones in use don't count
+impl PartialEq for Factories {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for Factories {}
+
+// serialized as a list, like a vector
+#[cfg(feature = "serde")]
+impl Serialize for Factories {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+// the round is searched to the end once this few piles of tiles are left
+const QUIESCENCE_GROUPS: usize = 3;
+
+/// What happens next in a game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// Players take tiles from the factories and the center
+    Picking,
+    /// The game is over, with the last round and the end of game bonuses
+    /// scored
+    Scoring,
+    /// The round is over and scored, and the factories of the next must be
+    /// dealt
+    Dealing,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Phase::Picking => "picking",
+            Phase::Scoring => "scoring",
+            Phase::Dealing => "dealing",
+        })
+    }
+}
+
+/// A complete game position.
+#[derive(PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct State {
-    bag: TileSet,
-    factories: Vec<TileSet>,
-    center: TileSet,
-    tray: TileSet,
+    pub(crate) bag: TileSet,
+    pub(crate) factories: Factories,
+    pub(crate) center: TileSet,
+    pub(crate) lid: TileSet,
     pub players: Vec<Player>,
+    /// Number of moves made so far
     pub moves: usize,
+    /// Rounds dealt so far, the current round while tiles are taken. Zero
+    /// before the first deal
+    pub round: usize,
+    /// Side of the player boards played
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "WallVariant::is_colored")
+    )]
+    pub variant: WallVariant,
+    // incrementally updated zobrist hash
+    #[cfg_attr(feature = "serde", serde(skip))]
+    zobrist: u64,
+}
+
+impl Clone for State {
+    fn clone(&self) -> Self {
+        Self {
+            bag: self.bag,
+            factories: self.factories,
+            center: self.center,
+            lid: self.lid,
+            players: self.players.clone(),
+            moves: self.moves,
+            round: self.round,
+            variant: self.variant,
+            zobrist: self.zobrist,
+        }
+    }
+    // reuses the players, e.g. of states sampled over and over in search
+    fn clone_from(&mut self, source: &Self) {
+        self.bag = source.bag;
+        self.factories = source.factories;
+        self.center = source.center;
+        self.lid = source.lid;
+        self.players.clone_from(&source.players);
+        self.moves = source.moves;
+        self.round = source.round;
+        self.variant = source.variant;
+        self.zobrist = source.zobrist;
+    }
 }
 
 impl Hash for State {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.bag.hash(state);
-        self.factories.hash(state);
-        self.center.hash(state);
-        self.tray.hash(state);
-        for player in &self.players {
-            player.discard.hash(state);
-            player.points.hash(state);
-            for row in &player.rows {
-                row.hash(state);
-            }
-            player.wall.hash(state);
+        self.zobrist.hash(state);
+    }
+}
+
+// the hash isn't serialized, but computed when deserializing
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for State {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Fields {
+            bag: TileSet,
+            factories: Vec<TileSet>,
+            center: TileSet,
+            lid: TileSet,
+            players: Vec<Player>,
+            moves: usize,
+            // as in notation, positions without a round are in the first
+            #[serde(default = "first_round")]
+            round: usize,
+            #[serde(default)]
+            variant: WallVariant,
         }
-        self.moves.hash(state);
+        fn first_round() -> usize {
+            1
+        }
+        let fields = Fields::deserialize(deserializer)?;
+        let mut state = State::from_parts(
+            fields.bag,
+            fields.factories,
+            fields.center,
+            fields.lid,
+            fields.players,
+            fields.moves,
+            fields.variant,
+        )
+        .map_err(serde::de::Error::custom)?;
+        state.round = fields.round;
+        state.check_integrity().map_err(serde::de::Error::custom)?;
+        Ok(state)
     }
 }
 
 impl State {
+    /// A new game with all tiles in the bag. Call `deal` to start the first round.
+    /// Panics unless there are two to four players.
     pub fn new(players: usize) -> Self {
+        Self::new_variant(players, WallVariant::Colored)
+    }
+    /// A new game played on the given side of the player boards.
+    pub fn new_variant(players: usize, variant: WallVariant) -> Self {
+        assert!((2..=4).contains(&players), "a game has two to four players");
         let bag = [
-            iter::repeat(Tile::BLACK).take(20),
-            iter::repeat(Tile::WHITE).take(20),
-            iter::repeat(Tile::AZUL).take(20),
-            iter::repeat(Tile::YELLOW).take(20),
-            iter::repeat(Tile::RED).take(20),
+            iter::repeat_n(Tile::BLACK, 20),
+            iter::repeat_n(Tile::WHITE, 20),
+            iter::repeat_n(Tile::AZUL, 20),
+            iter::repeat_n(Tile::YELLOW, 20),
+            iter::repeat_n(Tile::RED, 20),
         ]
         .into_iter()
-        .flat_map(|it| it)
+        .flatten()
         .collect();
-        let players = iter::repeat(Player::new()).take(players).collect();
-        Self {
+        let players = iter::repeat_n(Player::new(), players).collect();
+        Self::from_parts(
             bag,
-            factories: Vec::new(),
-            center: TileSet::new(),
-            tray: TileSet::new(),
+            Vec::new(),
+            TileSet::new(),
+            TileSet::new(),
             players,
-            moves: 0,
+            0,
+            variant,
+        )
+        .expect("an empty wall has room for every tile")
+    }
+    pub(crate) fn from_parts(
+        bag: TileSet,
+        factories: Vec<TileSet>,
+        center: TileSet,
+        lid: TileSet,
+        players: Vec<Player>,
+        moves: usize,
+        variant: WallVariant,
+    ) -> Result<Self, AzulError> {
+        let mut state = Self {
+            bag,
+            factories: Factories::try_from(&factories[..])?,
+            center,
+            lid,
+            players,
+            moves,
+            round: 0,
+            variant,
+            zobrist: 0,
+        };
+        for player in &mut state.players {
+            player.pending = player.score_pending(&variant)?;
         }
+        state.zobrist = state.compute_zobrist();
+        Ok(state)
     }
-    fn tile_count(&self) -> usize {
-        [
-            self.bag.len(),
-            self.factories.iter().map(|f| f.len()).sum(),
-            self.center.len(),
-            self.tray.len(),
-            self.players.iter().map(|p| p.tile_count()).sum(),
-        ]
-        .iter()
-        .sum()
+    /// Zobrist hash of the position computed from scratch. Moves and deals
+    /// update `zobrist` incrementally instead, which this checks.
+    pub fn compute_zobrist(&self) -> u64 {
+        let mut hash = self.bag.zobrist(zobrist::BAG)
+            ^ self.center.zobrist(zobrist::CENTER)
+            ^ self.lid.zobrist(zobrist::LID)
+            ^ self.turn_zobrist();
+        for (index, factory) in self.factories.iter().enumerate() {
+            let copy = self.factories[..index]
+                .iter()
+                .filter(|other| *other == factory)
+                .count();
+            hash ^= factory.zobrist(zobrist::FACTORY + copy as u64);
+        }
+        for (index, player) in self.players.iter().enumerate() {
+            hash ^= player.zobrist(index);
+        }
+        hash
     }
+    // factories are hashed as a multiset, since their order doesn't matter:
+    // the n:th of the factories with the same tiles by its tiles and n. The
+    // factory is hashed as the last of its copies, so emptying it leaves
+    // the hashes of the others unchanged
+    fn factory_zobrist(&self, index: usize) -> u64 {
+        let factory = &self.factories[index];
+        let copies = self
+            .factories
+            .iter()
+            .filter(|other| *other == factory)
+            .count();
+        factory.zobrist(zobrist::FACTORY + copies as u64 - 1)
+    }
+    fn turn_zobrist(&self) -> u64 {
+        zobrist::key(zobrist::TURN, self.current_player(), 0)
+    }
+    // hash of the parts of the state a move changes, excluding round scoring
+    fn move_zobrist(&self, m: &Move) -> u64 {
+        let player_index = self.current_player();
+        let player = &self.players[player_index];
+        let mut hash = self.center.zobrist(zobrist::CENTER)
+            ^ self.lid.zobrist(zobrist::LID)
+            ^ self.turn_zobrist()
+            ^ player.floor_zobrist(player_index);
+        if let Origin::Factory(index) = m.origin {
+            hash ^= self.factory_zobrist(index);
+        }
+        if let Destination::Row(row_index) | Destination::Wall(row_index, _) = m.destination {
+            hash ^= player.row_zobrist(player_index, row_index);
+        }
+        hash
+    }
+    /// Zobrist hash of the position. Equal positions have equal hashes.
+    pub fn zobrist(&self) -> u64 {
+        self.zobrist
+    }
+    // every tile in the game, wherever it is
+    fn tiles(&self) -> TileSet {
+        let mut tiles = self.bag;
+        tiles.extend(self.center);
+        tiles.extend(self.lid);
+        for factory in &self.factories {
+            tiles.extend(*factory);
+        }
+        for player in &self.players {
+            tiles.extend(player.tiles());
+        }
+        tiles
+    }
+    // five factories for two players and two more for each further player
+    pub(crate) fn factory_count(&self) -> usize {
+        2 * self.players.len() + 1
+    }
+    /// Fill the factories with tiles drawn from the bag. Late in a long game
+    /// the bag and the lid may not hold enough for every factory, and the
+    /// round is played with the last factories short or empty.
     pub fn deal<R: Rng>(&mut self, rng: &mut R) {
-        let n = 5; // TODO: Compute based on number of players
-        if self.bag.len() < 4 * n {
-            // move tiles from tray to bag
-            let mut tmp = TileSet::new();
-            mem::swap(&mut tmp, &mut self.tray);
-            self.bag.extend(tmp);
-        }
-        // deal factories
-        for _ in 0..n {
-            let tiles = self.bag.draw(rng, 4);
+        self.factories.clear();
+        for _ in 0..self.factory_count() {
+            let mut tiles = self.bag.draw(rng, 4);
+            if tiles.len() < 4 {
+                // the bag ran out, continue from the refilled bag
+                self.refill();
+                tiles.extend(self.bag.draw(rng, 4 - tiles.len()));
+            }
             self.factories.push(tiles);
         }
+        self.round += 1;
+        self.zobrist = self.compute_zobrist();
+    }
+    /// For each color, in the order black, white, azul, yellow, red, the
+    /// chance of the next deal putting each number of tiles of the color in
+    /// the factories, from none up. A deal draws from the bag until it runs
+    /// out, and then from the tiles in the lid.
+    pub fn draw_probabilities(&self) -> [Vec<f64>; 5] {
+        TILES.map(|tile| self.draw_distribution(tile))
+    }
+    // draw_probabilities of a single color
+    pub(crate) fn draw_distribution(&self, tile: Tile) -> Vec<f64> {
+        let draws = 4 * self.factory_count();
+        if self.bag.len() >= draws {
+            return hypergeometric(&self.bag, tile, draws);
+        }
+        // all of the bag is dealt, and the rest drawn from the lid
+        let mut distribution = vec![0.0; self.bag.get(tile)];
+        distribution.extend(hypergeometric(&self.lid, tile, draws - self.bag.len()));
+        distribution
+    }
+    // move the discarded tiles from the lid back to the bag
+    fn refill(&mut self) {
+        let mut tmp = TileSet::new();
+        mem::swap(&mut tmp, &mut self.lid);
+        self.bag.extend(tmp);
+    }
+    // every way to fill one more factory from the bag with its probability,
+    // refilling the bag from the lid if it runs out
+    fn fills(&self) -> Vec<(f64, State)> {
+        let mut fills = Vec::new();
+        for (p, tiles) in self.bag.draws(4) {
+            let mut state = self.clone();
+            for tile in TILES {
+                state.bag.remove(tile, tiles.get(tile));
+            }
+            if tiles.len() == 4 || state.lid.len() == 0 {
+                state.factories.push(tiles);
+                fills.push((p, state));
+                continue;
+            }
+            state.refill();
+            for (q, more) in state.bag.draws(4 - tiles.len()) {
+                let mut state = state.clone();
+                for tile in TILES {
+                    state.bag.remove(tile, more.get(tile));
+                }
+                let mut tiles = tiles;
+                tiles.extend(more);
+                state.factories.push(tiles);
+                fills.push((p * q, state));
+            }
+        }
+        fills
+    }
+    // every possible deal with its probability, unless there are more than
+    // limit of them
+    fn deals(&self, limit: usize) -> Option<Vec<(f64, State)>> {
+        let mut state = self.clone();
+        state.factories.clear();
+        state.round += 1;
+        let mut deals = vec![(1.0, state)];
+        for _ in 0..self.factory_count() {
+            let mut next = Vec::new();
+            for (probability, state) in &deals {
+                for (p, state) in state.fills() {
+                    if next.len() == limit {
+                        return None;
+                    }
+                    next.push((probability * p, state));
+                }
+            }
+            deals = next;
+        }
+        for (_, state) in &mut deals {
+            state.zobrist = state.compute_zobrist();
+        }
+        Some(deals)
+    }
+    /// Deal the given factories rather than random ones, e.g. when
+    /// replaying a game. Returns false if the bag doesn't hold the tiles,
+    /// or there are more factories than any game has.
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    pub(crate) fn deal_factories(&mut self, factories: Vec<TileSet>) -> bool {
+        let Ok(factories) = Factories::try_from(&factories[..]) else {
+            return false;
+        };
+        // the bag is emptied before any tile is taken from the lid
+        let count: usize = factories.iter().map(|factory| factory.len()).sum();
+        if count > self.bag.len() {
+            self.refill();
+        }
+        for tile in TILES {
+            let count: usize = factories.iter().map(|factory| factory.get(tile)).sum();
+            if count > self.bag.get(tile) {
+                return false;
+            }
+            self.bag.remove(tile, count);
+        }
+        self.factories = factories;
+        self.round += 1;
+        self.zobrist = self.compute_zobrist();
+        true
+    }
+    // number of piles of one color that can be taken, each at least one move
+    fn tile_groups(&self) -> usize {
+        self.factories
+            .iter()
+            .chain(iter::once(&self.center))
+            .map(|tiles| TILES.iter().filter(|tile| tiles.get(**tile) > 0).count())
+            .sum()
     }
     fn is_empty(&self) -> bool {
         self.factories
@@ -422,138 +1495,562 @@ impl State {
             + self.center.len()
             == 0
     }
-    // clean up by updating score, dealing new tiles, etc
-    fn prepare_next_round<R: Rng>(&mut self, rng: &mut R) {
+    /// What happens next: taking tiles, dealing or, once the game is
+    /// over, nothing but the final scoring.
+    pub fn phase(&self) -> Phase {
+        if self.is_game_over() {
+            Phase::Scoring
+        } else if self.is_empty() {
+            Phase::Dealing
+        } else {
+            Phase::Picking
+        }
+    }
+    /// Whether the round is over and new factories must be dealt before
+    /// play can continue.
+    pub fn needs_deal(&self) -> bool {
+        self.is_empty() && !self.is_game_over()
+    }
+    // clean up by updating score, etc. Dealing is left to the caller
+    fn end_turn(&mut self) -> Result<(), AzulError> {
+        self.end_turn_scoring(&mut |_, _| {})
+    }
+    // end_turn, telling event about every change of points of a player
+    fn end_turn_scoring(
+        &mut self,
+        event: &mut impl FnMut(usize, ScoreEvent),
+    ) -> Result<(), AzulError> {
         // are the more tiles?
         if self.is_empty() {
-            // 1. Score and move tiles to tray/wall
-            for player in &mut self.players {
-                player.prepare_next_round(&mut self.tray);
+            // 1. Score and move tiles to lid/wall
+            for (index, player) in self.players.iter_mut().enumerate() {
+                player
+                    .prepare_next_round(&self.variant, &mut self.lid, &mut |e| event(index, e))?;
+            }
+            // 2. Award end of game bonuses
+            if self.is_game_over() {
+                for (index, player) in self.players.iter_mut().enumerate() {
+                    player.final_score(&mut |e| event(index, e));
+                }
             }
-            // 2. Deal new factories
-            self.deal(rng);
+            // 3. Update current player
+            self.moves += 1;
+            self.zobrist = self.compute_zobrist();
+        } else {
+            // 3. Update current player
+            self.zobrist ^= self.turn_zobrist();
+            self.moves += 1;
+            self.zobrist ^= self.turn_zobrist();
         }
-        // 3. Update current player
-        self.moves += 1;
+        Ok(())
     }
     fn is_game_over(&self) -> bool {
         // game is over if any player has any row with all cells filled
-        self.players.iter().any(|player| {
-            player
-                .wall
-                .rows
-                .iter()
-                .any(|row| row.iter().all(|cell| *cell))
-        })
+        self.players
+            .iter()
+            .any(|player| (0..5).any(|row| player.wall.is_row_complete(row)))
     }
-    fn place_all<R: Rng>(&self, tile: Tile, count: usize, rng: &mut R) -> Vec<Self> {
-        // Put the "count" number of "tile" on one row. Return a state for each
-        // such placement. Furthermore the tiles cannot be placed anywhere, place
-        // them in the discard
-        let player_index = self.current_player();
-        let states: Vec<_> = (0..5)
-            .flat_map(|row| {
-                //println!("    placing in row {}", row);
-                let mut state = self.clone();
-                if state.players[player_index].maybe_place(tile, count, row) {
-                    state.prepare_next_round(rng);
-                    Some(state)
-                } else {
-                    None
+    // what decides the winner: points, then complete rows
+    fn standing(&self, index: usize) -> (usize, usize) {
+        let player = &self.players[index];
+        (player.points, player.complete_rows())
+    }
+    // standing of the winner, once the game is over
+    fn best_standing(&self) -> Option<(usize, usize)> {
+        if !self.is_game_over() {
+            return None;
+        }
+        (0..self.players.len())
+            .map(|index| self.standing(index))
+            .max()
+    }
+    fn available(&self, origin: Origin) -> Option<&TileSet> {
+        match origin {
+            Origin::Factory(index) => self.factories.get(index),
+            Origin::Center => Some(&self.center),
+        }
+    }
+    // destinations the current player can put count tiles on. The floor
+    // line takes tiles whether or not they fit on a pattern row.
+    fn destinations(&self, tile: Tile, count: usize) -> Vec<Destination> {
+        let mut destinations = Vec::new();
+        self.for_destinations(tile, count, |destination| destinations.push(destination));
+        destinations
+    }
+
+    // calls back with every destination of the tiles, without collecting
+    // them, for move generation in search
+    fn for_destinations(&self, tile: Tile, count: usize, mut f: impl FnMut(Destination)) {
+        let player = &self.players[self.current_player()];
+        for row in (0..5).filter(|row| player.can_place(tile, *row)) {
+            let columns = self.variant.columns(player, row, tile);
+            if self.variant.chooses_column() && player.completes(count, row) && columns != 0 {
+                // ...and pick the column of the wall
+                columns_in(columns).for_each(|column| f(Destination::Wall(row, column)));
+            } else {
+                f(Destination::Row(row));
+            }
+        }
+        // ...or drop them all on the floor line
+        f(Destination::Floor);
+    }
+
+    /// All moves the current player can make.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        self.legal_moves_into(&mut moves);
+        moves
+    }
+
+    // the legal moves written over `moves`, which keeps its capacity
+    fn legal_moves_into(&self, moves: &mut Vec<Move>) {
+        moves.clear();
+        if self.is_game_over() {
+            return;
+        }
+        // take the tiles from one of the factories or the center...
+        let origins = (0..self.factories.len())
+            .map(Origin::Factory)
+            .chain(iter::once(Origin::Center));
+        for origin in origins {
+            let available = self.available(origin).unwrap();
+            // ...and select one color...
+            for tile in TILES {
+                if available.get(tile) > 0 {
+                    // ...and place them somewhere
+                    self.for_destinations(tile, available.get(tile), |destination| {
+                        moves.push(Move {
+                            origin,
+                            tile,
+                            destination,
+                        })
+                    });
                 }
-            })
-            .collect();
-        if states.is_empty() {
-            // player must discard all tiles :-(
-            let mut state = self.clone();
-            state.players[player_index].discard[tile] += count;
-            state.prepare_next_round(rng);
-            vec![state]
-        } else {
-            states
+            }
+        }
+    }
+
+    // why the move is illegal, if it is
+    fn check(&self, m: &Move) -> Result<(), MoveError> {
+        if self.is_game_over() {
+            return Err(MoveError::GameOver);
+        }
+        let available = self.available(m.origin).ok_or(MoveError::NoSuchFactory)?;
+        if available.get(m.tile) == 0 {
+            return Err(MoveError::TileNotAvailable);
+        }
+        match m.destination {
+            Destination::Row(row) | Destination::Wall(row, _) if row >= 5 => {
+                Err(MoveError::NoSuchRow)
+            }
+            Destination::Wall(_, column) if column >= 5 => Err(MoveError::NoSuchColumn),
+            destination
+                if !self
+                    .destinations(m.tile, available.get(m.tile))
+                    .contains(&destination) =>
+            {
+                Err(MoveError::RowNotAvailable)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the state after the current player made the move. If the move
+    /// ends the round it's scored, but new factories are not dealt, see
+    /// `needs_deal`.
+    pub fn apply(&self, m: &Move) -> Result<State, AzulError> {
+        self.check(m)?;
+        let mut state = self.clone();
+        state.take(m)?;
+        state.end_turn()?;
+        Ok(state)
+    }
+    /// Like `apply`, also telling how the points of each player changed if
+    /// the move ends the round, and the game: the events of every player,
+    /// in seating order. There are none while the round goes on.
+    pub fn apply_scoring(&self, m: &Move) -> Result<(State, Vec<Vec<ScoreEvent>>), AzulError> {
+        self.check(m)?;
+        let mut state = self.clone();
+        state.take(m)?;
+        let mut events = vec![Vec::new(); self.players.len()];
+        state.end_turn_scoring(&mut |player, event| events[player].push(event))?;
+        if events.iter().all(Vec::is_empty) {
+            events.clear();
+        }
+        Ok((state, events))
+    }
+
+    // moves the tiles of a legal move, leaving the end of turn to the caller
+    fn take(&mut self, m: &Move) -> Result<(), AzulError> {
+        // remove the hash of what is about to change...
+        self.zobrist ^= self.move_zobrist(m);
+        // take tile and leave rest in center
+        let count = match m.origin {
+            Origin::Factory(index) => {
+                let mut factory = TileSet::new();
+                mem::swap(&mut factory, &mut self.factories[index]);
+                let count = factory.drain(m.tile);
+                self.center.extend(factory);
+                count
+            }
+            Origin::Center => self.center.drain(m.tile),
+        };
+        let player_index = self.current_player();
+        let player = &mut self.players[player_index];
+        match m.destination {
+            Destination::Row(row) => {
+                player.maybe_place(m.tile, count, row, None, &mut self.lid);
+            }
+            Destination::Wall(row, column) => {
+                player.maybe_place(m.tile, count, row, Some(column), &mut self.lid);
+            }
+            Destination::Floor => player.floor.push(m.tile, count, &mut self.lid),
+        }
+        if let Destination::Row(row) | Destination::Wall(row, _) = m.destination {
+            if player.rows[row].is_some_and(|(_, count)| count == row + 1) {
+                player.pending = player.score_pending(&self.variant)?;
+            }
+        }
+        // ...and add it back after the change
+        self.zobrist ^= self.move_zobrist(m);
+        Ok(())
+    }
+
+    /// Describes a legal move made from this state.
+    pub fn describe(&self, m: &Move) -> MoveDescription {
+        MoveDescription {
+            player: self.current_player(),
+            origin: m.origin,
+            tile: m.tile,
+            count: self
+                .available(m.origin)
+                .map_or(0, |tiles| tiles.get(m.tile)),
+            destination: m.destination,
         }
     }
 
+    /// Returns the state after the described move, like `apply`, checking
+    /// that it's made by the player to move and takes as many tiles as
+    /// described. The inverse of `describe_move`.
+    pub fn apply_description(&self, description: &MoveDescription) -> Result<State, AzulError> {
+        let m = description.to_move();
+        self.check(&m)?;
+        if description.player != self.current_player() {
+            return Err(MoveError::NotYourTurn.into());
+        }
+        if description.count != self.describe(&m).count {
+            return Err(MoveError::WrongCount.into());
+        }
+        self.apply(&m)
+    }
+
+    // are the states equal, apart from new factories dealt from bag and lid?
+    fn same_except_deal(&self, dealt: &State) -> bool {
+        self.is_empty()
+            && self.center == dealt.center
+            && self.players == dealt.players
+            && self.moves == dealt.moves
+    }
+
+    /// Checks that all 20 tiles of every color are accounted for, that no
+    /// pattern row holds a color already on its wall row, and that the hash
+    /// and pending points are up to date.
+    pub fn check_integrity(&self) -> Result<(), AzulError> {
+        let tiles = self.tiles();
+        for tile in TILES {
+            let count = tiles.get(tile);
+            if count != 20 {
+                return Err(AzulError::TileCount { tile, count });
+            }
+        }
+        if self.zobrist != self.compute_zobrist() {
+            return Err(AzulError::StaleHash);
+        }
+        for (index, player) in self.players.iter().enumerate() {
+            for (row_index, row) in player.rows.iter().enumerate() {
+                if row.is_some_and(|(tile, _)| player.wall.has_tile(row_index, &tile)) {
+                    return Err(AzulError::RowOnWall {
+                        player: index,
+                        row: row_index,
+                    });
+                }
+            }
+            if player.pending != player.score_pending(&self.variant)? {
+                return Err(AzulError::PendingPoints { player: index });
+            }
+        }
+        Ok(())
+    }
+    /// Panics unless `check_integrity` passes.
     pub fn self_check(&self) {
-        let n = self.tile_count();
-        if n != 100 {
-            println!("bad tile count {}", n);
-            panic!();
+        if let Err(error) = self.check_integrity() {
+            panic!("{}", error);
         }
     }
 }
 
-impl GameState for State {
+/// What a move changed, to take it back with `DeterministicGameState::undo`.
+pub struct Undo {
+    // the factory taken from, by index
+    factory: Option<(usize, TileSet)>,
+    center: TileSet,
+    lid: TileSet,
+    // the board of the player who moved
+    player: Player,
+    // every board before round scoring, when the move ended the round
+    players: Option<Vec<Player>>,
+    zobrist: u64,
+}
+
+impl DeterministicGameState for State {
+    type Move = Move;
+    type Undo = Undo;
+
     fn current_player(&self) -> usize {
         self.moves % self.players.len()
     }
-    fn children<R: Rng>(&self, rng: &mut R) -> Vec<Self> {
-        let mut children = Vec::new();
-        // take the tiles from one of the factories...
-        for factory_index in 0..self.factories.len() {
-            let mut state = self.clone();
-            //println!("Taking factory #{}", factory_index);
-            let factory = state.factories.remove(factory_index);
-            // ...and select one color
-            for tile in TILES {
-                // take tile and leave rest in center
-                let mut factory = factory.clone();
-                let count = factory.drain(tile);
-                if count > 0 {
-                    let mut state = state.clone();
-                    //println!("  Taking {} of {:?}", count, tile);
-                    state.center.extend(factory);
-                    children.extend(state.place_all(tile, count, rng));
-                }
+    fn legal_moves(&self) -> Vec<Move> {
+        State::legal_moves(self)
+    }
+    fn play(&self, m: &Move) -> Self {
+        self.apply(m).unwrap()
+    }
+    // taking from a factory equal to an earlier one leads to the same
+    // position but for the order of the factories. Any other two moves
+    // place different tiles, so there is no need to compare the states
+    fn legal_moves_into(&self, moves: &mut Vec<Move>) {
+        State::legal_moves_into(self, moves)
+    }
+    fn distinct_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        self.distinct_moves_into(&mut moves);
+        moves
+    }
+    fn distinct_moves_into(&self, moves: &mut Vec<Move>) {
+        let first = |index: usize| {
+            let factory = &self.factories[index];
+            self.factories.iter().position(|other| other == factory) == Some(index)
+        };
+        State::legal_moves_into(self, moves);
+        moves.retain(|m| match m.origin {
+            Origin::Factory(index) => first(index),
+            Origin::Center => true,
+        });
+    }
+    // moves dropping at least margin more tiles on the floor line than
+    // another move taking the same tiles, e.g. four tiles on the first
+    // row when the fourth row is empty. The fewest dropped are kept, so
+    // every pick of tiles keeps a move
+    fn prune_moves(&self, moves: &mut Vec<Move>, margin: usize) {
+        let player = &self.players[self.current_player()];
+        let pick = |m: &Move| {
+            let origin = match m.origin {
+                Origin::Factory(index) => index,
+                Origin::Center => MAX_FACTORIES,
+            };
+            origin * TILES.len() + m.tile as usize
+        };
+        let dropped = |m: &Move| {
+            let count = self
+                .available(m.origin)
+                .map_or(0, |tiles| tiles.get(m.tile));
+            match m.destination {
+                Destination::Row(row) | Destination::Wall(row, _) => player.overflow(count, row),
+                Destination::Floor => count,
             }
+        };
+        let mut fewest = [usize::MAX; (MAX_FACTORIES + 1) * TILES.len()];
+        for m in moves.iter() {
+            fewest[pick(m)] = fewest[pick(m)].min(dropped(m));
         }
-        // Or take all tiles of one type from the center
-        for tile in TILES {
-            // take tile from center
-            let mut state = self.clone();
-            let count = state.center.drain(tile);
-            if count > 0 {
-                //println!("  Taking {:?} from center", tile);
-                children.extend(state.place_all(tile, count, rng));
-            }
+        moves.retain(|m| dropped(m) < fewest[pick(m)] + margin.max(1));
+    }
+    // the most points win, and ties are broken by the most complete rows
+    fn result(&self) -> GameResult {
+        let Some(best) = self.best_standing() else {
+            return GameResult::Ongoing;
+        };
+        let scores = self.players.iter().map(|player| player.points).collect();
+        let players: Vec<_> = (0..self.players.len())
+            .filter(|index| self.standing(*index) == best)
+            .collect();
+        match players[..] {
+            [player] => GameResult::Won { player, scores },
+            _ => GameResult::Draw { players, scores },
         }
-        children
     }
-    fn winner(&self) -> Option<usize> {
-        if self.is_game_over() {
-            self.players.iter().map(|player| player.points).max()
-        } else {
-            None
+    fn key(&self) -> u64 {
+        self.zobrist
+    }
+    // search the end of the round through, since scoring can change the
+    // picture completely
+    fn is_quiet(&self) -> bool {
+        self.is_empty() || self.tile_groups() > QUIESCENCE_GROUPS
+    }
+    fn apply_in_place(&mut self, m: &Move) -> Undo {
+        let player = self.current_player();
+        let mut undo = Undo {
+            factory: match m.origin {
+                Origin::Factory(index) => Some((index, self.factories[index])),
+                Origin::Center => None,
+            },
+            center: self.center,
+            lid: self.lid,
+            player: self.players[player].clone(),
+            players: None,
+            zobrist: self.zobrist,
+        };
+        // states are checked when built, and legal moves keep them sound
+        self.take(m).expect("legal move on a sound state");
+        if self.is_empty() {
+            // the round is scored, which may change every board
+            undo.players = Some(self.players.clone());
+        }
+        self.end_turn().expect("legal move on a sound state");
+        undo
+    }
+    fn undo(&mut self, undo: Undo) {
+        self.moves -= 1;
+        if let Some((index, factory)) = undo.factory {
+            self.factories[index] = factory;
+        }
+        self.center = undo.center;
+        self.lid = undo.lid;
+        if let Some(players) = undo.players {
+            self.players = players;
         }
+        let player = self.current_player();
+        self.players[player] = undo.player;
+        self.zobrist = undo.zobrist;
     }
 }
 
+// logarithm of the binomial coefficient
+fn ln_choose(n: usize, k: usize) -> f64 {
+    (0..k).map(|i| ((n - i) as f64 / (k - i) as f64).ln()).sum()
+}
+
+// chance of drawing each number of the tile, from none up, in `draws`
+// draws from the tiles, by the hypergeometric distribution
+fn hypergeometric(tiles: &TileSet, tile: Tile, draws: usize) -> Vec<f64> {
+    let (total, of_tile) = (tiles.len(), tiles.get(tile));
+    let draws = draws.min(total);
+    let ways = ln_choose(total, draws);
+    (0..=draws.min(of_tile))
+        .map(|drawn| match draws - drawn <= total - of_tile {
+            true => {
+                (ln_choose(of_tile, drawn) + ln_choose(total - of_tile, draws - drawn) - ways).exp()
+            }
+            false => 0.0,
+        })
+        .collect()
+}
+
+impl StochasticGameState for State {
+    fn is_chance(&self) -> bool {
+        self.needs_deal()
+    }
+    fn sample<R: Rng>(&self, rng: &mut R) -> Self {
+        let mut state = self.clone();
+        state.deal(rng);
+        state
+    }
+    // copies into the vectors of the outcome, which keep their capacity
+    fn sample_into<R: Rng>(&self, rng: &mut R, outcome: &mut Self) {
+        outcome.clone_from(self);
+        outcome.deal(rng);
+    }
+    fn outcomes(&self, limit: usize) -> Option<Vec<(f64, Self)>> {
+        self.deals(limit)
+    }
+}
+
+// points of the player minus those of the best opponent, counting the
+// points of complete pattern rows as already scored, in hundredths of a
+// point like the other evaluations
+fn point_difference(state: &State, player: usize) -> i32 {
+    let points = |player: &Player| (player.points + player.pending) as i32;
+    let best_opponent = state
+        .players
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index != player)
+        .map(|(_, opponent)| points(opponent))
+        .max()
+        .unwrap_or(0);
+    100 * (points(&state.players[player]) - best_opponent)
+}
+
 impl Evaluation<State> for State {
     fn evaulate(&self, state: &State, player: usize) -> i32 {
-        state.players[player].points as i32
+        point_difference(state, player)
     }
 }
 
+/// Basic evaluation scoring a position by how far the player leads the best
+/// opponent, in hundredths of a point. Evaluations are cached in a fixed number of slots,
+/// so the cache doesn't grow over a long session.
 // Could not come up with a good name for a basic stupid evaluation
 pub struct Fish {
-    cache: HashMap<State, i32>,
+    // evaluations by zobrist hash and player, the latest one in each slot
+    cache: Vec<Cell<Option<(u64, usize, i32)>>>,
+    hits: Cell<u64>,
+}
+impl Default for Fish {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 impl Fish {
     pub fn new() -> Self {
+        Self::with_capacity(1 << 16)
+    }
+    /// An evaluation caching at least `capacity` evaluations.
+    pub fn with_capacity(capacity: usize) -> Self {
         Fish {
-            cache: HashMap::new(),
+            cache: vec![Cell::new(None); capacity.next_power_of_two()],
+            hits: Cell::new(0),
         }
     }
+    fn slot(&self, zobrist: u64, player: usize) -> &Cell<Option<(u64, usize, i32)>> {
+        let key = zobrist ^ (player as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        &self.cache[key as usize & (self.cache.len() - 1)]
+    }
 }
 impl Evaluation<State> for Fish {
     fn evaulate(&self, state: &State, player: usize) -> i32 {
-        state.players[player].points as i32
-    }
-    fn update(&mut self, state: &State, value: i32) {
-        self.cache.insert(state.clone(), value);
+        let slot = self.slot(state.zobrist, player);
+        match slot.get() {
+            Some((zobrist, cached, value)) if zobrist == state.zobrist && cached == player => {
+                self.hits.set(self.hits.get() + 1);
+                value
+            }
+            _ => {
+                let value = point_difference(state, player);
+                slot.set(Some((state.zobrist, player, value)));
+                value
+            }
+        }
     }
-    fn heuristic(&self, _states: &mut Vec<State>) {
-        //states.sort_by_key(|state| self.cache.get(state));
-        //states.reverse();
+    fn cache_hits(&self) -> u64 {
+        self.hits.get()
     }
 }
+
+/// Finds the move leading from `before` to `after`, if any. A new deal at
+/// the end of the round is allowed to have happened in between.
+pub fn describe_move(before: &State, after: &State) -> Option<MoveDescription> {
+    describe_scoring(before, after).map(|(description, _)| description)
+}
+
+/// Like `describe_move`, also telling how the points of each player changed
+/// when the move ended the round, as by `State::apply_scoring`.
+pub fn describe_scoring(
+    before: &State,
+    after: &State,
+) -> Option<(MoveDescription, Vec<Vec<ScoreEvent>>)> {
+    before.legal_moves().into_iter().find_map(|m| {
+        let (state, events) = before.apply_scoring(&m).ok()?;
+        (state == *after || state.same_except_deal(after)).then(|| (before.describe(&m), events))
+    })
+}
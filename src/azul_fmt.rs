@@ -0,0 +1,528 @@
+//! Text rendering of game states and moves for terminals.
+//!
+//! `render_changes` draws a position like `render_state`, marking what the
+//! last move changed: the player who made it, the factory the tiles came
+//! from, and every tile gained, pattern row cleared and point scored since
+//! the previous position. Marks are in inverse video, or a dot on blocks,
+//! so they need colors; changes of points are written out either way.
+
+use crate::{
+    azul::{
+        describe_move, Destination, MoveDescription, Origin, Player, ScoreEvent, State, Tile,
+        TileSet, WallVariant, TILES, WALL,
+    },
+    clock::format_clock,
+    i18n::Language,
+    minmax::DeterministicGameState,
+};
+use std::{
+    env,
+    fmt::{self, Write},
+    io::{self, IsTerminal},
+    str::FromStr,
+    time::Duration,
+};
+
+const RESET: &str = "\x1b[0m";
+const DIM: &str = "\x1b[2m";
+const INVERSE: &str = "\x1b[7m";
+// ink of the dot marking a block, which inverse video wouldn't change
+const DOT: &str = "\x1b[30m";
+
+fn color(tile: Tile) -> &'static str {
+    match tile {
+        Tile::BLACK => "\x1b[90m",
+        Tile::WHITE => "\x1b[97m",
+        Tile::AZUL => "\x1b[34m",
+        Tile::YELLOW => "\x1b[33m",
+        Tile::RED => "\x1b[31m",
+    }
+}
+
+// background of the same color, for block glyphs
+fn background(tile: Tile) -> &'static str {
+    match tile {
+        Tile::BLACK => "\x1b[100m",
+        Tile::WHITE => "\x1b[107m",
+        Tile::AZUL => "\x1b[44m",
+        Tile::YELLOW => "\x1b[43m",
+        Tile::RED => "\x1b[41m",
+    }
+}
+
+// colors of the 256 color palette close to the Okabe-Ito ones, told apart
+// with red-green color blindness. Black is a middle gray, so that it shows
+// on dark terminals as well as light ones
+fn colorblind(tile: Tile) -> &'static str {
+    match tile {
+        Tile::BLACK => "\x1b[38;5;244m",
+        Tile::WHITE => "\x1b[1;38;5;255m",
+        Tile::AZUL => "\x1b[38;5;32m",
+        Tile::YELLOW => "\x1b[38;5;220m",
+        Tile::RED => "\x1b[38;5;166m",
+    }
+}
+
+fn colorblind_background(tile: Tile) -> &'static str {
+    match tile {
+        Tile::BLACK => "\x1b[48;5;244m",
+        Tile::WHITE => "\x1b[48;5;255m",
+        Tile::AZUL => "\x1b[48;5;32m",
+        Tile::YELLOW => "\x1b[48;5;220m",
+        Tile::RED => "\x1b[48;5;166m",
+    }
+}
+
+// a shape for each color, and its outline for the empty wall cells
+fn symbol(tile: Tile) -> (char, char) {
+    match tile {
+        Tile::BLACK => ('●', '○'),
+        Tile::WHITE => ('◆', '◇'),
+        Tile::AZUL => ('■', '□'),
+        Tile::YELLOW => ('▲', '△'),
+        Tile::RED => ('★', '☆'),
+    }
+}
+
+/// How tiles are told apart on the boards.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Theme {
+    /// Letters in the colors of the tiles
+    #[default]
+    Classic,
+    /// Letters in colors that people with red-green color blindness tell
+    /// apart, and that show on dark and light terminals alike
+    Colorblind,
+    /// Letters only, without colors
+    Letters,
+    /// A symbol for each color, `●` black, `◆` white, `■` azul, `▲`
+    /// yellow and `★` red, in the colors of the tiles. Letters without
+    /// Unicode
+    Symbols,
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Theme::Classic => "classic",
+            Theme::Colorblind => "colorblind",
+            Theme::Letters => "letters",
+            Theme::Symbols => "symbols",
+        })
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "classic" => Ok(Theme::Classic),
+            "colorblind" => Ok(Theme::Colorblind),
+            "letters" => Ok(Theme::Letters),
+            "symbols" => Ok(Theme::Symbols),
+            _ => Err(format!(
+                "unknown theme '{}', expected classic, colorblind, letters or symbols",
+                text
+            )),
+        }
+    }
+}
+
+impl Theme {
+    // the escape codes of the tile's color, and of its background for
+    // blocks, if the theme has colors
+    fn colors(self, tile: Tile) -> Option<(&'static str, &'static str)> {
+        match self {
+            Theme::Classic | Theme::Symbols => Some((color(tile), background(tile))),
+            Theme::Colorblind => Some((colorblind(tile), colorblind_background(tile))),
+            Theme::Letters => None,
+        }
+    }
+}
+
+impl fmt::Display for Tile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(Language::English.color(*self))
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&Language::English.origin(*self))
+    }
+}
+
+impl fmt::Display for Destination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&Language::English.destination(*self))
+    }
+}
+
+impl fmt::Display for MoveDescription {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&Language::English.describe(self))
+    }
+}
+
+impl fmt::Display for ScoreEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&Language::English.explain(self))
+    }
+}
+
+/// How boards are drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// ANSI color escape codes
+    pub color: bool,
+    /// Unicode characters, rather than ASCII only
+    pub unicode: bool,
+    /// Tiles as colored blocks rather than letters, and a box around the
+    /// wall. Needs Unicode, and colors for the blocks
+    pub glyphs: bool,
+    /// Mark the pattern rows the player to move can put tiles of this
+    /// color on, with the room in each and how many tiles would go to the
+    /// floor line
+    pub highlight: Option<Tile>,
+    /// How tiles are told apart
+    pub theme: Theme,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            color: true,
+            unicode: true,
+            glyphs: false,
+            highlight: None,
+            theme: Theme::Classic,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Plain ASCII text, e.g. for logs and files.
+    pub fn plain() -> Self {
+        Self {
+            color: false,
+            unicode: false,
+            glyphs: false,
+            highlight: None,
+            theme: Theme::Classic,
+        }
+    }
+
+    /// Colors and Unicode when standard output is a terminal, and plain
+    /// text when it's piped or redirected. Colors are also left out when
+    /// the `NO_COLOR` environment variable is set.
+    pub fn detect() -> Self {
+        let terminal = io::stdout().is_terminal();
+        Self {
+            color: terminal && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()),
+            unicode: terminal,
+            glyphs: false,
+            highlight: None,
+            theme: Theme::Classic,
+        }
+    }
+
+    // blocks are told apart by color alone, so symbols are kept as they are
+    fn blocks(&self) -> bool {
+        let colored = matches!(self.theme, Theme::Classic | Theme::Colorblind);
+        self.glyphs && self.unicode && self.color && colored
+    }
+
+    fn symbols(&self) -> bool {
+        self.theme == Theme::Symbols && self.unicode
+    }
+
+    fn boxed(&self) -> bool {
+        self.glyphs && self.unicode
+    }
+
+    // the text wrapped in the escape codes, or plain without colors
+    fn paint(&self, codes: &str, text: impl fmt::Display) -> String {
+        match self.color {
+            true => format!("{}{}{}", codes, text, RESET),
+            false => text.to_string(),
+        }
+    }
+
+    // the Unicode character, or its ASCII fallback
+    fn pick(&self, unicode: char, ascii: char) -> char {
+        match self.unicode {
+            true => unicode,
+            false => ascii,
+        }
+    }
+}
+
+// the tile, marked if the last move changed it
+fn render_tile(tile: Tile, changed: bool, options: &RenderOptions) -> String {
+    let glyph = match options.symbols() {
+        true => symbol(tile).0,
+        false => tile.letter(),
+    };
+    let mark = if changed { INVERSE } else { "" };
+    match (options.theme.colors(tile), options.blocks()) {
+        (Some((_, background)), true) if changed => {
+            options.paint(&format!("{}{}", background, DOT), '•')
+        }
+        (Some((color, background)), true) => {
+            options.paint(&format!("{}{}", color, background), '█')
+        }
+        (Some((color, _)), false) => options.paint(&format!("{}{}", mark, color), glyph),
+        (None, _) if changed => options.paint(INVERSE, glyph),
+        (None, _) => glyph.to_string(),
+    }
+}
+
+// tiles color by color, marking those that weren't in `before`
+fn render_tiles(tiles: &TileSet, before: Option<&TileSet>, options: &RenderOptions) -> String {
+    let mut out = String::new();
+    for tile in TILES {
+        let count = tiles.get(tile);
+        let gained = before.map_or(0, |before| count.saturating_sub(before.get(tile)));
+        out.push_str(&render_tile(tile, false, options).repeat(count - gained));
+        out.push_str(&render_tile(tile, true, options).repeat(gained));
+    }
+    out
+}
+
+// where the player to move can put tiles of the highlighted color
+struct Guide {
+    // most tiles of the color in one factory or the center
+    most: usize,
+    // room in each pattern row that can take the color
+    rows: [Option<usize>; 5],
+}
+
+impl Guide {
+    fn new(state: &State, tile: Tile) -> Self {
+        let sources = state.factories.iter().chain([&state.center]);
+        let most = sources.map(|source| source.get(tile)).max().unwrap_or(0);
+        let player = &state.players[state.current_player()];
+        let mut rows = [None; 5];
+        for m in state.legal_moves().iter().filter(|m| m.tile == tile) {
+            if let Destination::Row(row) | Destination::Wall(row, _) = m.destination {
+                let count = player.rows[row].map_or(0, |(_, count)| count);
+                rows[row] = Some(row + 1 - count);
+            }
+        }
+        Self { most, rows }
+    }
+
+    // the mark after a pattern row
+    fn mark(&self, row: usize, options: &RenderOptions) -> String {
+        let Some(room) = self.rows[row] else {
+            return String::new();
+        };
+        let arrow = options.pick('←', '<');
+        match self.most.saturating_sub(room) {
+            0 => format!(" {} room for {}", arrow, room),
+            floor => format!(" {} room for {}, up to {} to the floor", arrow, room, floor),
+        }
+    }
+}
+
+// the board of a player, marking the changes since `before` if given
+#[allow(clippy::too_many_arguments)]
+fn render_player(
+    out: &mut String,
+    player: &Player,
+    before: Option<&Player>,
+    variant: WallVariant,
+    name: &str,
+    clock: Option<Duration>,
+    guide: Option<&Guide>,
+    options: &RenderOptions,
+) -> fmt::Result {
+    write!(out, "{} {} points", name, player.points)?;
+    if let Some(before) = before.filter(|before| before.points != player.points) {
+        let change = player.points as i64 - before.points as i64;
+        write!(
+            out,
+            " {}",
+            options.paint(INVERSE, format!("({:+})", change))
+        )?;
+    }
+    match clock {
+        Some(clock) => writeln!(out, ", {}", format_clock(clock))?,
+        None => writeln!(out)?,
+    }
+    if options.boxed() {
+        writeln!(out, "      ┌{}┐", "─".repeat(11))?;
+    }
+    for (row_index, row) in player.rows.iter().enumerate() {
+        // pattern row, right aligned
+        let row_size = row_index + 1;
+        let (tile, count) = row.map_or((None, 0), |(tile, count)| (Some(tile), count));
+        let was = before.and_then(|before| before.rows[row_index]);
+        // the row was cleared, or gained the tiles furthest from the wall
+        let cleared = was.is_some() && row.is_none();
+        let gained = match (was, tile) {
+            (Some((was, before)), Some(tile)) if was == tile => count.saturating_sub(before),
+            (_, Some(_)) if before.is_some() => count,
+            _ => 0,
+        };
+        write!(out, "{}", " ".repeat(5 - row_size))?;
+        let empty = options.pick('·', '.');
+        let empties = empty.to_string().repeat(row_size - count);
+        match cleared {
+            true => write!(out, "{}", options.paint(INVERSE, empties))?,
+            false => write!(out, "{}", empties)?,
+        }
+        if let Some(tile) = tile {
+            write!(out, "{}", render_tile(tile, true, options).repeat(gained))?;
+            write!(
+                out,
+                "{}",
+                render_tile(tile, false, options).repeat(count - gained)
+            )?;
+        }
+        // wall, with empty cells dimmed
+        write!(out, " {}", options.pick('│', '|'))?;
+        for (column_index, tile) in WALL[row_index].iter().enumerate() {
+            match (player.wall.tile(row_index, column_index), variant) {
+                (Some(tile), _) => {
+                    let was = before.and_then(|before| before.wall.tile(row_index, column_index));
+                    let changed = before.is_some() && was.is_none();
+                    write!(out, " {}", render_tile(tile, changed, options))?
+                }
+                (None, WallVariant::Colored) => {
+                    let hint = match (options.blocks(), options.symbols()) {
+                        (true, _) => '▓',
+                        (false, true) => symbol(*tile).1,
+                        (false, false) => tile.letter().to_ascii_lowercase(),
+                    };
+                    let codes = match options.theme.colors(*tile) {
+                        Some((color, _)) => format!("{}{}", DIM, color),
+                        None => DIM.to_string(),
+                    };
+                    write!(out, " {}", options.paint(&codes, hint))?;
+                }
+                // the free wall is gray
+                (None, WallVariant::Free) => write!(out, " {}", options.paint(DIM, empty))?,
+            }
+        }
+        if options.boxed() {
+            write!(out, " │")?;
+        }
+        if let Some(guide) = guide {
+            write!(out, "{}", guide.mark(row_index, options))?;
+        }
+        writeln!(out)?;
+    }
+    if options.boxed() {
+        writeln!(out, "      └{}┘", "─".repeat(11))?;
+    }
+    writeln!(
+        out,
+        "floor: {} (-{})",
+        render_tiles(
+            &player.floor.tiles,
+            before.map(|before| &before.floor.tiles),
+            options
+        ),
+        player.floor.penalty()
+    )
+}
+
+/// Renders the factories, the center and all player boards.
+pub fn render_state(state: &State, names: &[&str], options: &RenderOptions) -> String {
+    render_state_with_clocks(state, names, &[], options)
+}
+
+/// Like `render_state`, with the time left on the clock of each player.
+pub fn render_state_with_clocks(
+    state: &State,
+    names: &[&str],
+    clocks: &[Duration],
+    options: &RenderOptions,
+) -> String {
+    render(state, None, names, clocks, options)
+}
+
+/// Like `render_state_with_clocks`, marking what changed since `previous`,
+/// the position before the last move. Clocks may be left empty.
+pub fn render_changes(
+    previous: &State,
+    state: &State,
+    names: &[&str],
+    clocks: &[Duration],
+    options: &RenderOptions,
+) -> String {
+    render(state, Some(previous), names, clocks, options)
+}
+
+fn render(
+    state: &State,
+    previous: Option<&State>,
+    names: &[&str],
+    clocks: &[Duration],
+    options: &RenderOptions,
+) -> String {
+    let mut out = String::new();
+    writeln!(out, "round {}, {}", state.round, state.phase()).unwrap();
+    let last_move = previous.and_then(|previous| describe_move(previous, state));
+    // after a new deal the factories and center have nothing to compare to
+    let dealt = previous.filter(|previous| previous.round == state.round);
+    let factories: Vec<_> = state
+        .factories
+        .iter()
+        .enumerate()
+        .map(|(index, factory)| {
+            let taken =
+                dealt.is_some() && last_move.is_some_and(|m| m.origin == Origin::Factory(index));
+            let label = match taken {
+                true => options.paint(INVERSE, index + 1),
+                false => (index + 1).to_string(),
+            };
+            format!("{}: {}", label, render_tiles(factory, None, options))
+        })
+        .collect();
+    // at most five factories to a line, lined up under the first
+    for (index, line) in factories.chunks(5).enumerate() {
+        let label = if index == 0 { "factories" } else { "" };
+        writeln!(out, "{:9} {}", label, line.join("  ")).unwrap();
+    }
+    if factories.is_empty() {
+        writeln!(out, "factories").unwrap();
+    }
+    let center = render_tiles(&state.center, dealt.map(|before| &before.center), options);
+    writeln!(out, "center {}", center).unwrap();
+    let guide = options
+        .highlight
+        .map(|tile| (tile, Guide::new(state, tile)));
+    if let Some((tile, Guide { most: 0, .. })) = guide {
+        writeln!(out, "no {} tiles to take", tile).unwrap();
+    }
+    for (index, player) in state.players.iter().enumerate() {
+        let name = names.get(index).copied().unwrap_or("?");
+        let name = match last_move.is_some_and(|m| m.player == index) {
+            true => options.paint(INVERSE, name),
+            false => name.to_string(),
+        };
+        let clock = clocks.get(index).copied();
+        let before = previous.map(|previous| &previous.players[index]);
+        // only the player to move can use the guide
+        let guide = guide
+            .as_ref()
+            .filter(|_| index == state.current_player())
+            .map(|(_, guide)| guide);
+        writeln!(out).unwrap();
+        render_player(
+            &mut out,
+            player,
+            before,
+            state.variant,
+            &name,
+            clock,
+            guide,
+            options,
+        )
+        .unwrap();
+    }
+    out
+}
@@ -0,0 +1,224 @@
+//! Raster rendering of game states as PNG images, for clients that can't
+//! show the text boards, e.g. chats and web pages without a board of their
+//! own.
+//!
+//! Tiles are drawn as colored squares: the factories and the center along
+//! the top, and below them the board of every player side by side, with
+//! their points, pattern rows, wall and floor line. Wall cells without a
+//! tile show their color faintly, and the board of the player to move is
+//! framed.
+
+use crate::{
+    azul::{Player, State, Tile, TileSet, WallVariant, TILES, WALL},
+    minmax::DeterministicGameState,
+};
+use std::{fs, io, path::Path};
+
+// side of a tile, and the space around tiles and parts of the image, in
+// pixels
+const CELL: usize = 20;
+const GAP: usize = 4;
+const MARGIN: usize = 16;
+// the center is wrapped after this many tiles
+const CENTER_WIDTH: usize = 10;
+// slots of the floor line
+const FLOOR: usize = 7;
+// pixels of a pixel of the digits
+const DIGIT_SCALE: usize = 4;
+
+type Rgb = [u8; 3];
+
+const BACKGROUND: Rgb = [0xf5, 0xf0, 0xe6];
+const SLOT: Rgb = [0xdc, 0xd4, 0xc8];
+const OUTLINE: Rgb = [0x88, 0x88, 0x88];
+const INK: Rgb = [0x33, 0x33, 0x33];
+
+// the colors of the tiles, as in HTML reports
+fn rgb(tile: Tile) -> Rgb {
+    match tile {
+        Tile::BLACK => [0x33, 0x33, 0x33],
+        Tile::WHITE => [0xee, 0xee, 0xee],
+        Tile::AZUL => [0x2a, 0x6e, 0xbb],
+        Tile::YELLOW => [0xf2, 0xc1, 0x2e],
+        Tile::RED => [0xdd, 0x33, 0x33],
+    }
+}
+
+// a quarter of the way from the background to the color
+fn faint(color: Rgb) -> Rgb {
+    let mut faint = BACKGROUND;
+    for (faint, color) in faint.iter_mut().zip(color) {
+        *faint = ((3 * *faint as usize + color as usize) / 4) as u8;
+    }
+    faint
+}
+
+// the digits in three by five pixels, a row of three bits each
+const DIGITS: [[u8; 5]; 10] = [
+    [7, 5, 5, 5, 7],
+    [2, 6, 2, 2, 7],
+    [7, 1, 7, 4, 7],
+    [7, 1, 7, 1, 7],
+    [5, 5, 7, 1, 1],
+    [7, 4, 7, 1, 7],
+    [7, 4, 7, 5, 7],
+    [7, 1, 1, 1, 1],
+    [7, 5, 7, 5, 7],
+    [7, 5, 7, 1, 7],
+];
+
+// RGB pixels, row by row
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: BACKGROUND.repeat(width * height),
+        }
+    }
+
+    fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, color: Rgb) {
+        for row in y..(y + height).min(self.height) {
+            for column in x..(x + width).min(self.width) {
+                let index = 3 * (row * self.width + column);
+                self.pixels[index..index + 3].copy_from_slice(&color);
+            }
+        }
+    }
+
+    // a rectangle drawn as its edges, `thickness` pixels wide
+    fn frame(&mut self, x: usize, y: usize, width: usize, height: usize, thickness: usize) {
+        self.fill(x, y, width, thickness, INK);
+        self.fill(x, y + height - thickness, width, thickness, INK);
+        self.fill(x, y, thickness, height, INK);
+        self.fill(x + width - thickness, y, thickness, height, INK);
+    }
+
+    // a square of a tile, or of an empty slot, with its top left corner at
+    // x, y
+    fn tile(&mut self, x: usize, y: usize, color: Rgb) {
+        self.fill(x, y, CELL, CELL, OUTLINE);
+        self.fill(x + 1, y + 1, CELL - 2, CELL - 2, color);
+    }
+
+    fn number(&mut self, x: usize, y: usize, number: usize) {
+        for (index, digit) in number.to_string().bytes().enumerate() {
+            let x = x + index * 4 * DIGIT_SCALE;
+            for (row, bits) in DIGITS[(digit - b'0') as usize].iter().enumerate() {
+                for column in 0..3 {
+                    if bits & (4 >> column) != 0 {
+                        let (left, top) = (x + column * DIGIT_SCALE, y + row * DIGIT_SCALE);
+                        self.fill(left, top, DIGIT_SCALE, DIGIT_SCALE, INK);
+                    }
+                }
+            }
+        }
+    }
+
+    // tiles in rows of `width`, color by color
+    fn tiles(&mut self, x: usize, y: usize, tiles: &TileSet, width: usize) {
+        let tiles = TILES
+            .iter()
+            .flat_map(|tile| std::iter::repeat_n(*tile, tiles.get(*tile)));
+        for (index, tile) in tiles.enumerate() {
+            let (row, column) = (index / width, index % width);
+            self.tile(x + column * (CELL + GAP), y + row * (CELL + GAP), rgb(tile));
+        }
+    }
+}
+
+// pixels taken by a line of cells, without the space after the last
+fn span(cells: usize) -> usize {
+    (cells * (CELL + GAP)).saturating_sub(GAP)
+}
+
+// the board of a player: points, then pattern rows and wall side by side,
+// then the floor line
+const BOARD_WIDTH: usize = 2 * MARGIN + 10 * (CELL + GAP) + GAP;
+const POINTS_HEIGHT: usize = 5 * DIGIT_SCALE + MARGIN;
+const BOARD_HEIGHT: usize = 2 * MARGIN + POINTS_HEIGHT + 6 * (CELL + GAP) + GAP;
+
+fn draw_player(canvas: &mut Canvas, x: usize, y: usize, player: &Player, variant: WallVariant) {
+    canvas.number(x + MARGIN, y + MARGIN, player.points);
+    let top = y + MARGIN + POINTS_HEIGHT;
+    let wall = x + MARGIN + 5 * (CELL + GAP) + GAP;
+    for (row_index, row) in player.rows.iter().enumerate() {
+        let y = top + row_index * (CELL + GAP);
+        // pattern rows are filled from the wall outwards
+        let (tile, count) = row.map_or((None, 0), |(tile, count)| (Some(tile), count));
+        for slot in 0..=row_index {
+            let x = wall - GAP - (slot + 1) * (CELL + GAP);
+            match tile {
+                Some(tile) if slot < count => canvas.tile(x, y, rgb(tile)),
+                _ => canvas.tile(x, y, SLOT),
+            }
+        }
+        for (column_index, tile) in WALL[row_index].iter().enumerate() {
+            let x = wall + column_index * (CELL + GAP);
+            match (player.wall.tile(row_index, column_index), variant) {
+                (Some(tile), _) => canvas.tile(x, y, rgb(tile)),
+                (None, WallVariant::Colored) => canvas.tile(x, y, faint(rgb(*tile))),
+                (None, WallVariant::Free) => canvas.tile(x, y, SLOT),
+            }
+        }
+    }
+    let floor = top + 5 * (CELL + GAP) + GAP;
+    for slot in 0..FLOOR {
+        canvas.tile(x + MARGIN + slot * (CELL + GAP), floor, SLOT);
+    }
+    canvas.tiles(x + MARGIN, floor, &player.floor.tiles, FLOOR);
+}
+
+/// The factories, the center and all player boards, as a PNG image.
+pub fn encode_png(state: &State) -> Vec<u8> {
+    // a factory is a plate of four tiles, shown even when empty
+    let plate = span(2) + 2 * GAP;
+    let factories = state.factories.len();
+    let center_rows = state.center.len().div_ceil(CENTER_WIDTH).max(1);
+    let top = plate.max(span(center_rows));
+    let center = MARGIN + factories * (plate + 2 * GAP) + MARGIN;
+    let width = (center + span(CENTER_WIDTH) + MARGIN).max(state.players.len() * BOARD_WIDTH);
+    let height = MARGIN + top + MARGIN + BOARD_HEIGHT;
+    let mut canvas = Canvas::new(width, height);
+    for (index, tiles) in state.factories.iter().enumerate() {
+        let x = MARGIN + index * (plate + 2 * GAP);
+        canvas.fill(x, MARGIN, plate, plate, SLOT);
+        canvas.tiles(x + GAP, MARGIN + GAP, tiles, 2);
+    }
+    canvas.tiles(center, MARGIN, &state.center, CENTER_WIDTH);
+    let boards = MARGIN + top + MARGIN;
+    for (index, player) in state.players.iter().enumerate() {
+        let x = index * BOARD_WIDTH;
+        draw_player(&mut canvas, x, boards, player, state.variant);
+        if index == state.current_player() && !state.result().is_over() {
+            canvas.frame(
+                x + GAP,
+                boards,
+                BOARD_WIDTH - 2 * GAP,
+                BOARD_HEIGHT - GAP,
+                2,
+            );
+        }
+    }
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    // encoding to memory only fails for images of no pixels
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(&canvas.pixels).unwrap();
+    writer.finish().unwrap();
+    out
+}
+
+/// Writes the factories, the center and all player boards to a PNG image
+/// file.
+pub fn render_png(state: &State, path: impl AsRef<Path>) -> io::Result<()> {
+    fs::write(path, encode_png(state))
+}
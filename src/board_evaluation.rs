@@ -0,0 +1,200 @@
+//! Evaluation looking past the current score: pattern rows about to be
+//! moved to the wall, adjacency on the wall, the floor line and end of game
+//! bonuses, weighted differently once the game may end with the round.
+
+use crate::{
+    azul::{Player, State, Tile, TILES},
+    minmax::Evaluation,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Weights of the terms of `BoardEvaluation`. Every term is measured in
+/// points before it is weighted. Deserializing fills in weights left out
+/// from the default ones.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct Weights {
+    /// Points scored so far
+    pub points: f64,
+    /// Wall points of full pattern rows, scored at the end of the round
+    pub complete_rows: f64,
+    /// Wall points of partly filled pattern rows, scaled by how full they are
+    pub partial_rows: f64,
+    /// Like `partial_rows`, for rows that can't be completed this round
+    /// since too few tiles of their color are left in the factories and the
+    /// center. Weighing these less makes taking the last tiles an opponent
+    /// needs worth something.
+    pub stranded_rows: f64,
+    /// Wall points of partly filled pattern rows between rounds, scaled by
+    /// the chance that the next deal has the tiles missing, instead of how
+    /// full they are
+    pub expected_rows: f64,
+    /// Penalty of the floor line
+    pub floor: f64,
+    /// End of game bonuses, scaled by how close each row, column and color
+    /// is to complete
+    pub bonus: f64,
+    /// How much the score of the best opponent counts against the player
+    pub opponent: f64,
+    /// Value of sharing the victory at the end of the game. Positive values
+    /// seek ties and negative ones avoid them
+    pub contempt: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            points: 1.0,
+            complete_rows: 1.0,
+            partial_rows: 0.5,
+            stranded_rows: 0.25,
+            expected_rows: 1.0,
+            floor: 1.0,
+            bonus: 0.5,
+            opponent: 1.0,
+            contempt: 0.0,
+        }
+    }
+}
+
+impl Weights {
+    /// Weights for when the game may end with the round, see `is_endgame`.
+    /// Rows that won't be finished before the game ends are worth less,
+    /// and bonuses about to be scored more.
+    pub fn endgame() -> Self {
+        Self {
+            partial_rows: 0.25,
+            expected_rows: 0.5,
+            stranded_rows: 0.1,
+            bonus: 0.75,
+            ..Self::default()
+        }
+    }
+}
+
+/// Whether a player has four tiles on a row of the wall, so that the game
+/// may end with this round.
+pub fn is_endgame(state: &State) -> bool {
+    state
+        .players
+        .iter()
+        .any(|player| (0..5).any(|row| player.wall.len_row(row) >= 4))
+}
+
+/// Evaluation of a position by a weighted sum of board features, scored for
+/// the player minus the best scoring opponent. Values are in hundredths of a
+/// point.
+#[derive(Clone, Debug)]
+pub struct BoardEvaluation {
+    pub weights: Weights,
+    /// Weights used instead once the game may end with the round
+    pub endgame: Weights,
+}
+
+impl Default for BoardEvaluation {
+    fn default() -> Self {
+        Self {
+            weights: Weights::default(),
+            endgame: Weights::endgame(),
+        }
+    }
+}
+
+impl BoardEvaluation {
+    /// An evaluation with the same weights all game long.
+    pub fn new(weights: Weights) -> Self {
+        Self {
+            endgame: weights.clone(),
+            weights,
+        }
+    }
+
+    // weighted wall points of full and partly filled pattern rows. Full
+    // rows are scored together, as at the end of the round. Between rounds
+    // partly filled rows count by their chance of being completed in the
+    // next, so that the value doesn't hinge on sampling the deal, and
+    // during a round by whether the tiles left can complete them
+    fn rows(weights: &Weights, player: &Player, state: &State) -> f64 {
+        let mut value = weights.complete_rows * player.pending_points() as f64;
+        for (row_index, row) in player.rows.iter().enumerate() {
+            let Some((tile, count)) = *row else {
+                continue;
+            };
+            let missing = row_index + 1 - count;
+            if missing == 0 {
+                continue;
+            }
+            let points = player.points_for(&state.variant, row_index, tile) as f64;
+            let full = count as f64 / (row_index + 1) as f64;
+            value += match state.needs_deal() {
+                true => weights.expected_rows * points * dealt_at_least(state, tile, missing),
+                false if left_in_round(state, tile) < missing => {
+                    weights.stranded_rows * points * full
+                }
+                false => weights.partial_rows * points * full,
+            };
+        }
+        value
+    }
+
+    // end of game bonuses, each scaled by the square of how much is done
+    fn bonus(player: &Player) -> f64 {
+        let progress = |count: usize| (count as f64 / 5.0).powi(2);
+        let rows: f64 = (0..5).map(|row| progress(player.wall.len_row(row))).sum();
+        let columns: f64 = (0..5)
+            .map(|column| progress(player.wall.len_column(column)))
+            .sum();
+        let colors: f64 = TILES
+            .iter()
+            .map(|tile| progress(player.wall.len_color(tile)))
+            .sum();
+        2.0 * rows + 7.0 * columns + 10.0 * colors
+    }
+
+    fn score(weights: &Weights, player: &Player, state: &State) -> f64 {
+        weights.points * player.points as f64 + Self::rows(weights, player, state)
+            - weights.floor * player.floor.penalty() as f64
+            + weights.bonus * Self::bonus(player)
+    }
+}
+
+// chance that the next deal puts at least `wanted` of the tile in the
+// factories
+fn dealt_at_least(state: &State, tile: Tile, wanted: usize) -> f64 {
+    let distribution = state.draw_distribution(tile);
+    distribution.iter().skip(wanted).sum::<f64>().min(1.0)
+}
+
+// tiles of the color that can still be taken this round
+fn left_in_round(state: &State, tile: Tile) -> usize {
+    state
+        .factories
+        .iter()
+        .chain([&state.center])
+        .map(|tiles| tiles.get(tile))
+        .sum()
+}
+
+impl Evaluation<State> for BoardEvaluation {
+    fn evaulate(&self, state: &State, player: usize) -> i32 {
+        let weights = match is_endgame(state) {
+            true => &self.endgame,
+            false => &self.weights,
+        };
+        let best_opponent = state
+            .players
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| *index != player)
+            .map(|(_, opponent)| Self::score(weights, opponent, state))
+            .reduce(f64::max)
+            .unwrap_or(0.0);
+        let value =
+            Self::score(weights, &state.players[player], state) - weights.opponent * best_opponent;
+        (100.0 * value).round() as i32
+    }
+    fn contempt(&self) -> i32 {
+        (100.0 * self.weights.contempt).round() as i32
+    }
+}
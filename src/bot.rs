@@ -0,0 +1,267 @@
+//! Playing against the engine in a chat, e.g. on Discord or IRC. A `Bot`
+//! keeps a game for every channel and answers the messages starting with
+//! `!azul`:
+//!
+//! * `!azul new [players]` - start a game against one to three engines,
+//!   two players by default. Whoever starts the game plays it, first
+//! * `!azul <move>` - make a move in move notation, e.g. `!azul F1 B row2`,
+//!   after which the engines make theirs
+//! * `!azul show` - show the boards
+//! * `!azul resign` - give up the game
+//! * `!azul help`
+//!
+//! Replies are text and boards, rendered in plain ASCII. The chat decides
+//! how to send them: `Reply::to_markdown` puts boards in code blocks for
+//! chats with markdown, e.g. as the content of a Discord message, and
+//! `Reply::lines` splits them into lines for chats like IRC, where a
+//! message is a single line. `irc` connects a bot to an IRC server.
+
+use crate::{
+    azul::{describe_move, Move, State},
+    azul_fmt::{render_state, RenderOptions},
+    engine::{self, Dealer, Registered, Spec},
+    minmax::{DeterministicGameState, GameResult},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Write},
+    iter,
+    net::TcpStream,
+    thread,
+    time::Duration,
+};
+
+// messages for the bot start with this
+const PREFIX: &str = "!azul";
+
+// pause between lines sent to IRC, as servers disconnect clients flooding
+// them
+const IRC_PAUSE: Duration = Duration::from_millis(300);
+
+const HELP: &str = "!azul new [players] starts a game against the engine, \
+!azul <move> e.g. !azul F1 B row2 makes a move, !azul show shows the boards \
+and !azul resign gives up";
+
+/// Something for the bot to say.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Reply {
+    Text(String),
+    /// The boards of a game, as lines of plain text
+    Board(String),
+}
+
+impl Reply {
+    /// As a message of a chat with markdown, with boards in a code block.
+    pub fn to_markdown(&self) -> String {
+        match self {
+            Reply::Text(text) => text.clone(),
+            Reply::Board(board) => format!("```\n{}\n```", board),
+        }
+    }
+
+    /// As single line messages, for chats without formatting.
+    pub fn lines(&self) -> Vec<String> {
+        match self {
+            Reply::Text(text) => vec![text.clone()],
+            Reply::Board(board) => board
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+}
+
+// a game in a channel, with the user playing the first seat
+struct Game {
+    state: State,
+    user: String,
+    dealer: Dealer,
+}
+
+impl Game {
+    // the user, and the engines by seat if there are several
+    fn names(&self, engine: &Spec) -> Vec<String> {
+        let players = self.state.players.len();
+        let engines = (1..players).map(|seat| match players {
+            2 => engine.to_string(),
+            _ => format!("{} #{}", engine, seat + 1),
+        });
+        iter::once(self.user.clone()).chain(engines).collect()
+    }
+
+    fn board(&self, engine: &Spec) -> Reply {
+        let names = self.names(engine);
+        let names: Vec<_> = names.iter().map(String::as_str).collect();
+        Reply::Board(render_state(&self.state, &names, &RenderOptions::plain()))
+    }
+
+    fn result(&self, engine: &Spec) -> Reply {
+        let names = self.names(engine);
+        let points: Vec<_> = names
+            .iter()
+            .zip(&self.state.players)
+            .map(|(name, player)| format!("{} {}", name, player.points))
+            .collect();
+        let result = match self.state.result() {
+            GameResult::Won { player, .. } => format!("{} wins", names[player]),
+            _ => "the victory is shared".to_string(),
+        };
+        Reply::Text(format!("game over, {}: {}", points.join(", "), result))
+    }
+}
+
+/// Games in chat channels against an engine of the registry.
+pub struct Bot {
+    engine: Spec,
+    // the engine itself, playing every game
+    player: Registered,
+    games: HashMap<String, Game>,
+    rng: StdRng,
+}
+
+impl Bot {
+    /// A bot playing `engine`, with a generator seeded by `seed`.
+    pub fn new(engine: Spec, seed: u64) -> Self {
+        Self {
+            player: engine.build(),
+            engine,
+            games: HashMap::new(),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// The position of the game in the channel, if any.
+    pub fn state(&self, channel: &str) -> Option<&State> {
+        self.games.get(channel).map(|game| &game.state)
+    }
+
+    /// The replies to a message from `user` in `channel`, none unless it's
+    /// for the bot.
+    pub fn handle(&mut self, channel: &str, user: &str, message: &str) -> Vec<Reply> {
+        let Some(command) = message.trim().strip_prefix(PREFIX) else {
+            return Vec::new();
+        };
+        // e.g. !azulbot isn't for the bot
+        if !command.is_empty() && !command.starts_with(' ') {
+            return Vec::new();
+        }
+        let command = command.trim();
+        let (word, arguments) = command.split_once(' ').unwrap_or((command, ""));
+        let game = self.games.get(channel);
+        let text = |text: String| vec![Reply::Text(text)];
+        match (word, game) {
+            ("" | "help", _) => text(HELP.to_string()),
+            ("new", Some(game)) if game.user != user => text(format!(
+                "{} is playing here, wait for the game to end",
+                game.user
+            )),
+            ("new", _) => match arguments.trim() {
+                "" => self.start(channel, user, 2),
+                players => match players.parse() {
+                    Ok(players @ 2..=4) => self.start(channel, user, players),
+                    _ => text("a game has two to four players".to_string()),
+                },
+            },
+            (_, None) => text("no game here, start one with !azul new".to_string()),
+            ("show", Some(game)) => vec![game.board(&self.engine)],
+            (_, Some(game)) if game.user != user => text(format!("it's {}'s game", game.user)),
+            ("resign", Some(_)) => {
+                self.games.remove(channel);
+                text(format!("{} resigns", user))
+            }
+            _ => self.play(channel, command),
+        }
+    }
+
+    fn start(&mut self, channel: &str, user: &str, players: usize) -> Vec<Reply> {
+        let mut game = Game {
+            state: State::new(players),
+            user: user.to_string(),
+            dealer: Dealer::new(self.rng.gen()),
+        };
+        game.dealer.deal(&mut game.state);
+        let board = game.board(&self.engine);
+        self.games.insert(channel.to_string(), game);
+        vec![
+            Reply::Text(format!("{} plays {}, your move", user, self.engine)),
+            board,
+        ]
+    }
+
+    // the user's move, and the engines' moves after it
+    fn play(&mut self, channel: &str, text: &str) -> Vec<Reply> {
+        // only called with a game
+        let game = self.games.get_mut(channel).unwrap();
+        let m = match Move::parse(text) {
+            Ok(m) => m,
+            Err(error) => return vec![Reply::Text(error.to_string())],
+        };
+        let mut state = match game.state.apply(&m) {
+            Ok(state) => state,
+            Err(error) => return vec![Reply::Text(error.to_string())],
+        };
+        game.dealer.deal(&mut state);
+        game.state = state;
+        let names = game.names(&self.engine);
+        let mut replies = Vec::new();
+        while game.state.current_player() != 0 && !game.state.result().is_over() {
+            let player = game.state.current_player();
+            let before = game.state.clone();
+            game.state = engine::play(&mut self.player, &before, &mut self.rng, &mut game.dealer);
+            if let Some(description) = describe_move(&before, &game.state) {
+                let text = format!("{} {}", names[player], description);
+                replies.push(Reply::Text(text));
+            }
+        }
+        replies.push(game.board(&self.engine));
+        if game.state.result().is_over() {
+            replies.push(game.result(&self.engine));
+            self.games.remove(channel);
+        }
+        replies
+    }
+}
+
+/// Connects the bot to an IRC server, e.g. `irc.libera.chat:6667`, as
+/// `nick`, and plays in the channels until the server closes the
+/// connection. Private messages get private replies.
+pub fn irc(bot: &mut Bot, server: &str, nick: &str, channels: &[String]) -> io::Result<()> {
+    let mut out = TcpStream::connect(server)?;
+    let input = BufReader::new(out.try_clone()?);
+    write!(out, "NICK {}\r\nUSER {} 0 * :azul\r\n", nick, nick)?;
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim_end_matches('\r');
+        let (prefix, rest) = match line.strip_prefix(':') {
+            Some(line) => line.split_once(' ').unwrap_or((line, "")),
+            None => ("", line),
+        };
+        let (command, parameters) = rest.split_once(' ').unwrap_or((rest, ""));
+        match command {
+            "PING" => write!(out, "PONG {}\r\n", parameters)?,
+            // welcome, once registered
+            "001" => {
+                for channel in channels {
+                    write!(out, "JOIN {}\r\n", channel)?;
+                }
+            }
+            "PRIVMSG" => {
+                let Some((target, message)) = parameters.split_once(" :") else {
+                    continue;
+                };
+                let user = prefix.split('!').next().unwrap_or(prefix);
+                let channel = if target == nick { user } else { target };
+                for reply in bot.handle(channel, user, message) {
+                    for line in reply.lines() {
+                        write!(out, "PRIVMSG {} :{}\r\n", channel, line)?;
+                        thread::sleep(IRC_PAUSE);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
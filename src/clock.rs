@@ -0,0 +1,139 @@
+//! Time controls: every player has a clock that runs while they think, and
+//! loses the game when it runs out.
+
+use crate::{azul::State, minmax::DeterministicGameState};
+use std::{fmt, str::FromStr, time::Duration};
+
+// roughly the moves a player still has to make in a game of Azul
+const MOVES_AHEAD: u32 = 15;
+
+/// Time for the whole game and time added after every move, written as in
+/// chess, e.g. `5+3` for five minutes and three seconds a move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub increment: Duration,
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}+{}",
+            self.base.as_secs_f64() / 60.0,
+            self.increment.as_secs_f64()
+        )
+    }
+}
+
+impl FromStr for TimeControl {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (base, increment) = text.split_once('+').unwrap_or((text, "0"));
+        let seconds = |text: &str, unit: f64| match text.trim().parse::<f64>() {
+            Ok(value) if value >= 0.0 && value.is_finite() => {
+                Ok(Duration::from_secs_f64(value * unit))
+            }
+            _ => Err(format!("bad time control '{}', expected e.g. 5+3", text)),
+        };
+        Ok(Self {
+            base: seconds(base, 60.0)?,
+            increment: seconds(increment, 1.0)?,
+        })
+    }
+}
+
+/// The clocks of the players of a game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Clock {
+    control: TimeControl,
+    remaining: Vec<Duration>,
+    flagged: Option<usize>,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl, players: usize) -> Self {
+        Self {
+            control,
+            remaining: vec![control.base; players],
+            flagged: None,
+        }
+    }
+
+    /// Clocks showing `remaining` for each player, e.g. of a saved game.
+    pub fn with_remaining(control: TimeControl, remaining: Vec<Duration>) -> Self {
+        Self {
+            control,
+            remaining,
+            flagged: None,
+        }
+    }
+
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    /// Time left on the clock of every player.
+    pub fn remaining(&self) -> &[Duration] {
+        &self.remaining
+    }
+
+    /// The player whose time ran out, if any.
+    pub fn flagged(&self) -> Option<usize> {
+        self.flagged
+    }
+
+    /// Time for the player to think about a move: a share of the time left
+    /// and the increment, but never all of the time left.
+    pub fn budget(&self, player: usize) -> Duration {
+        let remaining = self.remaining[player];
+        (remaining / MOVES_AHEAD + self.control.increment).min(remaining / 2)
+    }
+
+    /// Stops the clock of the player after a move that took `elapsed`, and
+    /// adds the increment. Returns false if the time ran out, which loses
+    /// the game.
+    pub fn punch(&mut self, player: usize, elapsed: Duration) -> bool {
+        match self.remaining[player].checked_sub(elapsed) {
+            Some(remaining) if !remaining.is_zero() => {
+                self.remaining[player] = remaining + self.control.increment;
+                true
+            }
+            _ => {
+                self.remaining[player] = Duration::ZERO;
+                self.flagged = Some(player);
+                false
+            }
+        }
+    }
+
+    /// The winner of the game: the player with the most points of those in
+    /// time, once one has run out of time, or else by the rules. None while
+    /// the game goes on and when players share the victory.
+    pub fn winner(&self, state: &State) -> Option<usize> {
+        match self.winners(state)[..] {
+            [winner] => Some(winner),
+            _ => None,
+        }
+    }
+
+    /// Every player sharing the victory, decided like `winner`. Empty while
+    /// the game goes on.
+    pub fn winners(&self, state: &State) -> Vec<usize> {
+        let Some(flagged) = self.flagged else {
+            return state.winners();
+        };
+        let in_time = || (0..state.players.len()).filter(move |player| *player != flagged);
+        let best = in_time().map(|player| state.players[player].points).max();
+        in_time()
+            .filter(|player| Some(state.players[*player].points) == best)
+            .collect()
+    }
+}
+
+/// A clock reading, as minutes and seconds.
+pub fn format_clock(remaining: Duration) -> String {
+    let seconds = remaining.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
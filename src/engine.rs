@@ -0,0 +1,494 @@
+//! Engines behind a common trait, so that any of them can take a seat in a
+//! game or a tournament, and a registry of them by name.
+
+use crate::{
+    azul::{AzulError, Fish, Move, State},
+    board_evaluation::BoardEvaluation,
+    clock::Clock,
+    mcts::Mcts,
+    minmax::{search_with_options, DeterministicGameState, Evaluation, SearchInfo, SearchOptions},
+    observer::Observer,
+    ponder::{expected_position, Ponderer},
+};
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    rngs::StdRng,
+    seq::SliceRandom,
+    Rng, SeedableRng,
+};
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+/// Something that picks moves.
+pub trait Engine {
+    /// The move to make in a state where the game isn't over. The state is
+    /// what every player knows, and `rng` is the engine's own, so it can't
+    /// tell the coming deals.
+    fn choose<R: Rng>(&mut self, state: &State, rng: &mut R) -> Move;
+}
+
+/// Uniformly random legal moves.
+pub struct Random;
+
+impl Engine for Random {
+    fn choose<R: Rng>(&mut self, state: &State, rng: &mut R) -> Move {
+        *state.legal_moves().choose(rng).unwrap()
+    }
+}
+
+/// Minmax to a fixed depth.
+pub struct Minmax<E> {
+    pub depth: usize,
+    pub evaluation: E,
+    /// See `SearchOptions::late_move_reductions`
+    pub late_move_reductions: bool,
+}
+
+impl<E: Evaluation<State>> Engine for Minmax<E> {
+    fn choose<R: Rng>(&mut self, state: &State, rng: &mut R) -> Move {
+        let options = SearchOptions {
+            late_move_reductions: self.late_move_reductions,
+            ..SearchOptions::depth(self.depth)
+        };
+        let (m, _) = search_with_options(state, &mut self.evaluation, rng, &options);
+        m.unwrap()
+    }
+}
+
+/// The move leading to the best evaluated position, without searching any
+/// further. Ties are broken at random.
+pub struct Greedy<E> {
+    pub evaluation: E,
+}
+
+// every legal move with the evaluation of the position after it, for the
+// player making it
+fn move_values(state: &State, evaluation: &impl Evaluation<State>) -> Vec<(Move, i32)> {
+    let player = state.current_player();
+    let mut state = state.clone();
+    state
+        .legal_moves()
+        .into_iter()
+        .map(|m| {
+            let undo = state.apply_in_place(&m);
+            let value = evaluation.evaulate(&state, player);
+            state.undo(undo);
+            (m, value)
+        })
+        .collect()
+}
+
+impl<E: Evaluation<State>> Engine for Greedy<E> {
+    fn choose<R: Rng>(&mut self, state: &State, rng: &mut R) -> Move {
+        let values = move_values(state, &self.evaluation);
+        let best = values.iter().map(|(_, value)| *value).max().unwrap();
+        let moves: Vec<_> = values
+            .into_iter()
+            .filter(|(_, value)| *value == best)
+            .collect();
+        moves.choose(rng).unwrap().0
+    }
+}
+
+/// A weaker opponent for casual play: a shallow search of a noisy
+/// evaluation that now and then makes a random move on purpose.
+pub struct Casual {
+    pub depth: usize,
+    /// Most the evaluation of a position is off by, in hundredths of a
+    /// point
+    pub noise: i32,
+    /// Chance of a random move instead of a searched one
+    pub mistakes: f64,
+}
+
+/// Strongest level of `Casual::level`.
+pub const MAX_LEVEL: u8 = 8;
+
+impl Casual {
+    /// The opponent at a level from 1, a beginner, to `MAX_LEVEL`, which
+    /// plays like `minmax:4`. Panics for other levels.
+    pub fn level(level: u8) -> Self {
+        let (depth, noise, mistakes) = match level {
+            1 => (1, 800, 0.35),
+            2 => (1, 500, 0.25),
+            3 => (1, 300, 0.15),
+            4 => (2, 300, 0.1),
+            5 => (2, 150, 0.05),
+            6 => (3, 100, 0.02),
+            7 => (3, 50, 0.0),
+            8 => (4, 0, 0.0),
+            _ => panic!("levels go from 1 to {}", MAX_LEVEL),
+        };
+        Self {
+            depth,
+            noise,
+            mistakes,
+        }
+    }
+}
+
+impl Engine for Casual {
+    fn choose<R: Rng>(&mut self, state: &State, rng: &mut R) -> Move {
+        if rng.gen_bool(self.mistakes) {
+            return Random.choose(state, rng);
+        }
+        Minmax {
+            depth: self.depth,
+            evaluation: Noisy {
+                evaluation: BoardEvaluation::default(),
+                noise: self.noise,
+                seed: rng.gen(),
+            },
+            late_move_reductions: true,
+        }
+        .choose(state, rng)
+    }
+}
+
+// an evaluation off by up to noise, the same for a position throughout a
+// search so that the search stays consistent
+struct Noisy<E> {
+    evaluation: E,
+    noise: i32,
+    seed: u64,
+}
+
+impl<E: Evaluation<State>> Evaluation<State> for Noisy<E> {
+    fn evaulate(&self, state: &State, player: usize) -> i32 {
+        let value = self.evaluation.evaulate(state, player);
+        if self.noise == 0 {
+            return value;
+        }
+        let hash =
+            (state.zobrist() ^ self.seed ^ player as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        let offset = (hash >> 32) % (2 * self.noise as u64 + 1);
+        value + offset as i32 - self.noise
+    }
+    fn contempt(&self) -> i32 {
+        self.evaluation.contempt()
+    }
+}
+
+/// Randomness in the first moves of a game, so that games between the
+/// same engines don't all start alike.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Temperature {
+    /// How much worse, in points by `BoardEvaluation`, a move can be and
+    /// still be played often. A move worse than the best by this much is
+    /// played e≈2.7 times less often. Zero or less turns sampling off
+    pub temperature: f64,
+    /// Moves of the game, counting every player's, that are sampled
+    pub plies: usize,
+}
+
+/// An engine playing moves sampled by their evaluation in the first moves
+/// of a game, near-best moves more often the better they are, and its own
+/// moves after that.
+pub struct Tempered<E> {
+    pub engine: E,
+    pub temperature: Temperature,
+}
+
+impl<E: Engine> Engine for Tempered<E> {
+    fn choose<R: Rng>(&mut self, state: &State, rng: &mut R) -> Move {
+        let Temperature { temperature, plies } = self.temperature;
+        if temperature <= 0.0 || state.moves >= plies {
+            return self.engine.choose(state, rng);
+        }
+        let values = move_values(state, &BoardEvaluation::default());
+        let best = values.iter().map(|(_, value)| *value).max().unwrap();
+        let weights = values
+            .iter()
+            .map(|(_, value)| ((value - best) as f64 / (100.0 * temperature)).exp());
+        // the best move has weight one, so the weights are valid
+        let distribution = WeightedIndex::new(weights).unwrap();
+        values[distribution.sample(rng)].0
+    }
+}
+
+impl Engine for Mcts {
+    fn choose<R: Rng>(&mut self, state: &State, rng: &mut R) -> Move {
+        self.search(state, rng).unwrap()
+    }
+}
+
+/// Deals of a game, from random numbers of their own. Engines draw from
+/// others, so the deals don't depend on how they search, and nothing they
+/// do with their random numbers tells what the next deal will be.
+pub struct Dealer {
+    rng: StdRng,
+}
+
+impl Dealer {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Deals the factories, if the round is over.
+    pub fn deal(&mut self, state: &mut State) {
+        if state.needs_deal() {
+            state.deal(&mut self.rng);
+        }
+    }
+}
+
+/// The position after the engine's move, dealt by `dealer` if the round
+/// ended.
+pub fn play<E: Engine, R: Rng>(
+    engine: &mut E,
+    state: &State,
+    rng: &mut R,
+    dealer: &mut Dealer,
+) -> State {
+    play_observed(engine, state, rng, dealer, &mut ())
+}
+
+/// Like `play`, telling `observer` about the move, the scoring if it ended
+/// the round, the deal that followed and the end of the game.
+pub fn play_observed<E: Engine, R: Rng>(
+    engine: &mut E,
+    state: &State,
+    rng: &mut R,
+    dealer: &mut Dealer,
+    observer: &mut impl Observer,
+) -> State {
+    let m = engine.choose(state, rng);
+    let (mut next, scoring) = state.apply_scoring(&m).unwrap();
+    observer.on_move(state, &state.describe(&m), &next);
+    if !scoring.is_empty() {
+        observer.on_round_end(&next, &scoring);
+    }
+    if next.needs_deal() {
+        dealer.deal(&mut next);
+        observer.on_deal(&next);
+    }
+    if next.result().is_over() {
+        observer.on_game_over(&next);
+    }
+    next
+}
+
+/// Plays a dealt game to the end, `engines[i]` in seat `i`, a move at a time
+/// like `play_observed`. `on_move` is called with the position before and
+/// after every move, dealt, and the time the engine took. Returns the final
+/// position, or the seat of an engine that broke the rules and how.
+pub fn play_game<E: Engine, R: Rng>(
+    engines: &mut [E],
+    mut state: State,
+    rng: &mut R,
+    dealer: &mut Dealer,
+    observer: &mut impl Observer,
+    mut on_move: impl FnMut(&State, &State, Duration),
+) -> Result<State, (usize, AzulError)> {
+    while !state.result().is_over() {
+        let player = state.current_player();
+        let start = Instant::now();
+        let next = play_observed(&mut engines[player], &state, rng, dealer, observer);
+        next.check_integrity().map_err(|error| (player, error))?;
+        on_move(&state, &next, start.elapsed());
+        state = next;
+    }
+    Ok(state)
+}
+
+/// An engine from the registry, written e.g. `minmax:4` or `random`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Spec {
+    /// Uniformly random legal moves
+    Random,
+    /// The best move one ply deep by `BoardEvaluation`
+    Greedy,
+    /// Minmax to the given depth using `BoardEvaluation`, written
+    /// `minmax:<depth>`, or `minmax:<depth>:nolmr` without late move
+    /// reductions
+    Minmax { depth: usize, reductions: bool },
+    /// Like `Minmax` using `Fish`, written `fish:<depth>`
+    Fish { depth: usize, reductions: bool },
+    /// Monte Carlo tree search with the given number of playouts
+    Mcts(usize),
+    /// `Casual` at the given level
+    Level(u8),
+}
+
+impl fmt::Display for Spec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Spec::Random => f.write_str("random"),
+            Spec::Greedy => f.write_str("greedy"),
+            Spec::Minmax { depth, reductions } => {
+                write!(
+                    f,
+                    "minmax:{}{}",
+                    depth,
+                    if *reductions { "" } else { ":nolmr" }
+                )
+            }
+            Spec::Fish { depth, reductions } => {
+                write!(
+                    f,
+                    "fish:{}{}",
+                    depth,
+                    if *reductions { "" } else { ":nolmr" }
+                )
+            }
+            Spec::Mcts(iterations) => write!(f, "mcts:{}", iterations),
+            Spec::Level(level) => write!(f, "level:{}", level),
+        }
+    }
+}
+
+impl FromStr for Spec {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (name, parameter) = match text.split_once(':') {
+            Some((name, parameter)) => (name, Some(parameter)),
+            None => (text, None),
+        };
+        // searches can turn late move reductions off, e.g. minmax:4:nolmr
+        let (parameter, reductions) = match parameter.and_then(|p| p.strip_suffix(":nolmr")) {
+            Some(parameter) if matches!(name, "minmax" | "fish") => (Some(parameter), false),
+            _ => (parameter, true),
+        };
+        let number = |default: usize| match parameter {
+            None => Ok(default),
+            Some(parameter) => parameter
+                .parse()
+                .map_err(|_| format!("bad number '{}' in '{}'", parameter, text)),
+        };
+        match name {
+            "random" => Ok(Spec::Random),
+            "greedy" => Ok(Spec::Greedy),
+            "minmax" => Ok(Spec::Minmax {
+                depth: number(4)?,
+                reductions,
+            }),
+            "fish" => Ok(Spec::Fish {
+                depth: number(4)?,
+                reductions,
+            }),
+            "mcts" => Ok(Spec::Mcts(number(1000)?)),
+            "level" => match number(4)? {
+                level @ 1.. if level <= MAX_LEVEL as usize => Ok(Spec::Level(level as u8)),
+                _ => Err(format!("levels go from 1 to {}, not '{}'", MAX_LEVEL, text)),
+            },
+            _ => Err(format!(
+                "unknown engine '{}', expected random, greedy, minmax, fish, mcts or level",
+                name
+            )),
+        }
+    }
+}
+
+impl Spec {
+    /// The engine, built once for the moves of a game or more so that it
+    /// keeps what it learns from move to move, e.g. the evaluations `Fish`
+    /// has cached.
+    pub fn build(&self) -> Registered {
+        match self {
+            Spec::Random => Registered::Random(Random),
+            Spec::Greedy => Registered::Greedy(Greedy {
+                evaluation: BoardEvaluation::default(),
+            }),
+            Spec::Minmax { depth, reductions } => Registered::Minmax(Minmax {
+                depth: *depth,
+                evaluation: BoardEvaluation::default(),
+                late_move_reductions: *reductions,
+            }),
+            Spec::Fish { depth, reductions } => Registered::Fish(Minmax {
+                depth: *depth,
+                evaluation: Fish::new(),
+                late_move_reductions: *reductions,
+            }),
+            Spec::Mcts(iterations) => Registered::Mcts(Mcts {
+                iterations: *iterations,
+                ..Mcts::default()
+            }),
+            Spec::Level(level) => Registered::Level(Casual::level(*level)),
+        }
+    }
+}
+
+/// An engine of the registry, built by `Spec::build`.
+pub enum Registered {
+    Random(Random),
+    Greedy(Greedy<BoardEvaluation>),
+    Minmax(Minmax<BoardEvaluation>),
+    Fish(Minmax<Fish>),
+    Mcts(Mcts),
+    Level(Casual),
+}
+
+impl Engine for Registered {
+    fn choose<R: Rng>(&mut self, state: &State, rng: &mut R) -> Move {
+        match self {
+            Registered::Random(engine) => engine.choose(state, rng),
+            Registered::Greedy(engine) => engine.choose(state, rng),
+            Registered::Minmax(engine) => engine.choose(state, rng),
+            Registered::Fish(engine) => engine.choose(state, rng),
+            Registered::Mcts(engine) => engine.choose(state, rng),
+            Registered::Level(engine) => engine.choose(state, rng),
+        }
+    }
+}
+
+/// The engine of a game against people: an engine of the registry, or
+/// else a search as the options say or, in a game with clocks, as long as
+/// its clock allows. With `ponder` the search thinks about the position it
+/// expects next while the people think.
+pub struct Opponent<E> {
+    engine: Option<Registered>,
+    search: Ponderer<State, E>,
+    options: SearchOptions,
+    ponder: bool,
+}
+
+impl<E: Evaluation<State> + Send + 'static> Opponent<E> {
+    pub fn new(
+        engine: Option<Registered>,
+        evaluation: E,
+        options: SearchOptions,
+        ponder: bool,
+    ) -> Self {
+        Self {
+            engine,
+            search: Ponderer::new(evaluation),
+            options,
+            ponder,
+        }
+    }
+
+    /// The move to make, and what the search found out unless an engine of
+    /// the registry chose it.
+    pub fn choose<R: Rng>(
+        &mut self,
+        state: &State,
+        clock: Option<&Clock>,
+        rng: &mut R,
+    ) -> (Move, Option<SearchInfo<Move>>) {
+        if let Some(engine) = &mut self.engine {
+            return (engine.choose(state, rng), None);
+        }
+        let options = match clock {
+            Some(clock) => SearchOptions {
+                determinizations: self.options.determinizations,
+                late_move_reductions: self.options.late_move_reductions,
+                pruning: self.options.pruning,
+                ..SearchOptions::timed(clock.budget(state.current_player()))
+            },
+            None => self.options.clone(),
+        };
+        let (_, info) = self.search.search(state, rng, &options);
+        if self.ponder {
+            if let Some(expected) = expected_position(state, &info.pv) {
+                self.search.ponder(expected, &options, rng.gen());
+            }
+        }
+        (info.pv[0], Some(info))
+    }
+}
@@ -0,0 +1,90 @@
+//! Positions as fixed size vectors of numbers, the input of learned
+//! evaluations. Shared by the [`selfplay`](crate::selfplay) training data
+//! and the evaluations using the models trained on it, so that both see
+//! the same encoding.
+//!
+//! A position is encoded for a player, whose board comes first. Features,
+//! by index:
+//!
+//! | Index     | Feature                                                  |
+//! |-----------|----------------------------------------------------------|
+//! | 0..45     | tiles of each color in each of nine factories, / 4       |
+//! | 45..50    | tiles of each color in the center, / 20                  |
+//! | 50..55    | tiles of each color in the bag, / 20                     |
+//! | 55..60    | tiles of each color in the lid, / 20                     |
+//! | 60        | 1 if the player is to move, else 0                       |
+//! | 61..269   | four boards of [`BOARD`] features, the player's first and then the following players in turn order |
+//!
+//! Each board is:
+//!
+//! | Index     | Feature                                                  |
+//! |-----------|----------------------------------------------------------|
+//! | 0..25     | each pattern row, by color, how full it is with the color |
+//! | 25..50    | the wall, row by row, 1 where there is a tile            |
+//! | 50        | tiles on the floor line, / 7                             |
+//! | 51        | points, / 100                                            |
+//!
+//! Colors are in the order black, white, azul, yellow, red. Factories and
+//! boards missing in games with fewer players are zero.
+
+use crate::{
+    azul::{Player, State, TileSet, TILES},
+    minmax::DeterministicGameState,
+};
+
+/// Features of a board.
+pub const BOARD: usize = 25 + 25 + 1 + 1;
+
+/// Index of the first board.
+pub const BOARDS: usize = 9 * 5 + 3 * 5 + 1;
+
+/// Number of features of a position.
+pub const FEATURES: usize = BOARDS + 4 * BOARD;
+
+fn tiles(features: &mut Vec<f32>, tiles: &TileSet, scale: f32) {
+    features.extend(TILES.map(|tile| tiles.get(tile) as f32 / scale));
+}
+
+fn board(features: &mut Vec<f32>, player: &Player) {
+    for (row_index, row) in player.rows.iter().enumerate() {
+        let fill = |tile| match row {
+            Some((filled, count)) if *filled == tile => *count as f32 / (row_index + 1) as f32,
+            _ => 0.0,
+        };
+        features.extend(TILES.map(fill));
+    }
+    for row_index in 0..5 {
+        for column_index in 0..5 {
+            let placed = player.wall.tile(row_index, column_index).is_some();
+            features.push(if placed { 1.0 } else { 0.0 });
+        }
+    }
+    features.push(player.floor.len() as f32 / 7.0);
+    features.push(player.points as f32 / 100.0);
+}
+
+/// The position as [`FEATURES`] numbers for `player`, see the module
+/// documentation.
+pub fn encode(state: &State, player: usize) -> Vec<f32> {
+    let mut features = Vec::with_capacity(FEATURES);
+    for index in 0..9 {
+        let factory = state.factories.get(index).copied();
+        tiles(&mut features, &factory.unwrap_or(TileSet::new()), 4.0);
+    }
+    for tileset in [&state.center, &state.bag, &state.lid] {
+        tiles(&mut features, tileset, 20.0);
+    }
+    features.push(if state.current_player() == player {
+        1.0
+    } else {
+        0.0
+    });
+    let players = state.players.len();
+    for seat in 0..4 {
+        match seat < players {
+            true => board(&mut features, &state.players[(player + seat) % players]),
+            false => features.extend([0.0; BOARD]),
+        }
+    }
+    features
+}
@@ -0,0 +1,351 @@
+//! C API for embedding the engine in other programs, e.g. game clients
+//! made with Unity or Godot. Built with `--features ffi`, which also
+//! generates the header `include/azul.h`.
+//!
+//! A game is an opaque `AzulGame` handle from `azul_new` that must be
+//! released with `azul_free`. Moves are plain `AzulMove` structs.
+
+use crate::{
+    azul::{AzulError, Destination, Move, MoveError, Origin, State, Tile},
+    board_evaluation::BoardEvaluation,
+    minmax::{search, DeterministicGameState, GameResult},
+};
+use rand::{rngs::StdRng, SeedableRng};
+use std::{
+    ffi::{c_char, CStr, CString},
+    ptr, slice,
+};
+
+/// A game in progress.
+pub struct AzulGame {
+    state: State,
+    rng: StdRng,
+}
+
+/// A move. `origin` is a factory index or -1 for the center, `tile` is
+/// 0 black, 1 white, 2 azul, 3 yellow or 4 red and `destination` is a
+/// pattern row index or -1 for the floor line. `column` is the wall column
+/// chosen when completing a row in the free variant, otherwise -1. Indices
+/// start at 0.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct AzulMove {
+    pub origin: i32,
+    pub tile: i32,
+    pub destination: i32,
+    pub column: i32,
+}
+
+/// Result of calls that can fail.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AzulStatus {
+    Ok,
+    /// A null pointer or a move with out of range values
+    InvalidArgument,
+    GameOver,
+    NoSuchFactory,
+    NoSuchRow,
+    NoSuchColumn,
+    TileNotAvailable,
+    RowNotAvailable,
+    /// The position breaks the rules
+    InvalidState,
+}
+
+/// How a game stands, see `azul_result`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AzulResult {
+    Ongoing,
+    Won,
+    /// The victory is shared
+    Draw,
+}
+
+impl From<MoveError> for AzulStatus {
+    fn from(error: MoveError) -> Self {
+        match error {
+            MoveError::GameOver => AzulStatus::GameOver,
+            MoveError::NoSuchFactory => AzulStatus::NoSuchFactory,
+            MoveError::NoSuchRow => AzulStatus::NoSuchRow,
+            MoveError::NoSuchColumn => AzulStatus::NoSuchColumn,
+            MoveError::TileNotAvailable => AzulStatus::TileNotAvailable,
+            MoveError::RowNotAvailable => AzulStatus::RowNotAvailable,
+            // moves are passed without descriptions
+            MoveError::NotYourTurn | MoveError::WrongCount => AzulStatus::InvalidArgument,
+        }
+    }
+}
+
+impl From<AzulError> for AzulStatus {
+    fn from(error: AzulError) -> Self {
+        match error {
+            AzulError::Move(error) => error.into(),
+            _ => AzulStatus::InvalidState,
+        }
+    }
+}
+
+impl From<Move> for AzulMove {
+    fn from(m: Move) -> Self {
+        AzulMove {
+            origin: match m.origin {
+                Origin::Factory(index) => index as i32,
+                Origin::Center => -1,
+            },
+            tile: m.tile as i32,
+            destination: match m.destination {
+                Destination::Row(index) | Destination::Wall(index, _) => index as i32,
+                Destination::Floor => -1,
+            },
+            column: match m.destination {
+                Destination::Wall(_, column) => column as i32,
+                _ => -1,
+            },
+        }
+    }
+}
+
+impl TryFrom<AzulMove> for Move {
+    type Error = AzulStatus;
+
+    fn try_from(m: AzulMove) -> Result<Self, Self::Error> {
+        let origin = match m.origin {
+            -1 => Origin::Center,
+            index => Origin::Factory(index.try_into().or(Err(AzulStatus::InvalidArgument))?),
+        };
+        let tile = usize::try_from(m.tile)
+            .ok()
+            .and_then(|tile| Tile::try_from(tile).ok())
+            .ok_or(AzulStatus::InvalidArgument)?;
+        let index = |value: i32| usize::try_from(value).or(Err(AzulStatus::InvalidArgument));
+        let destination = match (m.destination, m.column) {
+            (-1, -1) => Destination::Floor,
+            (row, -1) => Destination::Row(index(row)?),
+            (row, column) => Destination::Wall(index(row)?, index(column)?),
+        };
+        Ok(Move {
+            origin,
+            tile,
+            destination,
+        })
+    }
+}
+
+/// A new, dealt game. Deals are random from `seed`. Null unless there are
+/// two to four players.
+#[no_mangle]
+pub extern "C" fn azul_new(players: usize, seed: u64) -> *mut AzulGame {
+    if !(2..=4).contains(&players) {
+        return ptr::null_mut();
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut state = State::new(players);
+    state.deal(&mut rng);
+    Box::into_raw(Box::new(AzulGame { state, rng }))
+}
+
+/// A game from a position in notation, or null if the notation is invalid.
+///
+/// # Safety
+/// `notation` must be a valid, nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn azul_from_notation(notation: *const c_char, seed: u64) -> *mut AzulGame {
+    if notation.is_null() {
+        return ptr::null_mut();
+    }
+    let Ok(notation) = CStr::from_ptr(notation).to_str() else {
+        return ptr::null_mut();
+    };
+    let Ok(mut state) = State::from_notation(notation) else {
+        return ptr::null_mut();
+    };
+    let mut rng = StdRng::seed_from_u64(seed);
+    if state.needs_deal() {
+        state.deal(&mut rng);
+    }
+    Box::into_raw(Box::new(AzulGame { state, rng }))
+}
+
+/// Releases a game. Null is ignored.
+///
+/// # Safety
+/// `game` must come from `azul_new` or `azul_from_notation` and not be
+/// used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn azul_free(game: *mut AzulGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+/// Writes up to `capacity` legal moves to `moves` and returns the number of
+/// legal moves, which may be more than `capacity`. Pass a null `moves` to
+/// only count them.
+///
+/// # Safety
+/// `game` must be a valid game and `moves` must have room for `capacity`
+/// moves.
+#[no_mangle]
+pub unsafe extern "C" fn azul_legal_moves(
+    game: *const AzulGame,
+    moves: *mut AzulMove,
+    capacity: usize,
+) -> usize {
+    let Some(game) = game.as_ref() else {
+        return 0;
+    };
+    let legal = game.state.legal_moves();
+    if !moves.is_null() {
+        let out = slice::from_raw_parts_mut(moves, capacity);
+        for (slot, m) in out.iter_mut().zip(&legal) {
+            *slot = (*m).into();
+        }
+    }
+    legal.len()
+}
+
+/// Makes a move, dealing a new round if needed.
+///
+/// # Safety
+/// `game` must be a valid game.
+#[no_mangle]
+pub unsafe extern "C" fn azul_apply(game: *mut AzulGame, m: AzulMove) -> AzulStatus {
+    let Some(game) = game.as_mut() else {
+        return AzulStatus::InvalidArgument;
+    };
+    let m = match Move::try_from(m) {
+        Ok(m) => m,
+        Err(status) => return status,
+    };
+    match game.state.apply(&m) {
+        Ok(mut state) => {
+            if state.needs_deal() {
+                state.deal(&mut game.rng);
+            }
+            game.state = state;
+            AzulStatus::Ok
+        }
+        Err(error) => error.into(),
+    }
+}
+
+/// Searches `depth` moves ahead and writes the engine's move to `best`,
+/// without making it.
+///
+/// # Safety
+/// `game` must be a valid game and `best` point to a move.
+#[no_mangle]
+pub unsafe extern "C" fn azul_best_move(
+    game: *mut AzulGame,
+    depth: usize,
+    best: *mut AzulMove,
+) -> AzulStatus {
+    let (Some(game), false) = (game.as_mut(), best.is_null()) else {
+        return AzulStatus::InvalidArgument;
+    };
+    let mut evaluation = BoardEvaluation::default();
+    match search(&game.state, &mut evaluation, &mut game.rng, depth) {
+        Some(m) => {
+            *best = m.into();
+            AzulStatus::Ok
+        }
+        None => AzulStatus::GameOver,
+    }
+}
+
+/// The player to move.
+///
+/// # Safety
+/// `game` must be a valid game.
+#[no_mangle]
+pub unsafe extern "C" fn azul_current_player(game: *const AzulGame) -> usize {
+    game.as_ref().map_or(0, |game| game.state.current_player())
+}
+
+/// The winning player, or -1 while the game is in progress and after a
+/// draw.
+///
+/// # Safety
+/// `game` must be a valid game.
+#[no_mangle]
+pub unsafe extern "C" fn azul_winner(game: *const AzulGame) -> i32 {
+    game.as_ref()
+        .and_then(|game| game.state.winner())
+        .map_or(-1, |winner| winner as i32)
+}
+
+/// Whether the game goes on, was won or ended in a draw.
+///
+/// # Safety
+/// `game` must be a valid game.
+#[no_mangle]
+pub unsafe extern "C" fn azul_result(game: *const AzulGame) -> AzulResult {
+    match game.as_ref().map(|game| game.state.result()) {
+        None | Some(GameResult::Ongoing) => AzulResult::Ongoing,
+        Some(GameResult::Won { .. }) => AzulResult::Won,
+        Some(GameResult::Draw { .. }) => AzulResult::Draw,
+    }
+}
+
+/// Writes up to `capacity` of the players sharing the victory to `winners`
+/// and returns their number, which may be more than `capacity` and is 0
+/// while the game is in progress. Pass a null `winners` to only count them.
+///
+/// # Safety
+/// `game` must be a valid game and `winners` must have room for `capacity`
+/// players.
+#[no_mangle]
+pub unsafe extern "C" fn azul_winners(
+    game: *const AzulGame,
+    winners: *mut usize,
+    capacity: usize,
+) -> usize {
+    let Some(game) = game.as_ref() else {
+        return 0;
+    };
+    let players = game.state.winners();
+    if !winners.is_null() {
+        let out = slice::from_raw_parts_mut(winners, capacity);
+        for (slot, player) in out.iter_mut().zip(&players) {
+            *slot = *player;
+        }
+    }
+    players.len()
+}
+
+/// Points of a player so far.
+///
+/// # Safety
+/// `game` must be a valid game.
+#[no_mangle]
+pub unsafe extern "C" fn azul_points(game: *const AzulGame, player: usize) -> usize {
+    game.as_ref()
+        .and_then(|game| game.state.players.get(player))
+        .map_or(0, |player| player.points)
+}
+
+/// The position in notation. Release it with `azul_free_string`.
+///
+/// # Safety
+/// `game` must be a valid game.
+#[no_mangle]
+pub unsafe extern "C" fn azul_notation(game: *const AzulGame) -> *mut c_char {
+    let Some(game) = game.as_ref() else {
+        return ptr::null_mut();
+    };
+    // notation never contains nul
+    CString::new(game.state.to_notation()).unwrap().into_raw()
+}
+
+/// Releases a string returned by the library. Null is ignored.
+///
+/// # Safety
+/// `string` must come from the library and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn azul_free_string(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
@@ -0,0 +1,86 @@
+//! Game logs in JSON Lines, one object per move as it is played, for
+//! analysis with e.g. jq or pandas:
+//!
+//! ```text
+//! {"ply":0,"player":0,"state":{...},"position":"...","move":"F1 B -> row2","evaluation":125,"time_ms":41.2}
+//! ```
+//!
+//! `state` and `position` are the position before the move, as JSON and in
+//! notation. `evaluation` is the score of the engine's search for the
+//! player moving, in hundredths of a point, and `null` for moves made
+//! without one. `time_ms` is the time taken to decide on the move. Moves
+//! ending the round also have `scoring`, the score events of every player
+//! in seating order.
+
+use crate::{
+    azul::{describe_scoring, ScoreEvent, State},
+    minmax::DeterministicGameState,
+};
+use serde::Serialize;
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::Duration,
+};
+
+#[derive(Serialize)]
+struct Entry<'a> {
+    ply: usize,
+    player: usize,
+    state: &'a State,
+    position: String,
+    #[serde(rename = "move")]
+    m: String,
+    evaluation: Option<i32>,
+    time_ms: f64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    scoring: Vec<Vec<ScoreEvent>>,
+}
+
+/// Writes a line for every move, flushed right away so the log can be
+/// followed while the game is played.
+pub struct GameLog<W: Write> {
+    out: W,
+}
+
+impl GameLog<BufWriter<File>> {
+    /// Logs to a new file, replacing any existing one.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> GameLog<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    /// Logs the move from `before` to `after`. Returns false if there is no
+    /// such move.
+    pub fn push(
+        &mut self,
+        before: &State,
+        after: &State,
+        evaluation: Option<i32>,
+        elapsed: Duration,
+    ) -> io::Result<bool> {
+        let Some((description, scoring)) = describe_scoring(before, after) else {
+            return Ok(false);
+        };
+        let entry = Entry {
+            ply: before.moves,
+            player: before.current_player(),
+            state: before,
+            position: before.to_notation(),
+            m: description.to_move().to_string(),
+            evaluation,
+            time_ms: elapsed.as_secs_f64() * 1000.0,
+            scoring,
+        };
+        serde_json::to_writer(&mut self.out, &entry)?;
+        writeln!(self.out)?;
+        self.out.flush()?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,80 @@
+//! Positions of a game so far, with undo and redo.
+
+use crate::minmax::DeterministicGameState;
+
+/// The positions of a game, from the first to the current one. Undone
+/// positions are kept until a different one is pushed, so they can be
+/// redone.
+#[derive(Clone, Debug)]
+pub struct History<S> {
+    states: Vec<S>,
+    // index of the current position in states
+    current: usize,
+}
+
+impl<S> History<S> {
+    pub fn new(initial: S) -> Self {
+        Self {
+            states: vec![initial],
+            current: 0,
+        }
+    }
+
+    pub fn current(&self) -> &S {
+        &self.states[self.current]
+    }
+
+    /// Makes `state` the current position, forgetting any undone positions.
+    pub fn push(&mut self, state: S) {
+        self.states.truncate(self.current + 1);
+        self.states.push(state);
+        self.current += 1;
+    }
+
+    /// Steps back one position. Returns false if already at the first.
+    pub fn undo(&mut self) -> bool {
+        if self.current == 0 {
+            return false;
+        }
+        self.current -= 1;
+        true
+    }
+
+    /// Steps forward to the next undone position. Returns false if nothing
+    /// was undone.
+    pub fn redo(&mut self) -> bool {
+        if self.current + 1 == self.states.len() {
+            return false;
+        }
+        self.current += 1;
+        true
+    }
+
+    /// The positions from the first up to and including the current one.
+    pub fn states(&self) -> &[S] {
+        &self.states[..=self.current]
+    }
+}
+
+impl<S: DeterministicGameState> History<S> {
+    /// Steps back one position, and on until `player` is to move, e.g. to
+    /// take back the last move of a person together with the replies of
+    /// the engines. Returns false if already at the first.
+    pub fn undo_to(&mut self, player: usize) -> bool {
+        if !self.undo() {
+            return false;
+        }
+        while self.current().current_player() != player && self.undo() {}
+        true
+    }
+
+    /// Steps forward one undone position, and on until `player` is to
+    /// move. Returns false if nothing was undone.
+    pub fn redo_to(&mut self, player: usize) -> bool {
+        if !self.redo() {
+            return false;
+        }
+        while self.current().current_player() != player && self.redo() {}
+        true
+    }
+}
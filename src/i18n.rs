@@ -0,0 +1,356 @@
+//! Translations of the text shown to players: move descriptions, score
+//! explanations and the prompts of the command line.
+//!
+//! Messages are looked up by `Text` in a catalog for every `Language`, with
+//! `{}` for their arguments, in order. Moves, score events and colors
+//! display in English, as `Language::English` writes them:
+//!
+//! ```
+//! use azul::i18n::{Language, Text};
+//!
+//! let language: Language = "sv".parse().unwrap();
+//! assert_eq!(language.format(Text::Points, &[&"Ada", &42]), "Ada: 42 poäng");
+//! ```
+
+use crate::azul::{Destination, MoveDescription, Origin, ScoreEvent, Tile};
+use std::{fmt, str::FromStr};
+
+/// A language of the catalog.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Language {
+    #[default]
+    English,
+    Swedish,
+    German,
+}
+
+/// Every language, in the order of `Language`.
+pub const LANGUAGES: [Language; 3] = [Language::English, Language::Swedish, Language::German];
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Language::English => "en",
+            Language::Swedish => "sv",
+            Language::German => "de",
+        })
+    }
+}
+
+impl FromStr for Language {
+    type Err = String;
+
+    /// A language by its code, e.g. `sv`, or its name.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.to_ascii_lowercase().as_str() {
+            "en" | "english" => Ok(Language::English),
+            "sv" | "swedish" | "svenska" => Ok(Language::Swedish),
+            "de" | "german" | "deutsch" => Ok(Language::German),
+            _ => Err(format!(
+                "unknown language '{}', expected en, sv or de",
+                text
+            )),
+        }
+    }
+}
+
+/// The messages of the catalog.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Text {
+    /// A move: count, tiles, origin and destination
+    Took,
+    Factory,
+    Center,
+    Row,
+    /// Row and wall column, in the free variant
+    RowForColumn,
+    Floor,
+    /// A tile placed on the wall: tile, row, column and points
+    Placed,
+    /// Tiles of a row without a place on the wall: tile and row
+    Discarded,
+    FloorPenalty,
+    RowBonus,
+    ColumnBonus,
+    /// Bonus for all tiles of a color, given as tiles
+    ColorBonus,
+    /// Asking the human for a move
+    YourMove,
+    ShowWhichColor,
+    SaveWhere,
+    NumberBetween,
+    NotLegal,
+    Hint,
+    OneHintTaken,
+    HintsTaken,
+    Expecting,
+    /// How the points of a player changed in a round
+    Scores,
+    Dealt,
+    Points,
+    Wins,
+    WinsOnTime,
+    /// The players sharing the victory, joined by `And`
+    ShareVictory,
+    And,
+    OutOfTime,
+    Saved,
+    /// Default names of the players of `play`
+    You,
+    Engine,
+}
+
+fn english(text: Text) -> &'static str {
+    match text {
+        Text::Took => "took {} {} from {} to {}",
+        Text::Factory => "factory {}",
+        Text::Center => "the center",
+        Text::Row => "row {}",
+        Text::RowForColumn => "row {} for column {}",
+        Text::Floor => "the floor",
+        Text::Placed => "{} on row {}, column {}: +{}",
+        Text::Discarded => "{} of row {} to the floor: no place on the wall",
+        Text::FloorPenalty => "floor: -{}",
+        Text::RowBonus => "row {} complete: +2",
+        Text::ColumnBonus => "column {} complete: +7",
+        Text::ColorBonus => "all {} tiles: +10",
+        Text::YourMove => "your move, by number or e.g. F1 B -> row2 (or hint, show <color>, undo, redo, save <file>)> ",
+        Text::ShowWhichColor => "show which color? black, white, azul, yellow or red",
+        Text::SaveWhere => "save to which file?",
+        Text::NumberBetween => "enter a number between 1 and {}",
+        Text::NotLegal => "{} is not a legal move",
+        Text::Hint => "hint: {} ({}), score {}",
+        Text::OneHintTaken => "you took 1 hint",
+        Text::HintsTaken => "you took {} hints",
+        Text::Expecting => "expecting {}",
+        Text::Scores => "{} scores {}",
+        Text::Dealt => "dealt {}",
+        Text::Points => "{}: {} points",
+        Text::Wins => "{} wins",
+        Text::WinsOnTime => "{} wins on time",
+        Text::ShareVictory => "{} share the victory",
+        Text::And => "and",
+        Text::OutOfTime => "{} ran out of time",
+        Text::Saved => "saved to {}",
+        Text::You => "You",
+        Text::Engine => "Engine",
+    }
+}
+
+fn swedish(text: Text) -> &'static str {
+    match text {
+        Text::Took => "tog {} {} från {} till {}",
+        Text::Factory => "fabrik {}",
+        Text::Center => "mitten",
+        Text::Row => "rad {}",
+        Text::RowForColumn => "rad {} för kolumn {}",
+        Text::Floor => "golvet",
+        Text::Placed => "{} på rad {}, kolumn {}: +{}",
+        Text::Discarded => "{} från rad {} till golvet: ingen plats på väggen",
+        Text::FloorPenalty => "golv: -{}",
+        Text::RowBonus => "rad {} klar: +2",
+        Text::ColumnBonus => "kolumn {} klar: +7",
+        Text::ColorBonus => "alla {} plattor: +10",
+        Text::YourMove => "ditt drag, med nummer eller t.ex. F1 B -> row2 (eller hint, show <färg>, undo, redo, save <fil>)> ",
+        Text::ShowWhichColor => "visa vilken färg? svart, vit, blå, gul eller röd",
+        Text::SaveWhere => "spara till vilken fil?",
+        Text::NumberBetween => "ange ett nummer mellan 1 och {}",
+        Text::NotLegal => "{} är inte ett tillåtet drag",
+        Text::Hint => "tips: {} ({}), värdering {}",
+        Text::OneHintTaken => "du tog 1 tips",
+        Text::HintsTaken => "du tog {} tips",
+        Text::Expecting => "väntar sig {}",
+        Text::Scores => "{} får {}",
+        Text::Dealt => "delade ut {}",
+        Text::Points => "{}: {} poäng",
+        Text::Wins => "{} vinner",
+        Text::WinsOnTime => "{} vinner på tid",
+        Text::ShareVictory => "{} delar på segern",
+        Text::And => "och",
+        Text::OutOfTime => "{} fick slut på tid",
+        Text::Saved => "sparat till {}",
+        Text::You => "Du",
+        Text::Engine => "Motor",
+    }
+}
+
+fn german(text: Text) -> &'static str {
+    match text {
+        Text::Took => "nahm {} {} aus {} in {}",
+        Text::Factory => "Fabrik {}",
+        Text::Center => "der Mitte",
+        Text::Row => "Reihe {}",
+        Text::RowForColumn => "Reihe {} für Spalte {}",
+        Text::Floor => "die Bodenreihe",
+        Text::Placed => "{} auf Reihe {}, Spalte {}: +{}",
+        Text::Discarded => "{} aus Reihe {} auf den Boden: kein Platz an der Wand",
+        Text::FloorPenalty => "Boden: -{}",
+        Text::RowBonus => "Reihe {} vollständig: +2",
+        Text::ColumnBonus => "Spalte {} vollständig: +7",
+        Text::ColorBonus => "alle {} Fliesen: +10",
+        Text::YourMove => "dein Zug, als Nummer oder z.B. F1 B -> row2 (oder hint, show <Farbe>, undo, redo, save <Datei>)> ",
+        Text::ShowWhichColor => "welche Farbe zeigen? schwarz, weiß, blau, gelb oder rot",
+        Text::SaveWhere => "in welche Datei speichern?",
+        Text::NumberBetween => "gib eine Zahl zwischen 1 und {} ein",
+        Text::NotLegal => "{} ist kein erlaubter Zug",
+        Text::Hint => "Tipp: {} ({}), Bewertung {}",
+        Text::OneHintTaken => "du hast 1 Tipp genommen",
+        Text::HintsTaken => "du hast {} Tipps genommen",
+        Text::Expecting => "erwartet {}",
+        Text::Scores => "{} erhält {}",
+        Text::Dealt => "ausgeteilt {}",
+        Text::Points => "{}: {} Punkte",
+        Text::Wins => "{} gewinnt",
+        Text::WinsOnTime => "{} gewinnt auf Zeit",
+        Text::ShareVictory => "{} teilen sich den Sieg",
+        Text::And => "und",
+        Text::OutOfTime => "{} hat keine Zeit mehr",
+        Text::Saved => "gespeichert in {}",
+        Text::You => "Du",
+        Text::Engine => "Computer",
+    }
+}
+
+// how the name of a color is used, as languages inflect it differently
+#[derive(Clone, Copy)]
+enum Form {
+    // on its own, e.g. the tile placed on the wall
+    Base,
+    // a number of tiles taken
+    Counted(usize),
+    // all tiles of the color
+    All,
+}
+
+impl Language {
+    /// The message, with `{}` for its arguments.
+    pub fn text(self, text: Text) -> &'static str {
+        match self {
+            Language::English => english(text),
+            Language::Swedish => swedish(text),
+            Language::German => german(text),
+        }
+    }
+
+    /// The message with its `{}` replaced by the arguments, in order.
+    pub fn format(self, text: Text, arguments: &[&dyn fmt::Display]) -> String {
+        let mut parts = self.text(text).split("{}");
+        let mut out = parts.next().unwrap_or("").to_string();
+        for (index, part) in parts.enumerate() {
+            if let Some(argument) = arguments.get(index) {
+                out.push_str(&argument.to_string());
+            }
+            out.push_str(part);
+        }
+        out
+    }
+
+    /// The name of a color.
+    pub fn color(self, tile: Tile) -> &'static str {
+        match self {
+            Language::English => match tile {
+                Tile::BLACK => "black",
+                Tile::WHITE => "white",
+                Tile::AZUL => "azul",
+                Tile::YELLOW => "yellow",
+                Tile::RED => "red",
+            },
+            Language::Swedish => match tile {
+                Tile::BLACK => "svart",
+                Tile::WHITE => "vit",
+                Tile::AZUL => "blå",
+                Tile::YELLOW => "gul",
+                Tile::RED => "röd",
+            },
+            Language::German => german_stem(tile),
+        }
+    }
+
+    fn inflect(self, tile: Tile, form: Form) -> String {
+        match (self, form) {
+            (Language::English, _) | (_, Form::Base) | (Language::Swedish, Form::Counted(1)) => {
+                self.color(tile).to_string()
+            }
+            (Language::Swedish, _) => match tile {
+                Tile::BLACK => "svarta",
+                Tile::WHITE => "vita",
+                Tile::AZUL => "blå",
+                Tile::YELLOW => "gula",
+                Tile::RED => "röda",
+            }
+            .to_string(),
+            (Language::German, Form::Counted(1)) => format!("{}e Fliese", german_stem(tile)),
+            (Language::German, Form::Counted(_)) => format!("{}e Fliesen", german_stem(tile)),
+            (Language::German, Form::All) => format!("{}en", german_stem(tile)),
+        }
+    }
+
+    /// Where tiles are taken from.
+    pub fn origin(self, origin: Origin) -> String {
+        match origin {
+            Origin::Factory(index) => self.format(Text::Factory, &[&(index + 1)]),
+            Origin::Center => self.text(Text::Center).to_string(),
+        }
+    }
+
+    /// Where tiles are put.
+    pub fn destination(self, destination: Destination) -> String {
+        match destination {
+            Destination::Row(index) => self.format(Text::Row, &[&(index + 1)]),
+            Destination::Wall(index, column) => {
+                self.format(Text::RowForColumn, &[&(index + 1), &(column + 1)])
+            }
+            Destination::Floor => self.text(Text::Floor).to_string(),
+        }
+    }
+
+    /// A move, e.g. `took 2 red from factory 1 to row 2`.
+    pub fn describe(self, description: &MoveDescription) -> String {
+        let count = description.count;
+        self.format(
+            Text::Took,
+            &[
+                &count,
+                &self.inflect(description.tile, Form::Counted(count)),
+                &self.origin(description.origin),
+                &self.destination(description.destination),
+            ],
+        )
+    }
+
+    /// How a score event changed the points, e.g. `row 2 complete: +2`.
+    pub fn explain(self, event: &ScoreEvent) -> String {
+        let color = |tile, form| self.inflect(tile, form);
+        match *event {
+            ScoreEvent::Placed {
+                row,
+                column,
+                tile,
+                points,
+            } => self.format(
+                Text::Placed,
+                &[&color(tile, Form::Base), &(row + 1), &(column + 1), &points],
+            ),
+            ScoreEvent::Discarded { row, tile } => {
+                self.format(Text::Discarded, &[&color(tile, Form::Base), &(row + 1)])
+            }
+            ScoreEvent::Floor { penalty } => self.format(Text::FloorPenalty, &[&penalty]),
+            ScoreEvent::RowBonus { row } => self.format(Text::RowBonus, &[&(row + 1)]),
+            ScoreEvent::ColumnBonus { column } => self.format(Text::ColumnBonus, &[&(column + 1)]),
+            ScoreEvent::ColorBonus { tile } => {
+                self.format(Text::ColorBonus, &[&color(tile, Form::All)])
+            }
+        }
+    }
+}
+
+// the color in German, to which adjective endings are added
+fn german_stem(tile: Tile) -> &'static str {
+    match tile {
+        Tile::BLACK => "schwarz",
+        Tile::WHITE => "weiß",
+        Tile::AZUL => "blau",
+        Tile::YELLOW => "gelb",
+        Tile::RED => "rot",
+    }
+}
@@ -0,0 +1,103 @@
+//! An engine for the [Azul](https://en.wikipedia.org/wiki/Azul_(board_game))
+//! board game.
+//!
+//! The rules live in [`azul`], the game agnostic searches in [`minmax`] and
+//! [`mcts`] and terminal rendering in [`azul_fmt`].
+//! A game is played by dealing a [`State`] and then repeatedly applying
+//! [`Move`]s, dealing again whenever a round ends:
+//!
+//! ```
+//! use azul::{DeterministicGameState, State};
+//! use rand::thread_rng;
+//!
+//! let mut rng = thread_rng();
+//! let mut state = State::new(2);
+//! state.deal(&mut rng);
+//! let m = state.legal_moves()[0];
+//! let mut state = state.apply(&m).unwrap();
+//! if state.needs_deal() {
+//!     state.deal(&mut rng);
+//! }
+//! assert_eq!(state.current_player(), 1);
+//! ```
+
+#[cfg(feature = "serde")]
+pub mod annotate;
+pub mod azul;
+pub mod azul_fmt;
+#[cfg(feature = "png")]
+pub mod azul_png;
+pub mod board_evaluation;
+#[cfg(feature = "bot")]
+pub mod bot;
+pub mod clock;
+pub mod engine;
+pub mod features;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "serde")]
+pub mod game_log;
+pub mod history;
+pub mod i18n;
+pub mod linear_evaluation;
+#[cfg(feature = "live")]
+pub mod live;
+pub mod mcts;
+pub mod minmax;
+pub mod notation;
+pub mod observer;
+#[cfg(feature = "onnx")]
+pub mod onnx;
+#[cfg(feature = "serde")]
+pub mod openings;
+pub mod perft;
+#[cfg(feature = "serde")]
+pub mod personas;
+#[cfg(feature = "serde")]
+pub mod play;
+pub mod ponder;
+pub mod protocol;
+#[cfg(feature = "serde")]
+pub mod rating;
+#[cfg(feature = "serde")]
+pub mod record;
+#[cfg(feature = "serde")]
+pub mod report;
+pub mod selfplay;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod simulation;
+#[cfg(feature = "serde")]
+pub mod stats;
+pub mod tournament;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod tune;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "serde")]
+pub mod watch;
+mod zobrist;
+
+pub use crate::azul::{
+    describe_move, describe_scoring, AzulError, Destination, Fish, Move, MoveDescription,
+    MoveError, Origin, Phase, Player, ScoreEvent, State, Tile, Undo, WallVariant,
+};
+pub use crate::azul_fmt::{
+    render_changes, render_state, render_state_with_clocks, RenderOptions, Theme,
+};
+#[cfg(feature = "png")]
+pub use crate::azul_png::{encode_png, render_png};
+pub use crate::board_evaluation::{BoardEvaluation, Weights};
+pub use crate::history::History;
+pub use crate::linear_evaluation::LinearEval;
+pub use crate::mcts::Mcts;
+pub use crate::minmax::{
+    random_move, search, search_move, search_multipv, search_timed, search_timed_with_info,
+    search_with_info, search_with_options, search_with_table, DeterministicGameState, Evaluation,
+    GameResult, SearchInfo, SearchOptions, StochasticGameState, StopToken,
+};
+pub use crate::notation::NotationError;
+#[cfg(feature = "serde")]
+pub use crate::record::{Annotation, GameRecord, Mark, SavedGame};
+pub use crate::simulation::{simulate, SimulatedGame, SimulationReport};
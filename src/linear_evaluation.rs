@@ -0,0 +1,84 @@
+//! Evaluation by a weighted sum of the [`features`](crate::features) of a
+//! position, with weights that can be tuned automatically, see
+//! [`tune`](crate::tune).
+
+use crate::{
+    azul::State,
+    features::{encode, BOARD, BOARDS, FEATURES},
+    minmax::Evaluation,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use std::{fs, io, path::Path};
+
+/// Evaluation by the sum of the features of a position for the player,
+/// each times its weight. Values are in hundredths of a point, so a weight
+/// is the points a feature of one is worth.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinearEval {
+    pub weights: [f32; FEATURES],
+}
+
+// weights in files, as arrays longer than 32 don't serialize
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct File {
+    weights: Vec<f32>,
+}
+
+impl Default for LinearEval {
+    /// The lead in points over the other players.
+    fn default() -> Self {
+        let mut weights = [0.0; FEATURES];
+        // points are scaled down by 100
+        let points = BOARD - 1;
+        weights[BOARDS + points] = 100.0;
+        for seat in 1..4 {
+            weights[BOARDS + seat * BOARD + points] = -100.0;
+        }
+        Self { weights }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl LinearEval {
+    /// Loads weights saved with `save`, or the default ones if there is no
+    /// such file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let json = match fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => return Err(error),
+        };
+        let file: File = serde_json::from_str(&json)?;
+        let weights = file.weights.try_into().map_err(|weights: Vec<f32>| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("expected {} weights, got {}", FEATURES, weights.len()),
+            )
+        })?;
+        Ok(Self { weights })
+    }
+
+    /// Saves the weights as JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = File {
+            weights: self.weights.to_vec(),
+        };
+        fs::write(path, serde_json::to_string(&file)?)
+    }
+}
+
+impl Evaluation<State> for LinearEval {
+    fn evaulate(&self, state: &State, player: usize) -> i32 {
+        let features = encode(state, player);
+        let value: f32 = self
+            .weights
+            .iter()
+            .zip(&features)
+            .map(|(weight, feature)| weight * feature)
+            .sum();
+        (100.0 * value).round() as i32
+    }
+}
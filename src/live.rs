@@ -0,0 +1,330 @@
+//! WebSocket server where two remote players play each other live.
+//!
+//! Clients send and receive JSON text messages. A client first joins a
+//! game by any id it likes, and is seated in the first free seat:
+//!
+//! ```text
+//! {"type": "join", "game": "friday"}
+//! ```
+//!
+//! Once both seats are taken the tiles are dealt and the game starts. The
+//! player to move then sends moves in move notation:
+//!
+//! ```text
+//! {"type": "move", "move": "F1 B -> row2"}
+//! ```
+//!
+//! The server replies to a join with `{"type": "joined", "game", "seat"}`,
+//! and broadcasts `{"type": "state", ...}` with the state, its notation,
+//! the legal moves, the player to move, the winner, the winners sharing a
+//! draw and the last move to both players whenever the game changes. Invalid messages and moves are
+//! answered with `{"type": "error", "message"}` and ignored. A player who
+//! disconnects frees the seat, so they can join again.
+//!
+//! Anyone can watch a game without taking a seat, e.g. engines playing
+//! each other, and is sent the same state messages as the players:
+//!
+//! ```text
+//! {"type": "watch", "game": "friday"}
+//! ```
+//!
+//! The server replies `{"type": "watching", "game"}`, followed by the state
+//! right away if the game has started.
+
+use crate::{
+    azul::{Move, State},
+    minmax::DeterministicGameState,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    io,
+    net::{TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+use tungstenite::{Message, WebSocket};
+
+const SEATS: usize = 2;
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Request {
+    Join {
+        game: String,
+    },
+    Watch {
+        game: String,
+    },
+    Move {
+        #[serde(rename = "move")]
+        m: String,
+    },
+}
+
+fn error(message: impl Into<String>) -> Value {
+    json!({ "type": "error", "message": message.into() })
+}
+
+// a game and the connections of its players, by seat, and spectators
+struct Table {
+    state: State,
+    seats: [Option<Sender<Value>>; SEATS],
+    spectators: Vec<Sender<Value>>,
+    started: bool,
+}
+
+impl Table {
+    fn new() -> Self {
+        Self {
+            state: State::new(SEATS),
+            seats: Default::default(),
+            spectators: Vec::new(),
+            started: false,
+        }
+    }
+
+    fn broadcast(&mut self, message: &Value) {
+        for seat in self.seats.iter().flatten() {
+            // a closed connection is cleaned up by its own thread
+            let _ = seat.send(message.clone());
+        }
+        // spectators hold no seat to free, so they're dropped once gone
+        self.spectators
+            .retain(|spectator| spectator.send(message.clone()).is_ok());
+    }
+
+    fn update(&self, last_move: Option<&Move>) -> Value {
+        let moves: Vec<_> = self
+            .state
+            .legal_moves()
+            .iter()
+            .map(|m| m.to_string())
+            .collect();
+        json!({
+            "type": "state",
+            "state": self.state,
+            "notation": self.state.to_notation(),
+            "legal_moves": moves,
+            "current_player": self.state.current_player(),
+            "winner": self.state.winner(),
+            "winners": self.state.winners(),
+            "last_move": last_move.map(|m| m.to_string()),
+        })
+    }
+}
+
+/// All games being played, by id.
+pub struct Lobby {
+    tables: HashMap<String, Table>,
+    rng: StdRng,
+}
+
+impl Lobby {
+    /// An empty lobby, dealing with a generator seeded by `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            tables: HashMap::new(),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    // seats a connection at a game. returns the seat
+    fn join(&mut self, game: &str, sender: Sender<Value>) -> Result<usize, Value> {
+        let table = self
+            .tables
+            .entry(game.to_string())
+            .or_insert_with(Table::new);
+        let Some(seat) = table.seats.iter().position(Option::is_none) else {
+            return Err(error(format!("game {} is full", game)));
+        };
+        let _ = sender.send(json!({ "type": "joined", "game": game, "seat": seat }));
+        table.seats[seat] = Some(sender);
+        if table.seats.iter().all(Option::is_some) {
+            if !table.started {
+                table.state.deal(&mut self.rng);
+                table.started = true;
+            }
+            let update = table.update(None);
+            table.broadcast(&update);
+        }
+        Ok(seat)
+    }
+
+    // sends the game to a connection from now on, without seating it
+    fn watch(&mut self, game: &str, sender: Sender<Value>) {
+        let table = self
+            .tables
+            .entry(game.to_string())
+            .or_insert_with(Table::new);
+        let _ = sender.send(json!({ "type": "watching", "game": game }));
+        if table.started {
+            let _ = sender.send(table.update(None));
+        }
+        table.spectators.push(sender);
+    }
+
+    fn play(&mut self, game: &str, seat: usize, m: &str) -> Result<(), Value> {
+        let table = self.tables.get_mut(game).unwrap();
+        if !table.started {
+            return Err(error("waiting for the other player"));
+        }
+        if table.state.current_player() != seat {
+            return Err(error("it's not your turn"));
+        }
+        let m = Move::parse(m).map_err(|e| error(e.to_string()))?;
+        let mut state = table.state.apply(&m).map_err(|e| error(e.to_string()))?;
+        if state.needs_deal() {
+            state.deal(&mut self.rng);
+        }
+        table.state = state;
+        let update = table.update(Some(&m));
+        table.broadcast(&update);
+        Ok(())
+    }
+
+    fn leave(&mut self, game: &str, seat: usize) {
+        if let Some(table) = self.tables.get_mut(game) {
+            table.seats[seat] = None;
+            table.broadcast(&json!({ "type": "left", "seat": seat }));
+            if table.seats.iter().all(Option::is_none) && table.spectators.is_empty() {
+                self.tables.remove(game);
+            }
+        }
+    }
+}
+
+fn io_error(error: tungstenite::Error) -> io::Error {
+    match error {
+        tungstenite::Error::Io(error) => error,
+        error => io::Error::other(error),
+    }
+}
+
+// serves one client until it disconnects
+fn connection(lobby: Arc<Mutex<Lobby>>, stream: TcpStream) -> io::Result<()> {
+    // the stream is blocking during the handshake, so it can't be interrupted
+    let mut socket = match tungstenite::accept(stream.try_clone()?) {
+        Ok(socket) => socket,
+        Err(tungstenite::HandshakeError::Failure(e)) => return Err(io_error(e)),
+        Err(tungstenite::HandshakeError::Interrupted(_)) => unreachable!(),
+    };
+    // wake up regularly to forward broadcasts from other threads
+    stream.set_read_timeout(Some(Duration::from_millis(50)))?;
+    let (sender, receiver) = mpsc::channel();
+    let mut seat: Option<(String, usize)> = None;
+    let result = serve(&lobby, &mut socket, &sender, &receiver, &mut seat);
+    if let Some((game, seat)) = seat {
+        lobby.lock().unwrap().leave(&game, seat);
+    }
+    result
+}
+
+fn serve(
+    lobby: &Mutex<Lobby>,
+    socket: &mut WebSocket<TcpStream>,
+    sender: &Sender<Value>,
+    receiver: &Receiver<Value>,
+    seat: &mut Option<(String, usize)>,
+) -> io::Result<()> {
+    loop {
+        for message in receiver.try_iter() {
+            socket
+                .send(Message::Text(message.to_string()))
+                .map_err(io_error)?;
+        }
+        let text = match socket.read() {
+            Ok(Message::Text(text)) => text,
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => continue,
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                continue
+            }
+            Err(tungstenite::Error::ConnectionClosed)
+            | Err(tungstenite::Error::Protocol(
+                tungstenite::error::ProtocolError::ResetWithoutClosingHandshake,
+            )) => return Ok(()),
+            Err(e) => return Err(io_error(e)),
+        };
+        let result = match (serde_json::from_str::<Request>(&text), &seat) {
+            (Err(e), _) => Err(error(e.to_string())),
+            (Ok(Request::Join { game }), None) => lobby
+                .lock()
+                .unwrap()
+                .join(&game, sender.clone())
+                .map(|index| *seat = Some((game, index))),
+            (Ok(Request::Join { .. }), Some((game, _))) => {
+                Err(error(format!("already in game {}", game)))
+            }
+            (Ok(Request::Watch { game }), _) => {
+                lobby.lock().unwrap().watch(&game, sender.clone());
+                Ok(())
+            }
+            (Ok(Request::Move { m }), Some((game, index))) => {
+                lobby.lock().unwrap().play(game, *index, &m)
+            }
+            (Ok(Request::Move { .. }), None) => Err(error("join a game first")),
+        };
+        if let Err(message) = result {
+            socket
+                .send(Message::Text(message.to_string()))
+                .map_err(io_error)?;
+        }
+    }
+}
+
+/// Serves live games on the port until the process is stopped, with a
+/// thread per connection.
+pub fn serve_live(port: u16, seed: u64) -> io::Result<()> {
+    serve_live_on(TcpListener::bind(("0.0.0.0", port))?, seed)
+}
+
+/// Serves live games to the connections of a listener that is already
+/// bound, e.g. to a port picked by the system.
+pub fn serve_live_on(listener: TcpListener, seed: u64) -> io::Result<()> {
+    let lobby = Arc::new(Mutex::new(Lobby::new(seed)));
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let lobby = lobby.clone();
+        thread::spawn(move || {
+            if let Err(error) = connection(lobby, stream) {
+                eprintln!("connection closed: {}", error);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Watches a live game on the server at `url`, e.g. `ws://localhost:8081`,
+/// calling `f` with every message from the server until `f` returns false
+/// or the server closes the connection.
+pub fn spectate(url: &str, game: &str, mut f: impl FnMut(&Value) -> bool) -> io::Result<()> {
+    let (mut socket, _) = tungstenite::connect(url).map_err(io_error)?;
+    let watch = json!({ "type": "watch", "game": game });
+    socket
+        .send(Message::Text(watch.to_string()))
+        .map_err(io_error)?;
+    loop {
+        let text = match socket.read() {
+            Ok(Message::Text(text)) => text,
+            Ok(Message::Close(_)) | Err(tungstenite::Error::ConnectionClosed) => return Ok(()),
+            Ok(_) => continue,
+            Err(e) => return Err(io_error(e)),
+        };
+        let message = serde_json::from_str(&text).map_err(io::Error::from)?;
+        if !f(&message) {
+            return socket.close(None).map_err(io_error);
+        }
+    }
+}
@@ -1,27 +1,1512 @@
-mod azul;
-mod minmax;
-
-use crate::{
-    azul::{Fish, State},
-    minmax::{random_move, search, GameState},
+use azul::{
+    annotate::Thresholds,
+    clock::{Clock, TimeControl},
+    engine::{self, Dealer, Engine, Opponent, Registered, Spec, Temperature, Tempered, MAX_LEVEL},
+    game_log::GameLog,
+    i18n::{Language, Text},
+    observer::{Console, Csv, JsonLines, Observer},
+    openings::Book,
+    perft::{perft, Chance},
+    personas::{self, Configured, ConfiguredEngine, Persona},
+    play::{format_pv, print_hint, print_scoring, read_move, Game, Help, Input},
+    rating::Ratings,
+    selfplay,
+    stats::Stats,
+    tournament::{self, Adjudication, Schedule, Summary},
+    tune::{self, Spsa},
+    watch::{tail, Spectator},
+};
+use azul::{
+    describe_scoring, render_changes, render_state, search_multipv, BoardEvaluation, Evaluation,
+    GameRecord, History, Move, RenderOptions, SavedGame, SearchOptions, State, StochasticGameState,
+    Theme,
+};
+use clap::{Parser, Subcommand};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, BufRead, BufWriter, IsTerminal},
+    path::{Path, PathBuf},
+    process,
+    str::FromStr,
+    time::{Duration, Instant},
 };
-use rand::thread_rng;
+
+#[derive(Parser)]
+#[command(about = "A machine player for the Azul board game")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Save a record of the game to this file
+    #[arg(long, global = true)]
+    record: Option<PathBuf>,
+    /// Stream every move of the game to this file as a line of JSON
+    #[arg(long, global = true)]
+    log: Option<PathBuf>,
+    /// Seed the random generator to replay a game exactly. Random if not given
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+    /// Who plays the game without a command: human or an engine, e.g.
+    /// random, greedy, minmax:4, fish:4, mcts:1000 or level:3
+    #[arg(long, value_delimiter = ',', default_value = "fish:4,random")]
+    players: Vec<Seat>,
+    /// Read the players of the game without a command from this TOML
+    /// file instead, with their engines, depths or times, evaluation
+    /// weights and names, see the personas module
+    #[arg(long, conflicts_with = "players")]
+    config: Option<PathBuf>,
+    /// Stream the moves, scoring, deals and end of the game without a
+    /// command to this file as lines of JSON
+    #[arg(long)]
+    events: Option<PathBuf>,
+    /// Write the points, floor penalty and tiles placed of every player in
+    /// every round of the game without a command to this CSV file
+    #[arg(long)]
+    csv: Option<PathBuf>,
+    /// Names of the players, separated by commas, in seating order
+    #[arg(long, global = true, value_delimiter = ',')]
+    names: Vec<String>,
+    /// Print boards without ANSI colors. They are left out anyway when
+    /// the output isn't a terminal
+    #[arg(long, global = true)]
+    no_color: bool,
+    /// Print boards in ASCII only
+    #[arg(long, global = true)]
+    ascii: bool,
+    /// Print tiles as colored blocks and draw a box around the wall
+    #[arg(long, global = true)]
+    glyphs: bool,
+    /// How tiles are told apart: classic, colorblind for colors that
+    /// people with color blindness tell apart, letters without colors, or
+    /// symbols
+    #[arg(long, global = true, default_value_t)]
+    theme: Theme,
+    /// Mark on the boards what the last move changed: the player who made
+    /// it, the tiles gained, the rows cleared and the points scored
+    #[arg(long, global = true)]
+    changes: bool,
+    /// Language of moves, scoring and prompts: en, sv or de
+    #[arg(long, global = true, default_value_t)]
+    lang: Language,
+}
+
+// a player of the game without a command
+#[derive(Clone)]
+enum Seat {
+    Human,
+    Engine(Configured),
+}
+
+impl Seat {
+    // built once for the game, so that engines keep what they learn
+    fn build(&self, language: Language) -> Player {
+        match self {
+            Seat::Human => Player::Human(language),
+            Seat::Engine(configured) => Player::Engine(configured.build()),
+        }
+    }
+}
+
+// a seat taken for the game
+enum Player {
+    Human(Language),
+    Engine(ConfiguredEngine),
+}
+
+impl FromStr for Seat {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "human" => Ok(Seat::Human),
+            // a count belongs to play --seats, not to this list
+            text if text.parse::<usize>().is_ok() => Err(format!(
+                "expected human or an engine, not '{}'; for a game of {} players against the engine use play --seats {}",
+                text, text, text
+            )),
+            text => text
+                .parse()
+                .map(|spec| Seat::Engine(Configured::Spec(spec))),
+        }
+    }
+}
+
+impl fmt::Display for Seat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Seat::Human => f.write_str("human"),
+            Seat::Engine(spec) => spec.fmt(f),
+        }
+    }
+}
+
+impl Engine for Player {
+    fn choose<R: Rng>(&mut self, state: &State, rng: &mut R) -> Move {
+        match self {
+            Player::Human(language) => loop {
+                let language = *language;
+                let help = |help| match help {
+                    Help::Hint => print_hint(state, language, rng),
+                    Help::Show(tile) => {
+                        let render = RenderOptions {
+                            highlight: Some(tile),
+                            ..RenderOptions::detect()
+                        };
+                        println!("\n{}", render_state(state, &[], &render));
+                    }
+                };
+                match read_move(state, language, &mut io::stdin().lock(), help) {
+                    Some(Input::Move(m)) => return m,
+                    Some(_) => println!("undo, redo and save only work in play"),
+                    None => process::exit(0),
+                }
+            },
+            Player::Engine(engine) => engine.choose(state, rng),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Play against the engine in the terminal
+    Play {
+        /// Number of players, you and the rest engines. Not to be confused
+        /// with --players, which lists who plays the game without a command
+        #[arg(long, default_value_t = 2)]
+        seats: usize,
+        /// Search depth of the engine
+        #[arg(long, default_value_t = 4)]
+        depth: usize,
+        /// Think for this many milliseconds per move instead of a fixed depth
+        #[arg(long)]
+        movetime: Option<u64>,
+        /// Search this many determinizations of the deals to come and play
+        /// the move most of them agree on
+        #[arg(long, default_value_t = 1)]
+        determinizations: usize,
+        /// Search every move to the full depth, without late move
+        /// reductions, e.g. to compare the strength with and without them
+        #[arg(long)]
+        no_lmr: bool,
+        /// Skip moves near the end of the search that drop this many more
+        /// tiles on the floor line than another move taking the same
+        /// tiles. 0 searches them all
+        #[arg(long, default_value_t = 2)]
+        pruning: usize,
+        /// Play with clocks, minutes per game plus seconds per move, e.g.
+        /// 5+3. The engine thinks as long as its clock allows, and running
+        /// out of time loses
+        #[arg(long)]
+        tc: Option<TimeControl>,
+        /// Play against this engine instead of the searching one, e.g.
+        /// random, greedy, minmax:4, fish:4 or mcts:1000
+        #[arg(long)]
+        opponent: Option<Spec>,
+        /// Play against a weaker engine, from 1 for beginners to 8, which
+        /// plays like minmax:4
+        #[arg(long, conflicts_with = "opponent")]
+        #[arg(value_parser = clap::value_parser!(u8).range(1..=MAX_LEVEL as i64))]
+        level: Option<u8>,
+        /// Start from this position, in notation, instead of a new game
+        #[arg(long)]
+        position: Option<String>,
+        /// Let the engine play moves from this opening book
+        #[arg(long)]
+        book: Option<PathBuf>,
+        /// Print statistics of every search of the engine
+        #[arg(long)]
+        verbose: bool,
+        /// Evaluate positions with this ONNX model, see the onnx module.
+        /// Needs the onnx feature
+        #[arg(long)]
+        model: Option<PathBuf>,
+        /// Let the engine think while it's your turn
+        #[arg(long)]
+        ponder: bool,
+        /// Continue a game saved with `save <file>` while playing, instead
+        /// of starting a new one
+        #[arg(long)]
+        resume: Option<PathBuf>,
+    },
+    /// Learn an opening book from self-play
+    Learn {
+        /// Book to add to, created if it doesn't exist
+        book: PathBuf,
+        #[arg(long, default_value_t = 100)]
+        games: usize,
+        /// Number of different deals to play the games from
+        #[arg(long, default_value_t = 10)]
+        deals: usize,
+        /// Search depth of the engine
+        #[arg(long, default_value_t = 2)]
+        depth: usize,
+    },
+    /// Let Monte Carlo tree search play against minmax
+    Mcts {
+        /// Playouts per move
+        #[arg(long, default_value_t = 1000)]
+        iterations: usize,
+        /// Search depth of minmax
+        #[arg(long, default_value_t = 4)]
+        depth: usize,
+    },
+    /// Play many engine versus engine games and summarize the results
+    Tournament {
+        /// Number of games, the engines take turns to start
+        #[arg(long, default_value_t = 100)]
+        games: usize,
+        /// First engine: random, greedy, minmax:<depth>, fish:<depth> or mcts:<iterations>
+        #[arg(long, default_value = "minmax:2")]
+        engine_a: Spec,
+        /// Second engine
+        #[arg(long, default_value = "random")]
+        engine_b: Spec,
+        /// Play a tournament between these engines instead, separated by
+        /// commas, with a match of --games games for every pairing
+        #[arg(long, value_delimiter = ',', num_args = 1..)]
+        engines: Vec<Spec>,
+        /// Who plays whom in a tournament: round-robin, or swiss:<rounds>
+        /// to pair engines close in the standings for that many rounds
+        #[arg(long, default_value = "round-robin")]
+        schedule: Schedule,
+        /// Update the Elo ratings in this file with the results
+        #[arg(long)]
+        ratings: Option<PathBuf>,
+        /// An engine resigns when it has been behind by this many points,
+        /// by the evaluation, for --resign-moves of its moves in a row
+        #[arg(long)]
+        resign: Option<f64>,
+        #[arg(long, default_value_t = 3)]
+        resign_moves: usize,
+        /// An engine wins when it has been ahead by this many points, by
+        /// the evaluation, for --adjudicate-moves moves in a row
+        #[arg(long)]
+        adjudicate: Option<f64>,
+        #[arg(long, default_value_t = 6)]
+        adjudicate_moves: usize,
+        /// Write the points, floor penalty and tiles placed of every seat
+        /// in every round to this CSV file. The first engine sits first in
+        /// the odd numbered games
+        #[arg(long)]
+        csv: Option<PathBuf>,
+        /// Sample the first --temperature-plies moves of every game among
+        /// the near-best, for more varied games: a move this many points
+        /// worse than the best is played e times less often. Off at 0
+        #[arg(long, default_value_t = 0.0)]
+        temperature: f64,
+        #[arg(long, default_value_t = 8)]
+        temperature_plies: usize,
+    },
+    /// Play the engine against itself and write every position with the
+    /// move chosen and the result as training data, see the selfplay module
+    Selfplay {
+        #[arg(long, default_value_t = 100)]
+        games: usize,
+        /// Directory to write the .npy files to
+        #[arg(long, default_value = "data")]
+        out: PathBuf,
+        /// The engine: random, greedy, minmax:<depth>, fish:<depth> or mcts:<iterations>
+        #[arg(long, default_value = "minmax:2")]
+        engine: Spec,
+        /// Sample the first --temperature-plies moves of every game among
+        /// the near-best, for more varied games: a move this many points
+        /// worse than the best is played e times less often. Off at 0
+        #[arg(long, default_value_t = 0.0)]
+        temperature: f64,
+        #[arg(long, default_value_t = 8)]
+        temperature_plies: usize,
+    },
+    /// Tune the weights of the linear evaluation by playing games between
+    /// small changes to them
+    Tune {
+        /// Weights to start from and save to after every iteration,
+        /// starting from the default weights if the file doesn't exist
+        weights: PathBuf,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize,
+        /// Pairs of games per iteration
+        #[arg(long, default_value_t = 2)]
+        games: usize,
+        /// Search depth of the engines in the games
+        #[arg(long, default_value_t = 1)]
+        depth: usize,
+    },
+    /// Serve games and the engine over HTTP as JSON
+    #[cfg(feature = "server")]
+    Serve {
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+        /// Let the engine think about a game until the next move is posted
+        #[arg(long)]
+        ponder: bool,
+    },
+    /// Host live games between remote players over WebSocket
+    #[cfg(feature = "live")]
+    Live {
+        #[arg(long, default_value_t = 8081)]
+        port: u16,
+    },
+    /// Play against the engine in a full screen terminal interface
+    #[cfg(feature = "tui")]
+    Tui {
+        /// Number of players, you and the rest engines. Not to be confused
+        /// with --players, which lists who plays the game without a command
+        #[arg(long, default_value_t = 2)]
+        seats: usize,
+        /// Search depth of the engine, and of the analysis
+        #[arg(long, default_value_t = 4)]
+        depth: usize,
+    },
+    /// Let people play against the engine in IRC channels, see the bot
+    /// module for the commands
+    #[cfg(feature = "bot")]
+    Irc {
+        /// Server and port, e.g. irc.libera.chat:6667
+        server: String,
+        /// Nickname of the bot
+        #[arg(long, default_value = "azulbot")]
+        nick: String,
+        /// Channels to join, separated by commas
+        #[arg(long, value_delimiter = ',', required = true)]
+        channels: Vec<String>,
+        /// The engine to play against
+        #[arg(long, default_value = "level:4")]
+        engine: Spec,
+    },
+    /// Print the best moves from a position with their scores and the
+    /// moves expected after them
+    Analyze {
+        /// The position, in notation
+        position: String,
+        /// Number of moves to print
+        #[arg(long, default_value_t = 3)]
+        multipv: usize,
+        /// Search depth
+        #[arg(long, default_value_t = 4)]
+        depth: usize,
+    },
+    /// Count the move sequences of each length from a position, to
+    /// validate move generation
+    Perft {
+        #[arg(long, default_value_t = 3)]
+        depth: usize,
+        /// Count every deal, rather than one random deal, at round ends
+        /// with at most this many deals
+        #[arg(long)]
+        enumerate: Option<usize>,
+        /// Start from this position, in notation, instead of a new game
+        #[arg(long)]
+        position: Option<String>,
+    },
+    /// Speak the AzulUCI protocol on stdin and stdout, for GUIs
+    Uci,
+    /// Replay a recorded game
+    Replay {
+        /// Record saved with --record
+        file: PathBuf,
+        /// Wait for enter between moves
+        #[arg(long)]
+        step: bool,
+    },
+    /// Follow a game from another terminal as it's played, redrawing the
+    /// boards after every move
+    Watch {
+        /// Game log written with --log or event stream written with
+        /// --events, or the address of a live server, e.g.
+        /// ws://localhost:8081
+        source: String,
+        /// The live game to watch
+        #[arg(long)]
+        game: Option<String>,
+    },
+    /// Search every position of a recorded game again and mark the moves
+    /// much worse than the engine's with ?! or ??
+    Annotate {
+        /// Record saved with --record
+        file: PathBuf,
+        /// The annotated record to write
+        #[arg(short, long, default_value = "annotated.json")]
+        output: PathBuf,
+        /// Search depth
+        #[arg(long, default_value_t = 2)]
+        depth: usize,
+        /// Points a move must lose to the engine's move to be dubious, ?!
+        #[arg(long, default_value_t = 2.0)]
+        dubious: f64,
+        /// Points a move must lose to the engine's move to be a blunder, ??
+        #[arg(long, default_value_t = 5.0)]
+        blunder: f64,
+    },
+    /// Write a recorded game as an HTML page with boards, scores and
+    /// evaluations
+    Report {
+        /// Record saved with --record
+        file: PathBuf,
+        /// The page to write
+        #[arg(short, long, default_value = "report.html")]
+        output: PathBuf,
+        /// Search depth of the evaluations, 0 to leave them out
+        #[arg(long, default_value_t = 2)]
+        depth: usize,
+    },
+    /// Print score distributions, round counts, colors taken and the first
+    /// player advantage over recorded games
+    Stats {
+        /// Records saved with --record
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Write the statistics to this file as CSV instead
+        #[arg(long)]
+        csv: Option<PathBuf>,
+    },
+}
 
 fn main() {
-    let mut rng = thread_rng();
-    let mut evaluation = Fish::new();
+    let cli = Cli::parse();
+    let record = cli.record.as_deref();
+    let log = cli.log.as_deref();
+    let seed = cli.seed.unwrap_or_else(|| thread_rng().gen());
+    let detected = RenderOptions::detect();
+    let render = RenderOptions {
+        color: detected.color && !cli.no_color,
+        unicode: detected.unicode && !cli.ascii,
+        glyphs: cli.glyphs,
+        highlight: None,
+        theme: cli.theme,
+    };
+    if !matches!(
+        cli.command,
+        Some(
+            Command::Replay { .. }
+                | Command::Watch { .. }
+                | Command::Annotate { .. }
+                | Command::Report { .. }
+                | Command::Stats { .. }
+                | Command::Uci
+        )
+    ) {
+        println!("seed {}", seed);
+    }
+    match cli.command {
+        None => {
+            let (players, names) = match &cli.config {
+                Some(path) => match load_personas(path) {
+                    Ok((players, names)) if cli.names.is_empty() => (players, names),
+                    Ok((players, _)) => (players, cli.names),
+                    Err(error) => {
+                        eprintln!("could not load players from {}: {}", path.display(), error);
+                        return;
+                    }
+                },
+                None => (cli.players, cli.names),
+            };
+            demo(
+                players,
+                &names,
+                seed,
+                record,
+                log,
+                cli.events.as_deref(),
+                cli.csv.as_deref(),
+                cli.lang,
+            )
+        }
+        Some(Command::Play {
+            seats,
+            depth,
+            movetime,
+            determinizations,
+            no_lmr,
+            pruning,
+            tc,
+            opponent,
+            level,
+            position,
+            book,
+            verbose,
+            model,
+            ponder,
+            resume,
+        }) => play(
+            seats,
+            &cli.names,
+            depth,
+            movetime.map(Duration::from_millis),
+            determinizations,
+            !no_lmr,
+            Some(pruning).filter(|margin| *margin > 0),
+            tc,
+            opponent.or(level.map(Spec::Level)),
+            position,
+            book.as_deref(),
+            verbose,
+            model.as_deref(),
+            ponder,
+            resume.as_deref(),
+            &render,
+            cli.changes,
+            cli.lang,
+            seed,
+            record,
+            log,
+        ),
+        Some(Command::Learn {
+            book,
+            games,
+            deals,
+            depth,
+        }) => learn(&book, games, deals, depth, seed),
+        Some(Command::Mcts { iterations, depth }) => mcts(iterations, depth, seed, record),
+        Some(Command::Tournament {
+            games,
+            engine_a,
+            engine_b,
+            engines,
+            schedule,
+            ratings,
+            resign,
+            resign_moves,
+            adjudicate,
+            adjudicate_moves,
+            csv,
+            temperature,
+            temperature_plies,
+        }) => {
+            let hundredths = |points: f64| (100.0 * points).round() as i32;
+            let rules = Adjudication {
+                resign: resign.map(hundredths),
+                resign_moves,
+                adjudicate: adjudicate.map(hundredths),
+                adjudicate_moves,
+            };
+            let temperature = Temperature {
+                temperature,
+                plies: temperature_plies,
+            };
+            if engines.is_empty() {
+                tournament(
+                    games,
+                    &engine_a,
+                    &engine_b,
+                    &rules,
+                    &temperature,
+                    seed,
+                    ratings.as_deref(),
+                    csv.as_deref(),
+                )
+            } else {
+                cross_table(
+                    games,
+                    &engines,
+                    schedule,
+                    &rules,
+                    &temperature,
+                    seed,
+                    ratings.as_deref(),
+                    csv.as_deref(),
+                )
+            }
+        }
+        Some(Command::Selfplay {
+            games,
+            out,
+            engine,
+            temperature,
+            temperature_plies,
+        }) => {
+            let engine = Tempered {
+                engine: engine.build(),
+                temperature: Temperature {
+                    temperature,
+                    plies: temperature_plies,
+                },
+            };
+            selfplay(games, &out, engine, seed)
+        }
+        Some(Command::Tune {
+            weights,
+            iterations,
+            games,
+            depth,
+        }) => {
+            let spsa = Spsa {
+                iterations,
+                games,
+                depth,
+                ..Spsa::default()
+            };
+            tune(&weights, &spsa, seed)
+        }
+        #[cfg(feature = "server")]
+        Some(Command::Serve { port, ponder }) => {
+            println!("serving on port {}", port);
+            let mut server = azul::server::Server::new(seed);
+            if ponder {
+                server = server.with_pondering();
+            }
+            if let Err(error) = server.serve(port) {
+                eprintln!("{}", error);
+            }
+        }
+        #[cfg(feature = "live")]
+        Some(Command::Live { port }) => {
+            println!("hosting live games on port {}", port);
+            if let Err(error) = azul::live::serve_live(port, seed) {
+                eprintln!("{}", error);
+            }
+        }
+        #[cfg(feature = "tui")]
+        Some(Command::Tui { seats, depth }) => tui(seats, &cli.names, depth, seed),
+        #[cfg(feature = "bot")]
+        Some(Command::Irc {
+            server,
+            nick,
+            channels,
+            engine,
+        }) => {
+            println!(
+                "playing {} in {} on {}",
+                engine,
+                channels.join(", "),
+                server
+            );
+            let mut bot = azul::bot::Bot::new(engine, seed);
+            if let Err(error) = azul::bot::irc(&mut bot, &server, &nick, &channels) {
+                eprintln!("{}", error);
+            }
+        }
+        Some(Command::Analyze {
+            position,
+            multipv,
+            depth,
+        }) => analyze(&position, multipv, depth, seed),
+        Some(Command::Perft {
+            depth,
+            enumerate,
+            position,
+        }) => count_moves(depth, enumerate, position, seed),
+        Some(Command::Uci) => {
+            let rng = StdRng::seed_from_u64(seed);
+            if let Err(error) = azul::protocol::run(rng, io::stdin().lock(), io::stdout()) {
+                eprintln!("{}", error);
+            }
+        }
+        Some(Command::Replay { file, step }) => {
+            replay(&file, step, &cli.names, &render, cli.changes, cli.lang)
+        }
+        Some(Command::Watch { source, game }) => {
+            watch(&source, game.as_deref(), &cli.names, &render)
+        }
+        Some(Command::Annotate {
+            file,
+            output,
+            depth,
+            dubious,
+            blunder,
+        }) => {
+            let thresholds = Thresholds {
+                dubious: (dubious * 100.0).round() as i32,
+                blunder: (blunder * 100.0).round() as i32,
+            };
+            annotate(&file, &output, depth, &thresholds)
+        }
+        Some(Command::Report {
+            file,
+            output,
+            depth,
+        }) => report(&file, &output, depth),
+        Some(Command::Stats { files, csv }) => stats(&files, csv.as_deref()),
+    }
+}
+
+fn save(record: &GameRecord, path: Option<&Path>) {
+    if let Some(path) = path {
+        if let Err(error) = record.save(path) {
+            eprintln!("could not save record to {}: {}", path.display(), error);
+        }
+    }
+}
+
+// the log at the path, if any. None also if it could not be created
+fn create_log(path: Option<&Path>) -> Result<Option<GameLog<BufWriter<File>>>, ()> {
+    match path.map(GameLog::create).transpose() {
+        Ok(log) => Ok(log),
+        Err(error) => {
+            eprintln!(
+                "could not create log {}: {}",
+                path.unwrap().display(),
+                error
+            );
+            Err(())
+        }
+    }
+}
+
+// a file at the path, if any, or Err if it could not be created
+fn create_file(path: Option<&Path>) -> Result<Option<BufWriter<File>>, ()> {
+    match path.map(File::create).transpose() {
+        Ok(file) => Ok(file.map(BufWriter::new)),
+        Err(error) => {
+            eprintln!("could not create {}: {}", path.unwrap().display(), error);
+            Err(())
+        }
+    }
+}
+
+// logs the move, giving up on the log if it can't be written
+fn log_move(
+    log: &mut Option<GameLog<BufWriter<File>>>,
+    before: &State,
+    after: &State,
+    evaluation: Option<i32>,
+    elapsed: Duration,
+) {
+    if let Some(writer) = log {
+        if let Err(error) = writer.push(before, after, evaluation, elapsed) {
+            eprintln!("could not write log: {}", error);
+            *log = None;
+        }
+    }
+}
+
+// the given names, followed by defaults for the players without one
+fn with_defaults(names: &[String], defaults: Vec<String>) -> Vec<String> {
+    let given = names.iter().cloned();
+    given
+        .chain(defaults.into_iter().skip(names.len()))
+        .collect()
+}
+
+// the seats and names of the players declared in a file
+fn load_personas(path: &Path) -> io::Result<(Vec<Seat>, Vec<String>)> {
+    let personas = personas::load(path)?;
+    let names = personas.iter().map(Persona::name).collect();
+    let players = personas
+        .iter()
+        // the file was checked when loaded
+        .map(|persona| match persona.engine().unwrap() {
+            Some(engine) => Seat::Engine(engine),
+            None => Seat::Human,
+        })
+        .collect();
+    Ok((players, names))
+}
+
+// a game between the players
+#[allow(clippy::too_many_arguments)]
+fn demo(
+    players: Vec<Seat>,
+    names: &[String],
+    seed: u64,
+    record_path: Option<&Path>,
+    log_path: Option<&Path>,
+    events_path: Option<&Path>,
+    csv_path: Option<&Path>,
+    language: Language,
+) {
+    if !(2..=4).contains(&players.len()) {
+        eprintln!("a game has two to four players");
+        return;
+    }
+    let Ok(mut log) = create_log(log_path) else {
+        return;
+    };
+    let Ok(events) = create_file(events_path) else {
+        return;
+    };
+    let Ok(csv) = create_file(csv_path) else {
+        return;
+    };
+    let mut events = events.map(JsonLines::new);
+    let mut csv = csv.map(Csv::new);
+    let names = with_defaults(names, players.iter().map(Seat::to_string).collect());
+    let mut players: Vec<_> = players.iter().map(|seat| seat.build(language)).collect();
+    let mut console = Console::new(names.clone()).with_language(language);
+    let mut observers: Vec<Box<dyn Observer + '_>> = vec![
+        Box::new(&mut console),
+        Box::new(&mut events),
+        Box::new(&mut csv),
+    ];
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut dealer = Dealer::new(rng.gen());
+    let mut state = State::new(players.len());
+    dealer.deal(&mut state);
+    observers.on_deal(&state);
+    let mut record = GameRecord::new(Some(seed), &state);
+    let played = engine::play_game(
+        &mut players,
+        state,
+        &mut rng,
+        &mut dealer,
+        &mut observers,
+        |before, after, elapsed| {
+            log_move(&mut log, before, after, None, elapsed);
+            record.push(before, after);
+        },
+    );
+    if let Err((player, error)) = played {
+        eprintln!("{} broke the rules: {}", names[player], error);
+        return;
+    }
+    drop(observers);
+    save(&record, record_path);
+    if let Some(Err(error)) = events.map(JsonLines::finish) {
+        eprintln!(
+            "could not write {}: {}",
+            events_path.unwrap().display(),
+            error
+        );
+    }
+    if let Some(Err(error)) = csv.map(Csv::finish) {
+        eprintln!("could not write {}: {}", csv_path.unwrap().display(), error);
+    }
+}
+
+// monte carlo tree search versus minmax
+fn mcts(iterations: usize, depth: usize, seed: u64, record_path: Option<&Path>) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut engines = [
+        Spec::Mcts(iterations).build(),
+        Spec::Fish {
+            depth,
+            reductions: true,
+        }
+        .build(),
+    ];
+    let names = ["MCTS", "Minmax"];
+    let mut dealer = Dealer::new(rng.gen());
     let mut state = State::new(2);
-    let names = ["Samuel", "Maria"];
+    dealer.deal(&mut state);
+    let mut record = GameRecord::new(Some(seed), &state);
+    let played = engine::play_game(
+        &mut engines,
+        state,
+        &mut rng,
+        &mut dealer,
+        &mut (),
+        |before, after, _| {
+            record.push(before, after);
+        },
+    );
+    let state = match played {
+        Ok(state) => state,
+        Err((player, error)) => {
+            eprintln!("{} broke the rules: {}", names[player], error);
+            return;
+        }
+    };
+    save(&record, record_path);
+    for (index, player) in state.players.iter().enumerate() {
+        println!("{}: {} points", names[index], player.points);
+    }
+}
+
+// names of the human and engine players, unless given
+fn seat_names(players: usize, human: usize, names: &[String], language: Language) -> Vec<String> {
+    let mut engines = 0;
+    let defaults = (0..players)
+        .map(|index| {
+            if index == human {
+                return language.text(Text::You).to_string();
+            }
+            engines += 1;
+            let engine = language.text(Text::Engine);
+            match players {
+                2 => engine.to_string(),
+                _ => format!("{} {}", engine, engines),
+            }
+        })
+        .collect();
+    with_defaults(names, defaults)
+}
+
+// human versus engines in the full screen interface
+#[cfg(feature = "tui")]
+fn tui(players: usize, names: &[String], depth: usize, seed: u64) {
+    if !(2..=4).contains(&players) {
+        eprintln!("a game has two to four players");
+        return;
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut state = State::new(players);
     state.deal(&mut rng);
-    while state.winner().is_none() {
-        println!("round {}: {}", state.moves, names[state.current_player()]);
-        if state.current_player() == 0 {
-            state = search(&state, &mut evaluation, &mut rng, 4).unwrap();
-        } else {
-            state = random_move(&state, &mut rng);
+    let human = rng.gen_range(0..players);
+    // the full screen interface is in English
+    let names = seat_names(players, human, names, Language::English);
+    match azul::tui::run(state, human, names.clone(), depth, rng.gen()) {
+        Ok(state) => {
+            for (index, player) in state.players.iter().enumerate() {
+                println!("{}: {} points", names[index], player.points);
+            }
         }
+        Err(error) => eprintln!("{}", error),
     }
-    for (index, player) in state.players.iter().enumerate() {
-        println!("player {}, {}", names[index], player.points);
+}
+
+// the evaluation of the model, if any, or else the built in one
+fn load_evaluation(model: Option<&Path>) -> Result<Box<dyn Evaluation<State> + Send>, String> {
+    match model {
+        None => Ok(Box::new(BoardEvaluation::default())),
+        #[cfg(feature = "onnx")]
+        Some(path) => match azul::onnx::OnnxEvaluation::load(path) {
+            Ok(evaluation) => Ok(Box::new(evaluation)),
+            Err(error) => Err(error.to_string()),
+        },
+        #[cfg(not(feature = "onnx"))]
+        Some(_) => Err("built without the onnx feature".to_string()),
+    }
+}
+
+// human versus engines
+#[allow(clippy::too_many_arguments)]
+fn play(
+    players: usize,
+    names: &[String],
+    depth: usize,
+    movetime: Option<Duration>,
+    determinizations: usize,
+    late_move_reductions: bool,
+    pruning: Option<usize>,
+    time_control: Option<TimeControl>,
+    opponent: Option<Spec>,
+    position: Option<String>,
+    book_path: Option<&Path>,
+    verbose: bool,
+    model: Option<&Path>,
+    ponder: bool,
+    resume: Option<&Path>,
+    render: &RenderOptions,
+    changes: bool,
+    language: Language,
+    seed: u64,
+    record_path: Option<&Path>,
+    log_path: Option<&Path>,
+) {
+    let Ok(mut log) = create_log(log_path) else {
+        return;
+    };
+    let book = match book_path.map(Book::load).transpose() {
+        Ok(book) => book.unwrap_or_default(),
+        Err(error) => {
+            eprintln!("could not load opening book: {}", error);
+            return;
+        }
+    };
+    let evaluation = match load_evaluation(model) {
+        Ok(evaluation) => evaluation,
+        Err(error) => {
+            eprintln!("could not load model: {}", error);
+            return;
+        }
+    };
+    let options = match movetime {
+        Some(budget) => SearchOptions::timed(budget),
+        None => SearchOptions::depth(depth),
+    };
+    let options = SearchOptions {
+        determinizations,
+        late_move_reductions,
+        pruning,
+        ..options
+    };
+    let engine = opponent.as_ref().map(Spec::build);
+    let opponent = Opponent::new(engine, evaluation, options, ponder);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (history, human, clock) = match resume {
+        Some(path) => match SavedGame::load(path)
+            .map_err(|error| error.to_string())
+            .and_then(|saved| Ok((saved.restore()?, saved)))
+        {
+            Ok(((history, clock), saved)) => {
+                rng = StdRng::seed_from_u64(saved.seed);
+                (history, saved.human, clock)
+            }
+            Err(error) => {
+                eprintln!("could not resume {}: {}", path.display(), error);
+                return;
+            }
+        },
+        None => {
+            let state = match position {
+                Some(position) => match State::from_notation(&position) {
+                    Ok(state) => state,
+                    Err(error) => {
+                        eprintln!("{}", error);
+                        return;
+                    }
+                },
+                None if (2..=4).contains(&players) => State::new(players),
+                None => {
+                    eprintln!("a game has two to four players");
+                    return;
+                }
+            };
+            // a position may have any number of players
+            let players = state.players.len();
+            if !(2..=4).contains(&players) {
+                eprintln!("a game has two to four players");
+                return;
+            }
+            // the human takes a random seat and engines the rest
+            let human = rng.gen_range(0..players);
+            let clock = time_control.map(|control| Clock::new(control, players));
+            (History::new(state), human, clock)
+        }
+    };
+    let players = history.current().players.len();
+    let mut game = Game {
+        history,
+        human,
+        names: seat_names(players, human, names, language),
+        clock,
+        opponent,
+        book,
+        render: *render,
+        changes,
+        verbose,
+        language,
+    };
+    game.play(
+        rng,
+        &mut io::stdin().lock(),
+        |before, after, score, elapsed| log_move(&mut log, before, after, score, elapsed),
+    );
+    save(
+        &GameRecord::from_states(Some(seed), game.history.states()),
+        record_path,
+    );
+}
+
+fn learn(path: &Path, games: usize, deals: usize, depth: usize, seed: u64) {
+    let mut book = match Book::load(path) {
+        Ok(book) => book,
+        Err(error) => {
+            eprintln!("could not load {}: {}", path.display(), error);
+            return;
+        }
+    };
+    let mut rng = StdRng::seed_from_u64(seed);
+    book.learn(games, deals, depth, &mut rng, |game| {
+        eprint!("\rgame {}/{}", game + 1, games);
+    });
+    eprintln!();
+    println!("{} positions in the book", book.positions.len());
+    if let Err(error) = book.save(path) {
+        eprintln!("could not save {}: {}", path.display(), error);
+    }
+}
+
+fn selfplay(games: usize, directory: &Path, mut engine: Tempered<Registered>, seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let result = selfplay::run(&mut engine, games, directory, &mut rng, |game| {
+        eprint!("\rgame {}/{}", game + 1, games);
+    });
+    eprintln!();
+    match result {
+        Ok(samples) => println!("{} positions in {}", samples, directory.display()),
+        Err(error) => eprintln!("could not write to {}: {}", directory.display(), error),
+    }
+}
+
+fn tune(path: &Path, spsa: &Spsa, seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let tuned = tune::tune_saved(path, spsa, &mut rng, |iteration| {
+        eprint!("\riteration {}/{}", iteration + 1, spsa.iterations);
+    });
+    eprintln!();
+    match tuned {
+        Ok(_) => println!("weights saved to {}", path.display()),
+        Err(error) => eprintln!("could not tune {}: {}", path.display(), error),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn tournament(
+    games: usize,
+    a: &Spec,
+    b: &Spec,
+    rules: &Adjudication,
+    temperature: &Temperature,
+    seed: u64,
+    ratings_path: Option<&Path>,
+    csv_path: Option<&Path>,
+) {
+    let Ok(csv) = create_file(csv_path) else {
+        return;
+    };
+    let mut csv = csv.map(Csv::new);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let summary = tournament::run(
+        a,
+        b,
+        games,
+        rules,
+        temperature,
+        &mut rng,
+        &mut csv,
+        |game, summary| {
+            eprint!(
+                "\rgame {}/{}: +{} ={} -{}",
+                game + 1,
+                games,
+                summary.wins,
+                summary.draws,
+                summary.losses
+            );
+        },
+    );
+    eprintln!();
+    if let Some(Err(error)) = csv.map(Csv::finish) {
+        eprintln!("could not write {}: {}", csv_path.unwrap().display(), error);
+    }
+    let (a, b) = (a.to_string(), b.to_string());
+    println!("{}", tournament::format_match(&a, &b, &summary, rules));
+    if let Some(path) = ratings_path {
+        update_ratings(path, &[(&a, &b, &summary)]);
+    }
+}
+
+// a tournament between more engines, ending with the cross-table
+#[allow(clippy::too_many_arguments)]
+fn cross_table(
+    games: usize,
+    engines: &[Spec],
+    schedule: Schedule,
+    rules: &Adjudication,
+    temperature: &Temperature,
+    seed: u64,
+    ratings_path: Option<&Path>,
+    csv_path: Option<&Path>,
+) {
+    if engines.len() < 2 {
+        eprintln!("a tournament needs at least two engines");
+        return;
+    }
+    let Ok(csv) = create_file(csv_path) else {
+        return;
+    };
+    let mut csv = csv.map(Csv::new);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let table = tournament::run_tournament(
+        engines,
+        schedule,
+        games,
+        rules,
+        temperature,
+        &mut rng,
+        &mut csv,
+        |a, b, game, summary| {
+            eprint!(
+                "\r{} vs {}, game {}/{}: +{} ={} -{}",
+                engines[a],
+                engines[b],
+                game + 1,
+                games,
+                summary.wins,
+                summary.draws,
+                summary.losses
+            );
+        },
+    );
+    eprintln!();
+    if let Some(Err(error)) = csv.map(Csv::finish) {
+        eprintln!("could not write {}: {}", csv_path.unwrap().display(), error);
+    }
+    print!("{}", table);
+    if let Some(path) = ratings_path {
+        let names: Vec<_> = engines.iter().map(Spec::to_string).collect();
+        let matches: Vec<_> = table
+            .matches()
+            .map(|(a, b, summary)| (&names[a], &names[b], summary))
+            .collect();
+        update_ratings(path, &matches);
+    }
+}
+
+fn update_ratings(path: &Path, matches: &[(&String, &String, &Summary)]) {
+    let mut ratings = match Ratings::load(path) {
+        Ok(ratings) => ratings,
+        Err(error) => {
+            eprintln!("could not load ratings from {}: {}", path.display(), error);
+            return;
+        }
+    };
+    for (a, b, summary) in matches {
+        ratings.record(a, b, summary);
+    }
+    if let Err(error) = ratings.save(path) {
+        eprintln!("could not save ratings to {}: {}", path.display(), error);
+    }
+    println!();
+    print!("{}", ratings);
+}
+
+// perft from a new game or a position
+fn analyze(position: &str, lines: usize, depth: usize, seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let state = match State::from_notation(position) {
+        Ok(state) => state.resolve(&mut rng),
+        Err(error) => {
+            eprintln!("{}", error);
+            return;
+        }
+    };
+    let mut evaluation = BoardEvaluation::default();
+    let options = SearchOptions::depth(depth);
+    let infos = search_multipv(&state, &mut evaluation, &mut rng, &options, lines);
+    if infos.is_empty() {
+        println!("the game is over");
+    }
+    for (rank, info) in infos.iter().enumerate() {
+        let m = info.pv[0];
+        println!(
+            "{}. {} ({}), score {}",
+            rank + 1,
+            m,
+            state.describe(&m),
+            info.score
+        );
+        if info.pv.len() > 1 {
+            println!("   expecting {}", format_pv(&info.pv[1..]));
+        }
+    }
+}
+
+fn count_moves(depth: usize, enumerate: Option<usize>, position: Option<String>, seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let state = match position {
+        Some(position) => match State::from_notation(&position) {
+            Ok(state) => state,
+            Err(error) => {
+                eprintln!("{}", error);
+                return;
+            }
+        },
+        None => State::new(2),
+    };
+    let chance = enumerate.map_or(Chance::Sample, Chance::Enumerate);
+    let start = Instant::now();
+    let counts = perft(&state.resolve(&mut rng), depth, chance, &mut rng);
+    for (depth, count) in counts.iter().enumerate() {
+        println!("depth {}: {}", depth + 1, count);
+    }
+    println!("{:.2}s", start.elapsed().as_secs_f64());
+}
+
+// prints every position of a recorded game
+fn replay(
+    path: &Path,
+    step: bool,
+    names: &[String],
+    render: &RenderOptions,
+    changes: bool,
+    language: Language,
+) {
+    let record = match GameRecord::load(path) {
+        Ok(record) => record,
+        Err(error) => {
+            eprintln!("could not load {}: {}", path.display(), error);
+            return;
+        }
+    };
+    let states = match record.replay() {
+        Ok(states) => states,
+        Err(error) => {
+            eprintln!("could not replay {}: {}", path.display(), error);
+            return;
+        }
+    };
+    let defaults = (1..=states[0].players.len())
+        .map(|index| format!("Player {}", index))
+        .collect();
+    let names = with_defaults(names, defaults);
+    let names: Vec<_> = names.iter().map(String::as_str).collect();
+    if let Some(seed) = record.seed {
+        println!("seed {}", seed);
+    }
+    let mut input = io::stdin().lock();
+    println!("{}", render_state(&states[0], &names, render));
+    for (index, (m, pair)) in record.moves.iter().zip(states.windows(2)).enumerate() {
+        if step {
+            let mut line = String::new();
+            if input.read_line(&mut line).unwrap() == 0 {
+                return;
+            }
+        }
+        // the record replayed, so the move is valid
+        let m = Move::parse(m).unwrap();
+        let description = pair[0].describe(&m);
+        let annotation = record
+            .annotation(index)
+            .map_or(String::new(), |annotation| {
+                format!(" {}, {} is better", annotation.mark, annotation.better)
+            });
+        println!(
+            "\n{} {} ({}){}",
+            names[description.player],
+            language.describe(&description),
+            m,
+            annotation
+        );
+        if let Some((_, events)) = describe_scoring(&pair[0], &pair[1]) {
+            print_scoring(&events, &names, language);
+        }
+        let board = match changes {
+            true => render_changes(&pair[0], &pair[1], &names, &[], render),
+            false => render_state(&pair[1], &names, render),
+        };
+        println!("{}", board);
+    }
+}
+
+// follows a game as it's played, from a file or a live server
+fn watch(source: &str, game: Option<&str>, names: &[String], render: &RenderOptions) {
+    let mut spectator = Spectator::new();
+    if source.starts_with("ws://") || source.starts_with("wss://") {
+        let Some(game) = game else {
+            eprintln!("pick the live game to watch with --game");
+            return;
+        };
+        #[cfg(feature = "live")]
+        if let Err(error) = azul::live::spectate(source, game, |message| {
+            let update = spectator.update(message);
+            show_watched(&spectator, update, names, render)
+        }) {
+            eprintln!("could not watch {}: {}", source, error);
+        }
+        #[cfg(not(feature = "live"))]
+        eprintln!(
+            "could not watch {} {}: built without the live feature",
+            source, game
+        );
+        return;
+    }
+    let file = match File::open(source) {
+        Ok(file) => file,
+        Err(error) => {
+            eprintln!("could not open {}: {}", source, error);
+            return;
+        }
+    };
+    let result = tail(io::BufReader::new(file), WATCH_POLL, |line| {
+        let update = spectator.update_line(line);
+        show_watched(&spectator, update, names, render)
+    });
+    if let Err(error) = result {
+        eprintln!("could not read {}: {}", source, error);
+    }
+}
+
+// how often a watched file is checked for new moves
+const WATCH_POLL: Duration = Duration::from_millis(100);
+
+// redraws the boards if the position changed. false once the game is over
+fn show_watched(
+    spectator: &Spectator,
+    update: Result<bool, String>,
+    names: &[String],
+    render: &RenderOptions,
+) -> bool {
+    match update {
+        Ok(true) => {}
+        Ok(false) => return true,
+        Err(error) => {
+            eprintln!("{}", error);
+            return true;
+        }
+    }
+    let Some(state) = spectator.state() else {
+        return true;
+    };
+    let defaults = (1..=state.players.len())
+        .map(|index| format!("Player {}", index))
+        .collect();
+    let names = with_defaults(names, defaults);
+    if io::stdout().is_terminal() {
+        // clear the screen and start from the top
+        print!("\x1b[2J\x1b[H");
+    }
+    if let Some(m) = spectator.last_move() {
+        println!("last move {}", m);
+    }
+    let borrowed: Vec<_> = names.iter().map(String::as_str).collect();
+    println!("{}", render_state(state, &borrowed, render));
+    if spectator.is_over() {
+        Console::new(names).on_game_over(state);
+    }
+    !spectator.is_over()
+}
+
+// writes a recorded game as an HTML page
+fn annotate(path: &Path, output: &Path, depth: usize, thresholds: &Thresholds) {
+    let record = match GameRecord::load(path) {
+        Ok(record) => record,
+        Err(error) => {
+            eprintln!("could not load {}: {}", path.display(), error);
+            return;
+        }
+    };
+    let annotated = match azul::annotate::annotate(&record, depth, thresholds) {
+        Ok(annotated) => annotated,
+        Err(error) => {
+            eprintln!("could not replay {}: {}", path.display(), error);
+            return;
+        }
+    };
+    for annotation in &annotated.annotations {
+        println!(
+            "{}. {} {}, {} is better by {:.2} points",
+            annotation.index + 1,
+            annotated.moves[annotation.index],
+            annotation.mark,
+            annotation.better,
+            annotation.loss as f64 / 100.0
+        );
+    }
+    if let Err(error) = annotated.save(output) {
+        eprintln!("could not write {}: {}", output.display(), error);
+    }
+}
+
+fn report(path: &Path, output: &Path, depth: usize) {
+    let record = match GameRecord::load(path) {
+        Ok(record) => record,
+        Err(error) => {
+            eprintln!("could not load {}: {}", path.display(), error);
+            return;
+        }
+    };
+    let html = match azul::report::render(&record, depth) {
+        Ok(html) => html,
+        Err(error) => {
+            eprintln!("could not replay {}: {}", path.display(), error);
+            return;
+        }
+    };
+    if let Err(error) = fs::write(output, html) {
+        eprintln!("could not write {}: {}", output.display(), error);
+    }
+}
+
+// statistics over recorded games
+fn stats(paths: &[PathBuf], csv: Option<&Path>) {
+    let mut stats = Stats::default();
+    for path in paths {
+        let added = GameRecord::load(path)
+            .map_err(|error| error.to_string())
+            .and_then(|record| stats.add(&record).map_err(|error| error.to_string()));
+        if let Err(error) = added {
+            eprintln!("could not read {}: {}", path.display(), error);
+            return;
+        }
+    }
+    match csv {
+        Some(path) => {
+            if let Err(error) = fs::write(path, stats.to_csv()) {
+                eprintln!("could not write {}: {}", path.display(), error);
+            }
+        }
+        None => print!("{}", stats),
     }
 }
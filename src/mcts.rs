@@ -0,0 +1,146 @@
+//! Monte Carlo Tree Search using UCT and random playouts.
+//!
+//! The tree is open loop: nodes are reached by sequences of moves, and
+//! chance events are sampled anew on every iteration, so a node stands for
+//! all states reachable by its moves.
+
+use crate::minmax::StochasticGameState;
+use rand::{seq::SliceRandom, Rng};
+use std::time::{Duration, Instant};
+
+/// Search budget and tuning of the search.
+#[derive(Clone, Debug)]
+pub struct Mcts {
+    /// Maximum number of playouts
+    pub iterations: usize,
+    /// Stop after this much time, even if iterations remain
+    pub budget: Option<Duration>,
+    /// UCT exploration constant
+    pub exploration: f64,
+}
+
+impl Default for Mcts {
+    fn default() -> Self {
+        Self {
+            iterations: 1000,
+            budget: None,
+            exploration: std::f64::consts::SQRT_2,
+        }
+    }
+}
+
+struct Node<M> {
+    // player who made the move leading here
+    player: usize,
+    visits: f64,
+    // sum of rewards for player
+    reward: f64,
+    children: Vec<(M, Node<M>)>,
+}
+
+impl<M: Copy + PartialEq> Node<M> {
+    fn new(player: usize) -> Self {
+        Self {
+            player,
+            visits: 0.0,
+            reward: 0.0,
+            children: Vec::new(),
+        }
+    }
+
+    fn uct(&self, parent_visits: f64, exploration: f64) -> f64 {
+        self.reward / self.visits + exploration * (parent_visits.ln() / self.visits).sqrt()
+    }
+
+    // one iteration from this node: select, expand, playout and backpropagate.
+    // returns the winner of the playout.
+    fn iterate<S, R>(&mut self, state: S, mcts: &Mcts, rng: &mut R) -> Option<usize>
+    where
+        S: StochasticGameState<Move = M>,
+        R: Rng,
+    {
+        let state = state.resolve(rng);
+        let moves = state.legal_moves();
+        let winner = if state.result().is_over() || moves.is_empty() {
+            state.winner()
+        } else {
+            let untried: Vec<_> = moves
+                .iter()
+                .filter(|m| self.children.iter().all(|(child, _)| child != *m))
+                .collect();
+            if let Some(m) = untried.choose(rng) {
+                // expand
+                let mut child = Node::new(state.current_player());
+                let winner = playout(state.play(m), rng);
+                child.update(winner);
+                self.children.push((**m, child));
+                winner
+            } else {
+                // select among children legal in this particular state
+                let parent_visits = self.visits;
+                let (m, child) = self
+                    .children
+                    .iter_mut()
+                    .filter(|(m, _)| moves.contains(m))
+                    .max_by(|(_, a), (_, b)| {
+                        let a = a.uct(parent_visits, mcts.exploration);
+                        let b = b.uct(parent_visits, mcts.exploration);
+                        a.total_cmp(&b)
+                    })
+                    .unwrap();
+                child.iterate(state.play(m), mcts, rng)
+            }
+        };
+        self.update(winner);
+        winner
+    }
+
+    fn update(&mut self, winner: Option<usize>) {
+        self.visits += 1.0;
+        if winner == Some(self.player) {
+            self.reward += 1.0;
+        }
+    }
+}
+
+// play random moves until the game is over
+fn playout<S: StochasticGameState, R: Rng>(state: S, rng: &mut R) -> Option<usize> {
+    let mut state = state.resolve(rng);
+    let mut moves = Vec::new();
+    while !state.result().is_over() {
+        state.legal_moves_into(&mut moves);
+        // tiles that fit on a row aren't thrown on the floor, or playouts
+        // drag on for hundreds of rounds
+        state.prune_moves(&mut moves, 1);
+        let m = moves.choose(rng)?;
+        // playouts are never taken back, so the undo is dropped
+        state.apply_in_place(m);
+        state = state.resolve(rng);
+    }
+    // players sharing the victory aren't rewarded
+    state.winner()
+}
+
+impl Mcts {
+    /// Returns the most visited move from `state`, or None if there are no
+    /// legal moves.
+    pub fn search<S: StochasticGameState, R: Rng>(
+        &self,
+        state: &S,
+        rng: &mut R,
+    ) -> Option<S::Move> {
+        let deadline = self.budget.map(|budget| Instant::now() + budget);
+        // nobody moved to the root, so it's not rewarded for anyone
+        let mut root = Node::new(usize::MAX);
+        for _ in 0..self.iterations {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break;
+            }
+            root.iterate(state.clone(), self, rng);
+        }
+        root.children
+            .iter()
+            .max_by(|(_, a), (_, b)| a.visits.total_cmp(&b.visits))
+            .map(|(m, _)| *m)
+    }
+}
@@ -1,24 +1,270 @@
-use rand::{seq::SliceRandom, Rng};
-use std::hash::Hash;
+//! Game agnostic minmax search with alpha-beta pruning and principal
+//! variation search.
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// How a game stands. Finished games have the final scores of every player,
+/// in seating order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GameResult {
+    /// The game goes on
+    Ongoing,
+    /// The player won
+    Won { player: usize, scores: Vec<usize> },
+    /// The players share the victory
+    Draw {
+        players: Vec<usize>,
+        scores: Vec<usize>,
+    },
+}
+
+impl GameResult {
+    pub fn is_over(&self) -> bool {
+        *self != GameResult::Ongoing
+    }
+}
+
+/// A two (or more) player game where players take turns making moves.
+pub trait DeterministicGameState: Sized + Clone + Hash + Eq {
+    type Move: Copy + Eq + Hash + Debug;
+    /// What `apply_in_place` changed, for `undo` to restore.
+    type Undo;
 
-pub trait GameState: Sized + Clone + Hash + Eq {
     fn current_player(&self) -> usize;
-    fn children<R: Rng>(&self, rng: &mut R) -> Vec<Self>;
-    fn winner(&self) -> Option<usize>;
+    fn legal_moves(&self) -> Vec<Self::Move>;
+    /// Like `legal_moves`, writing the moves over `moves`. Games should
+    /// override this to reuse its allocation, as searches generate moves
+    /// into the same few vectors over and over.
+    fn legal_moves_into(&self, moves: &mut Vec<Self::Move>) {
+        *moves = self.legal_moves();
+    }
+    /// The state after making a legal move. May be a chance state.
+    fn play(&self, m: &Self::Move) -> Self;
+    /// Whether the game goes on, and if not who won.
+    fn result(&self) -> GameResult;
+    /// The sole winner of a finished game. None while it goes on, and when
+    /// players share the victory, see `winners`.
+    fn winner(&self) -> Option<usize> {
+        match self.result() {
+            GameResult::Won { player, .. } => Some(player),
+            GameResult::Ongoing | GameResult::Draw { .. } => None,
+        }
+    }
+    /// Every player sharing the victory of a finished game, a single one
+    /// unless it's a draw. Empty while the game goes on.
+    fn winners(&self) -> Vec<usize> {
+        match self.result() {
+            GameResult::Ongoing => Vec::new(),
+            GameResult::Won { player, .. } => vec![player],
+            GameResult::Draw { players, .. } => players,
+        }
+    }
+    /// Makes a legal move in place, like `play` but without copying the
+    /// state.
+    fn apply_in_place(&mut self, m: &Self::Move) -> Self::Undo;
+    /// Takes back the latest move made with `apply_in_place`.
+    fn undo(&mut self, undo: Self::Undo);
+
+    /// Whether the state can be evaluated statically. Search continues past
+    /// its depth until it reaches a quiet state, e.g. when something is
+    /// about to be scored.
+    fn is_quiet(&self) -> bool {
+        true
+    }
+
+    /// The state after each legal move, in the order of `legal_moves`.
+    fn children(&self) -> Vec<Self> {
+        self.legal_moves().iter().map(|m| self.play(m)).collect()
+    }
+
+    /// The legal moves except those leading to the same state as an
+    /// earlier one, e.g. taking the same tiles from either of two equal
+    /// factories.
+    fn distinct_moves(&self) -> Vec<Self::Move> {
+        let mut seen = HashSet::new();
+        self.legal_moves()
+            .into_iter()
+            .filter(|m| seen.insert(self.play(m)))
+            .collect()
+    }
+
+    /// Like `distinct_moves`, writing the moves over `moves`.
+    fn distinct_moves_into(&self, moves: &mut Vec<Self::Move>) {
+        *moves = self.distinct_moves();
+    }
+
+    /// Drops the moves that are clearly worse than another of `moves`, by
+    /// at least `margin` in a measure of the game's own, for searches to
+    /// skip near their leaves. Must keep at least one move. Keeps them
+    /// all by default.
+    fn prune_moves(&self, _moves: &mut Vec<Self::Move>, _margin: usize) {}
+
+    /// Key identifying the position in the transposition table. Games with
+    /// a cheaper (e.g. incremental) hash should override this.
+    fn key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
 }
 
-pub trait Evaluation<S: GameState> {
-    fn evaulate(&self, state: &S, player: usize) -> i32;
+/// A game where some states are resolved by chance (e.g. dealing tiles)
+/// rather than by a player. States hold only what every player knows, such
+/// as which tiles are in the bag but not in what order they come out, so
+/// searches sample chance events rather than know their outcomes.
+pub trait StochasticGameState: DeterministicGameState {
+    /// Whether a chance event must happen before anyone can move.
+    fn is_chance(&self) -> bool;
+    /// A random outcome of the chance event.
+    fn sample<R: Rng>(&self, rng: &mut R) -> Self;
+    /// Like `sample`, writing the outcome over `outcome`. Games should
+    /// override this to reuse its allocations, as searches sample into
+    /// the same few states over and over.
+    fn sample_into<R: Rng>(&self, rng: &mut R, outcome: &mut Self) {
+        *outcome = self.sample(rng);
+    }
+    /// Every outcome of the chance event with its probability, or None if
+    /// there are more than `limit` of them (or they can't be listed).
+    fn outcomes(&self, _limit: usize) -> Option<Vec<(f64, Self)>> {
+        None
+    }
 
-    // TODO: Move heuristic into separate trait
+    /// Resolves the chance event, if any.
+    fn resolve<R: Rng>(self, rng: &mut R) -> Self {
+        if self.is_chance() {
+            self.sample(rng)
+        } else {
+            self
+        }
+    }
+}
 
-    // may re-order (but not modify) states
+/// Static evaluation of game states.
+pub trait Evaluation<S: DeterministicGameState> {
+    /// How good the state is for `player`. Should account for the
+    /// opponents too, e.g. the lead over the best of them, so that
+    /// hurting an opponent counts as much as helping yourself. Values are
+    /// in hundredths of a point, the unit of the aspiration window and of
+    /// contempt.
+    fn evaulate(&self, state: &S, player: usize) -> i32;
+
+    // called with the value of each evaluated leaf
     fn update(&mut self, _state: &S, _value: i32) {}
-    fn heuristic(&self, _states: &mut Vec<S>) {}
+
+    /// Value of a game the player ends sharing the victory with others.
+    /// Positive values seek ties and negative ones avoid them. Wins and
+    /// losses are worth more and less than anything.
+    fn contempt(&self) -> i32 {
+        0
+    }
+
+    /// Evaluations answered from a cache so far, for evaluations keeping
+    /// one. Searches report the hits of the search in `SearchInfo`.
+    fn cache_hits(&self) -> u64 {
+        0
+    }
+}
+
+// e.g. an evaluation chosen at runtime
+impl<S: DeterministicGameState, E: Evaluation<S> + ?Sized> Evaluation<S> for Box<E> {
+    fn evaulate(&self, state: &S, player: usize) -> i32 {
+        (**self).evaulate(state, player)
+    }
+    fn update(&mut self, state: &S, value: i32) {
+        (**self).update(state, value)
+    }
+    fn contempt(&self) -> i32 {
+        (**self).contempt()
+    }
+    fn cache_hits(&self) -> u64 {
+        (**self).cache_hits()
+    }
+}
+
+// Outcomes sampled at chance nodes with too many outcomes to list, by
+// default
+const SAMPLE_COUNT: usize = 32;
+// Deepest a timed search goes
+const MAX_DEPTH: usize = 64;
+// Half width of the first aspiration window, in units of the evaluation
+const ASPIRATION_WINDOW: i32 = 300;
+// Most depth left at which moves are pruned, and the margin searches prune
+// by, see SearchOptions::pruning
+const PRUNING_DEPTH: usize = 2;
+const PRUNING_MARGIN: usize = 2;
+// Moves searched to the full depth before late move reductions, the least
+// depth left at which moves are reduced, and by how much. Reducing by two
+// moves keeps the player to move at the leaves of a two player game, as
+// evaluations of positions after the opponent's move are lower
+const FULL_DEPTH_MOVES: usize = 6;
+const REDUCTION_DEPTH: usize = 4;
+const REDUCTION: usize = 2;
+
+// How a stored value relates to the true value of the position
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Entry<M> {
+    key: u64,
+    depth: usize,
+    value: i32,
+    bound: Bound,
+    best: Option<M>,
+}
+
+/// Fixed size cache of search results, indexed by `DeterministicGameState::key`.
+pub struct TranspositionTable<M> {
+    entries: Vec<Option<Entry<M>>>,
+}
+
+impl<M: Copy> Default for TranspositionTable<M> {
+    fn default() -> Self {
+        Self::new(1 << 16)
+    }
+}
+
+impl<M: Copy> TranspositionTable<M> {
+    /// A table with room for at least `capacity` positions.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: vec![None; capacity.next_power_of_two()],
+        }
+    }
+    fn slot(&self, key: u64) -> usize {
+        key as usize & (self.entries.len() - 1)
+    }
+    fn probe(&self, key: u64) -> Option<&Entry<M>> {
+        self.entries[self.slot(key)]
+            .as_ref()
+            .filter(|entry| entry.key == key)
+    }
+    // keep the deeper result when two positions share a slot
+    fn store(&mut self, entry: Entry<M>) {
+        let slot = self.slot(entry.key);
+        match &self.entries[slot] {
+            Some(old) if old.key != entry.key && old.depth > entry.depth => {}
+            _ => self.entries[slot] = Some(entry),
+        }
+    }
 }
 
-// search code. returns child index and evaluation
-pub fn minmax<S: GameState, E: Evaluation<S>, R: Rng>(
+// search code. returns the best move and evaluation
+pub fn minmax<S: StochasticGameState, E: Evaluation<S>, R: Rng>(
     state: &S,
     evaluation: &mut E,
     rng: &mut R,
@@ -26,79 +272,838 @@ pub fn minmax<S: GameState, E: Evaluation<S>, R: Rng>(
     depth: usize,
     alpha: i32,
     beta: i32,
-) -> (Option<usize>, i32) {
-    if depth == 0 {
-        let e = evaluation.evaulate(&state, state.current_player());
-        //evaluation.update(&state, e);
-        return (None, e);
-    }
-    if let Some(winner) = state.winner() {
-        return if winner == player {
-            (None, i32::MAX)
+) -> (Option<S::Move>, i32) {
+    let mut table = TranspositionTable::default();
+    let mut searcher = Searcher::new(evaluation, rng, &mut table, player, None);
+    searcher
+        .minmax(&mut state.clone(), depth, alpha, beta)
+        .unwrap()
+}
+
+// Moves that caused cutoffs earlier in the search, used to search the
+// most promising children first
+struct MoveOrdering<M> {
+    // the two latest cutoff moves at each remaining depth
+    killers: Vec<[Option<M>; 2]>,
+    // cutoffs by move anywhere in the tree, weighted by depth squared
+    history: HashMap<M, usize>,
+}
+
+impl<M: Copy + Eq + Hash> MoveOrdering<M> {
+    fn new() -> Self {
+        Self {
+            killers: Vec::new(),
+            history: HashMap::new(),
+        }
+    }
+
+    // indices of moves in the order to search them. the best move from the
+    // transposition table goes first, then killers, then by history
+    fn order(&self, moves: &[M], hint: Option<M>, depth: usize, order: &mut Vec<usize>) {
+        let killers = self.killers.get(depth).copied().unwrap_or_default();
+        let priority = |index: usize| {
+            let m = moves[index];
+            if Some(m) == hint {
+                usize::MAX
+            } else if killers[0] == Some(m) {
+                usize::MAX - 1
+            } else if killers[1] == Some(m) {
+                usize::MAX - 2
+            } else {
+                self.history.get(&m).copied().unwrap_or(0)
+            }
+        };
+        order.clear();
+        order.extend(0..moves.len());
+        order.sort_by_key(|index| std::cmp::Reverse(priority(*index)));
+    }
+
+    fn cutoff(&mut self, m: M, depth: usize) {
+        if self.killers.len() <= depth {
+            self.killers.resize(depth + 1, [None, None]);
+        }
+        let killers = &mut self.killers[depth];
+        if killers[0] != Some(m) {
+            killers[1] = killers[0];
+            killers[0] = Some(m);
+        }
+        *self.history.entry(m).or_insert(0) += depth * depth;
+    }
+}
+
+// State shared by all nodes of one search
+struct Searcher<'a, S: DeterministicGameState, E, R> {
+    evaluation: &'a mut E,
+    rng: &'a mut R,
+    table: &'a mut TranspositionTable<S::Move>,
+    ordering: MoveOrdering<S::Move>,
+    player: usize,
+    // give up once passed
+    deadline: Option<Instant>,
+    // give up once stopped
+    stop: Option<StopToken>,
+    // best move at the root so far in the current iteration
+    root_best: Option<S::Move>,
+    // moves not to search at the root, for multi-PV
+    excluded: Vec<S::Move>,
+    // moves from the root to the current node
+    ply: usize,
+    // search only distinct_moves
+    deduplicate: bool,
+    // search moves late in the order less deep
+    reductions: bool,
+    // margin of DeterministicGameState::prune_moves near the leaves
+    pruning: Option<usize>,
+    // outcomes to sample at chance nodes
+    chance_samples: usize,
+    // seed of the samples at chance nodes, mixed with the ply, for common
+    // random numbers
+    chance_seed: Option<u64>,
+    // states to sample chance outcomes into, and vectors of moves and
+    // their order, reused from node to node rather than allocated anew
+    pool: Vec<S>,
+    move_pool: Vec<(Vec<S::Move>, Vec<usize>)>,
+    stats: Stats,
+}
+
+// counters for SearchInfo
+#[derive(Default)]
+struct Stats {
+    nodes: u64,
+    tt_hits: u64,
+    cutoffs: u64,
+    researches: u64,
+    pruned: u64,
+    max_ply: usize,
+    evaluations: u64,
+    // cache hits of the evaluation before the search
+    cache_hits: u64,
+}
+
+impl<'a, S: StochasticGameState, E: Evaluation<S>, R: Rng> Searcher<'a, S, E, R> {
+    fn new(
+        evaluation: &'a mut E,
+        rng: &'a mut R,
+        table: &'a mut TranspositionTable<S::Move>,
+        player: usize,
+        deadline: Option<Instant>,
+    ) -> Self {
+        Self {
+            rng,
+            table,
+            ordering: MoveOrdering::new(),
+            player,
+            deadline,
+            stop: None,
+            root_best: None,
+            excluded: Vec::new(),
+            ply: 0,
+            deduplicate: false,
+            reductions: false,
+            pruning: None,
+            chance_samples: SAMPLE_COUNT,
+            chance_seed: None,
+            pool: Vec::new(),
+            move_pool: Vec::new(),
+            stats: Stats {
+                cache_hits: evaluation.cache_hits(),
+                ..Stats::default()
+            },
+            evaluation,
+        }
+    }
+
+    // returns the best move and evaluation, or None if out of time or
+    // stopped. Moves are made and taken back on the state, which is
+    // unchanged on return
+    fn minmax(
+        &mut self,
+        state: &mut S,
+        depth: usize,
+        alpha: i32,
+        beta: i32,
+    ) -> Option<(Option<S::Move>, i32)> {
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+            || self.stop.as_ref().is_some_and(StopToken::is_stopped)
+        {
+            return None;
+        }
+        self.stats.nodes += 1;
+        self.stats.max_ply = self.stats.max_ply.max(self.ply);
+        // finished games are won, lost or drawn whatever the depth left
+        match state.result() {
+            GameResult::Ongoing => {}
+            GameResult::Won { player, .. } if player == self.player => {
+                return Some((None, i32::MAX))
+            }
+            GameResult::Draw { players, .. } if players.contains(&self.player) => {
+                return Some((None, self.evaluation.contempt()))
+            }
+            _ => return Some((None, i32::MIN)),
+        }
+        if depth == 0 && state.is_quiet() {
+            // always from the searching player's point of view, as minmax
+            // maximizes for that player only
+            let e = self.evaluation.evaulate(state, self.player);
+            self.stats.evaluations += 1;
+            //self.evaluation.update(state, e);
+            return Some((None, e));
+        }
+        if state.is_chance() {
+            return Some((None, self.chance_value(state, depth)?));
+        }
+
+        // the value of the root without some of its moves isn't the value
+        // of the position, so it is neither looked up nor stored
+        let partial = self.ply == 0 && !self.excluded.is_empty();
+        let key = state.key();
+        let mut hint = None;
+        if let Some(entry) = self.table.probe(key) {
+            self.stats.tt_hits += 1;
+            if entry.depth >= depth && !partial {
+                let cutoff = match entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => entry.value > beta,
+                    Bound::Upper => entry.value < alpha,
+                };
+                if cutoff {
+                    return Some((entry.best, entry.value));
+                }
+            }
+            hint = entry.best;
+        }
+
+        let (mut moves, mut order) = self.move_pool.pop().unwrap_or_default();
+        match self.deduplicate {
+            true => state.distinct_moves_into(&mut moves),
+            false => state.legal_moves_into(&mut moves),
+        }
+        if self.ply == 0 {
+            moves.retain(|m| !self.excluded.contains(m));
+        } else if let Some(margin) = self.pruning.filter(|_| depth <= PRUNING_DEPTH) {
+            let count = moves.len();
+            state.prune_moves(&mut moves, margin);
+            // a move must be searched, whatever the game pruned
+            if moves.is_empty() {
+                state.legal_moves_into(&mut moves);
+            }
+            self.stats.pruned += (count - moves.len()) as u64;
+        }
+        self.ordering.order(&moves, hint, depth, &mut order);
+
+        let maximizing = state.current_player() == self.player;
+        let window = (alpha, beta);
+        let (mut alpha, mut beta) = window;
+        let mut best_value = if maximizing { i32::MIN } else { i32::MAX };
+        let mut best_move = None;
+        for (rank, &index) in order.iter().enumerate() {
+            let undo = state.apply_in_place(&moves[index]);
+            self.ply += 1;
+            let reduction =
+                if self.reductions && rank >= FULL_DEPTH_MOVES && depth >= REDUCTION_DEPTH {
+                    REDUCTION
+                } else {
+                    0
+                };
+            let result = if rank == 0 {
+                self.minmax(state, depth.saturating_sub(1), alpha, beta)
+            } else {
+                self.scout(
+                    state,
+                    depth.saturating_sub(1),
+                    reduction,
+                    alpha,
+                    beta,
+                    maximizing,
+                )
+            };
+            self.ply -= 1;
+            state.undo(undo);
+            let new_value = result?.1;
+            if maximizing {
+                if new_value >= best_value {
+                    best_value = new_value;
+                    best_move = Some(moves[index]);
+                    if self.ply == 0 {
+                        self.root_best = best_move;
+                    }
+                }
+                if best_value > beta {
+                    self.stats.cutoffs += 1;
+                    self.ordering.cutoff(moves[index], depth);
+                    break; // β cutoff
+                }
+                alpha = alpha.max(best_value);
+            } else {
+                if new_value <= best_value {
+                    best_value = new_value;
+                    best_move = Some(moves[index]);
+                }
+                if best_value < alpha {
+                    self.stats.cutoffs += 1;
+                    self.ordering.cutoff(moves[index], depth);
+                    break; // α cutoff
+                }
+                beta = beta.min(best_value);
+            }
+        }
+
+        self.move_pool.push((moves, order));
+        let bound = if best_value > window.1 {
+            Bound::Lower
+        } else if best_value < window.0 {
+            Bound::Upper
+        } else {
+            Bound::Exact
+        };
+        if !partial {
+            self.table.store(Entry {
+                key,
+                depth,
+                value: best_value,
+                bound,
+                best: best_move,
+            });
+        }
+        Some((best_move, best_value))
+    }
+
+    // principal variation search of a move after the first. Ordering tends
+    // to put the best move first, so the others are only tested to be
+    // worse, with a window of a single value, and searched again with the
+    // full window if they turn out better. A move late in the order is
+    // tested `reduction` moves less deep first, and again to the full
+    // depth if it turns out better
+    fn scout(
+        &mut self,
+        state: &mut S,
+        depth: usize,
+        reduction: usize,
+        alpha: i32,
+        beta: i32,
+        maximizing: bool,
+    ) -> Option<(Option<S::Move>, i32)> {
+        let bound = if maximizing { alpha } else { beta };
+        let (best, value) = self.minmax(state, depth - reduction, bound, bound)?;
+        let better = if maximizing {
+            value > alpha && value <= beta
         } else {
-            (None, i32::MIN)
+            value < beta && value >= alpha
         };
+        if better && reduction > 0 {
+            self.stats.researches += 1;
+            self.scout(state, depth, 0, alpha, beta, maximizing)
+        } else if better {
+            self.minmax(state, depth, alpha, beta)
+        } else {
+            Some((best, value))
+        }
     }
 
-    if state.current_player() == player {
-        let mut best_value = i32::MIN;
-        let mut best_index = None;
-        let mut alpha = alpha;
-        let mut children = state.children(rng);
-        evaluation.heuristic(&mut children);
-        for (index, child) in children.iter().enumerate() {
-            let new_value = minmax(child, evaluation, rng, player, depth - 1, alpha, beta).1;
-            if new_value >= best_value {
-                best_value = new_value;
-                best_index = Some(index);
+    // searches with a narrow window around the value of the previous
+    // iteration of iterative deepening, widening it until the value is
+    // inside
+    fn aspiration(
+        &mut self,
+        state: &mut S,
+        depth: usize,
+        guess: i32,
+    ) -> Option<(Option<S::Move>, i32)> {
+        let mut delta = ASPIRATION_WINDOW;
+        loop {
+            let alpha = guess.saturating_sub(delta);
+            let beta = guess.saturating_add(delta);
+            let (best, value) = self.minmax(state, depth, alpha, beta)?;
+            if (value >= alpha && value <= beta) || (alpha == i32::MIN && beta == i32::MAX) {
+                return Some((best, value));
             }
-            if best_value > beta {
-                break; // β cutoff
+            delta = delta.saturating_mul(4);
+        }
+    }
+
+    // the best move and then the best moves after it by the transposition
+    // table, until a chance event or a position that isn't in the table
+    fn principal_variation(&mut self, state: &mut S, best: S::Move) -> Vec<S::Move> {
+        const MAX_LENGTH: usize = 64;
+        let mut pv = vec![best];
+        let mut undos = vec![state.apply_in_place(&best)];
+        while pv.len() < MAX_LENGTH && !state.is_chance() && !state.result().is_over() {
+            let Some(m) = self.table.probe(state.key()).and_then(|entry| entry.best) else {
+                break;
+            };
+            // guard against positions sharing a key
+            if !state.legal_moves().contains(&m) {
+                break;
             }
-            alpha = alpha.max(best_value);
-        }
-        //evaluation.update(best_state.as_ref().unwrap(), best_value);
-        (best_index, best_value)
-    } else {
-        let mut best_value = i32::MAX;
-        let mut best_index = None;
-        let mut beta = beta;
-        let mut children = state.children(rng);
-        evaluation.heuristic(&mut children);
-        for (index, child) in children.iter().enumerate() {
-            let new_value = minmax(child, evaluation, rng, player, depth - 1, alpha, beta).1;
-            if new_value <= best_value {
-                best_value = new_value;
-                best_index = Some(index);
+            pv.push(m);
+            undos.push(state.apply_in_place(&m));
+        }
+        while let Some(undo) = undos.pop() {
+            state.undo(undo);
+        }
+        pv
+    }
+
+    // expected value of a chance node. exact when there are few outcomes,
+    // otherwise estimated from chance_samples random ones
+    fn chance_value(&mut self, state: &S, depth: usize) -> Option<i32> {
+        let samples = self.chance_samples.max(1);
+        let mut value = 0.0;
+        if let Some(outcomes) = state.outcomes(samples) {
+            for (probability, mut outcome) in outcomes {
+                // bounds of the parent don't apply to a single outcome
+                let (_, v) = self.minmax(&mut outcome, depth, i32::MIN, i32::MAX)?;
+                value += probability * v as f64;
             }
-            if best_value < alpha {
-                break; // α cutoff
+            return Some(value.round() as i32);
+        }
+        let mut seeded = self.chance_seed.map(|seed| {
+            let ply = (self.ply as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+            StdRng::seed_from_u64(seed ^ ply)
+        });
+        // one sample at a time, into a state from the pool
+        let mut outcome = self.pool.pop().unwrap_or_else(|| state.clone());
+        for _ in 0..samples {
+            match &mut seeded {
+                Some(rng) => state.sample_into(rng, &mut outcome),
+                None => state.sample_into(self.rng, &mut outcome),
             }
-            beta = beta.min(best_value);
+            let (_, v) = self.minmax(&mut outcome, depth, i32::MIN, i32::MAX)?;
+            value += (1.0 / samples as f64) * v as f64;
         }
-        //evaluation.update(best_state.as_ref().unwrap(), best_value);
-        (best_index, best_value)
+        self.pool.push(outcome);
+        Some(value.round() as i32)
     }
 }
 
-pub fn search<S: GameState, E: Evaluation<S>, R: Rng>(
+/// What a search found out about a position, and statistics of the search.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchInfo<M> {
+    /// Value of the position for the player to move
+    pub score: i32,
+    /// The moves both sides are expected to make, starting with the best
+    /// move, up to the next chance event
+    pub pv: Vec<M>,
+    /// Depth of the deepest completed search
+    pub depth: usize,
+    /// Most moves ahead searched, including extensions past the depth
+    pub max_depth: usize,
+    /// Positions visited
+    pub nodes: u64,
+    /// Positions found in the transposition table
+    pub tt_hits: u64,
+    /// Moves that were good enough to skip the remaining moves
+    pub cutoffs: u64,
+    /// Reduced moves that turned out better and were searched again to
+    /// the full depth
+    pub researches: u64,
+    /// Moves left out as clearly worse than another
+    pub pruned: u64,
+    /// Positions evaluated statically
+    pub evaluations: u64,
+    /// Evaluations answered from the cache of the evaluation, if it has one
+    pub cache_hits: u64,
+    pub elapsed: Duration,
+}
+
+impl<M> SearchInfo<M> {
+    fn new<S: DeterministicGameState<Move = M>, E: Evaluation<S>, R>(
+        searcher: &Searcher<S, E, R>,
+        score: i32,
+        pv: Vec<M>,
+        depth: usize,
+        start: Instant,
+    ) -> Self {
+        Self {
+            score,
+            pv,
+            depth,
+            max_depth: searcher.stats.max_ply,
+            nodes: searcher.stats.nodes,
+            tt_hits: searcher.stats.tt_hits,
+            cutoffs: searcher.stats.cutoffs,
+            researches: searcher.stats.researches,
+            pruned: searcher.stats.pruned,
+            evaluations: searcher.stats.evaluations,
+            cache_hits: searcher.evaluation.cache_hits() - searcher.stats.cache_hits,
+            elapsed: start.elapsed(),
+        }
+    }
+}
+
+/// Flag for stopping searches from another thread, e.g. when the move
+/// they are searching for has been made. Clones share the flag.
+#[derive(Clone, Debug, Default)]
+pub struct StopToken(Arc<AtomicBool>);
+
+impl StopToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops the searches with the token, which then return the best move
+    /// they have found so far.
+    pub fn stop(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// tokens are equal when they share the flag
+impl PartialEq for StopToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for StopToken {}
+
+/// How far and how thoroughly a search looks ahead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Moves to search ahead. With a time budget, the deepest to go
+    pub depth: usize,
+    /// Search only one of the moves leading to the same state, see
+    /// `DeterministicGameState::distinct_moves`
+    pub deduplicate: bool,
+    /// Outcomes sampled at chance nodes with too many outcomes to list.
+    /// More samples estimate the value of a deal better but take longer
+    pub chance_samples: usize,
+    /// Sample the same outcomes at every chance node the same number of
+    /// moves ahead, so that sibling moves are compared on the same deals
+    /// rather than on each their own luck
+    pub common_random_numbers: bool,
+    /// Search one level deeper at a time until the budget runs out,
+    /// instead of searching `depth` moves ahead right away
+    pub time_budget: Option<Duration>,
+    /// Stop searching when the token is stopped, with the result of the
+    /// deepest completed search, or if there is none the best move found
+    /// so far
+    pub stop: Option<StopToken>,
+    /// Search this many determinizations, each with a single deal sampled
+    /// at every chance node, and play the move most of them found best,
+    /// rather than averaging over samples within one search. A time budget
+    /// is shared between them
+    pub determinizations: usize,
+    /// Search moves late in the move order less deep, and again to the
+    /// full depth only if they turn out better than the best so far.
+    /// Searches deeper in the same time, at the risk of missing a good
+    /// move ordered late
+    pub late_move_reductions: bool,
+    /// Leave out the moves that `DeterministicGameState::prune_moves`
+    /// finds clearly worse than another by this margin, in the last moves
+    /// of the search. The root keeps every move. None searches them all
+    pub pruning: Option<usize>,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            depth: 4,
+            deduplicate: false,
+            chance_samples: SAMPLE_COUNT,
+            common_random_numbers: true,
+            time_budget: None,
+            stop: None,
+            determinizations: 1,
+            late_move_reductions: true,
+            pruning: Some(PRUNING_MARGIN),
+        }
+    }
+}
+
+impl SearchOptions {
+    /// Searches `depth` moves ahead.
+    pub fn depth(depth: usize) -> Self {
+        Self {
+            depth,
+            ..Self::default()
+        }
+    }
+
+    /// Searches as deep as `budget` allows.
+    pub fn timed(budget: Duration) -> Self {
+        Self {
+            depth: MAX_DEPTH,
+            time_budget: Some(budget),
+            ..Self::default()
+        }
+    }
+}
+
+/// Searches `depth` moves ahead and returns the best move from `state`.
+/// Callers make the move and deal what comes next with their own rng.
+pub fn search<S: StochasticGameState, E: Evaluation<S>, R: Rng>(
     state: &S,
     evaluation: &mut E,
     rng: &mut R,
     depth: usize,
-) -> Option<S> {
+) -> Option<S::Move> {
+    search_with_info(state, evaluation, rng, depth).0
+}
+
+/// Like `search`, also returning the score and principal variation.
+pub fn search_with_info<S: StochasticGameState, E: Evaluation<S>, R: Rng>(
+    state: &S,
+    evaluation: &mut E,
+    rng: &mut R,
+    depth: usize,
+) -> (Option<S::Move>, SearchInfo<S::Move>) {
+    search_with_options(state, evaluation, rng, &SearchOptions::depth(depth))
+}
+
+/// Iterative deepening search. Searches one level deeper at a time until
+/// `budget` runs out and returns the best move found by the deepest
+/// completed search. The first level is always completed.
+pub fn search_timed<S: StochasticGameState, E: Evaluation<S>, R: Rng>(
+    state: &S,
+    evaluation: &mut E,
+    budget: Duration,
+    rng: &mut R,
+) -> Option<S::Move> {
+    search_timed_with_info(state, evaluation, budget, rng).0
+}
+
+/// Like `search_timed`, also returning the score and principal variation
+/// of the deepest completed search.
+pub fn search_timed_with_info<S: StochasticGameState, E: Evaluation<S>, R: Rng>(
+    state: &S,
+    evaluation: &mut E,
+    budget: Duration,
+    rng: &mut R,
+) -> (Option<S::Move>, SearchInfo<S::Move>) {
+    search_with_options(state, evaluation, rng, &SearchOptions::timed(budget))
+}
+
+/// Searches as the options say and returns the best move from `state`, with
+/// the score and principal variation of the deepest completed search.
+pub fn search_with_options<S: StochasticGameState, E: Evaluation<S>, R: Rng>(
+    state: &S,
+    evaluation: &mut E,
+    rng: &mut R,
+    options: &SearchOptions,
+) -> (Option<S::Move>, SearchInfo<S::Move>) {
+    // shared between iterations, so earlier iterations order moves for later
+    let mut table = TranspositionTable::default();
+    search_with_table(state, evaluation, rng, options, &mut table)
+}
+
+/// Like `search_with_options`, but with results of earlier searches in
+/// `table`, e.g. of pondering, which the search adds to. The values in the
+/// table are for the player to move, so only share it between searches for
+/// the same player.
+pub fn search_with_table<S: StochasticGameState, E: Evaluation<S>, R: Rng>(
+    state: &S,
+    evaluation: &mut E,
+    rng: &mut R,
+    options: &SearchOptions,
+    table: &mut TranspositionTable<S::Move>,
+) -> (Option<S::Move>, SearchInfo<S::Move>) {
+    search_excluding(state, evaluation, rng, options, table, Vec::new())
+}
+
+/// The `lines` best moves from `state`, best first, each with its score
+/// and principal variation, e.g. to show the alternatives to the best
+/// move. Every line is a search of its own, with the moves of the earlier
+/// lines left out, so a time budget is per line. The lines sample the same
+/// deals, so that their scores compare.
+pub fn search_multipv<S: StochasticGameState, E: Evaluation<S>, R: Rng>(
+    state: &S,
+    evaluation: &mut E,
+    rng: &mut R,
+    options: &SearchOptions,
+    lines: usize,
+) -> Vec<SearchInfo<S::Move>> {
+    let seed = rng.gen();
+    let mut table = TranspositionTable::default();
+    let mut infos: Vec<SearchInfo<S::Move>> = Vec::new();
+    while infos.len() < lines {
+        let excluded = infos.iter().map(|info| info.pv[0]).collect();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (_, info) =
+            search_excluding(state, evaluation, &mut rng, options, &mut table, excluded);
+        // no moves left
+        if info.pv.is_empty() {
+            break;
+        }
+        infos.push(info);
+    }
+    infos
+}
+
+/// Searches `state` as if `m` were its only move, e.g. to compare a move
+/// that was played to the best one with the same options and seed.
+pub fn search_move<S: StochasticGameState, E: Evaluation<S>, R: Rng>(
+    state: &S,
+    evaluation: &mut E,
+    rng: &mut R,
+    options: &SearchOptions,
+    m: S::Move,
+) -> SearchInfo<S::Move> {
+    let mut table = TranspositionTable::default();
+    let mut excluded = state.legal_moves();
+    excluded.retain(|other| *other != m);
+    search_excluding(state, evaluation, rng, options, &mut table, excluded).1
+}
+
+// search_with_table without some of the moves at the root
+fn search_excluding<S: StochasticGameState, E: Evaluation<S>, R: Rng>(
+    state: &S,
+    evaluation: &mut E,
+    rng: &mut R,
+    options: &SearchOptions,
+    table: &mut TranspositionTable<S::Move>,
+    excluded: Vec<S::Move>,
+) -> (Option<S::Move>, SearchInfo<S::Move>) {
+    let count = options.determinizations;
+    if count <= 1 {
+        return search_once(state, evaluation, rng, options, table, excluded);
+    }
+    let start = Instant::now();
+    // a single sample from a seed of its own at every chance node fixes
+    // the deals of a determinization
+    let options = SearchOptions {
+        chance_samples: 1,
+        common_random_numbers: true,
+        time_budget: options.time_budget.map(|budget| budget / count as u32),
+        ..options.clone()
+    };
+    let mut infos = Vec::new();
+    for index in 0..count {
+        // values in the table only hold for the deals they were found with
+        let mut fresh = TranspositionTable::default();
+        let table = if index == 0 { &mut *table } else { &mut fresh };
+        let (_, info) = search_once(state, evaluation, rng, &options, table, excluded.clone());
+        infos.push(info);
+        if options.stop.as_ref().is_some_and(StopToken::is_stopped) {
+            break;
+        }
+    }
+    // the move found best by the most determinizations, ties broken by
+    // their average score
+    let mut votes: Vec<(S::Move, usize, i64)> = Vec::new();
+    for info in &infos {
+        let Some(&m) = info.pv.first() else {
+            continue;
+        };
+        match votes.iter_mut().find(|(other, _, _)| *other == m) {
+            Some((_, count, total)) => {
+                *count += 1;
+                *total += info.score as i64;
+            }
+            None => votes.push((m, 1, info.score as i64)),
+        }
+    }
+    let best = votes
+        .iter()
+        .max_by(|(_, a, a_total), (_, b, b_total)| {
+            let average = |total: i64, count: usize| total as f64 / count as f64;
+            a.cmp(b).then(
+                average(*a_total, *a)
+                    .partial_cmp(&average(*b_total, *b))
+                    .unwrap(),
+            )
+        })
+        .copied();
+    let mut info = match best {
+        Some((m, count, total)) => {
+            let first = infos.iter().find(|info| info.pv.first() == Some(&m));
+            SearchInfo {
+                score: (total / count as i64) as i32,
+                ..first.unwrap().clone()
+            }
+        }
+        None => infos[0].clone(),
+    };
+    info.depth = infos.iter().map(|info| info.depth).min().unwrap();
+    info.max_depth = infos.iter().map(|info| info.max_depth).max().unwrap();
+    info.nodes = infos.iter().map(|info| info.nodes).sum();
+    info.tt_hits = infos.iter().map(|info| info.tt_hits).sum();
+    info.cutoffs = infos.iter().map(|info| info.cutoffs).sum();
+    info.researches = infos.iter().map(|info| info.researches).sum();
+    info.pruned = infos.iter().map(|info| info.pruned).sum();
+    info.evaluations = infos.iter().map(|info| info.evaluations).sum();
+    info.cache_hits = infos.iter().map(|info| info.cache_hits).sum();
+    info.elapsed = start.elapsed();
+    (best.map(|(m, _, _)| m), info)
+}
+
+// search_excluding of a single determinization, or of all deals at once
+fn search_once<S: StochasticGameState, E: Evaluation<S>, R: Rng>(
+    state: &S,
+    evaluation: &mut E,
+    rng: &mut R,
+    options: &SearchOptions,
+    table: &mut TranspositionTable<S::Move>,
+    excluded: Vec<S::Move>,
+) -> (Option<S::Move>, SearchInfo<S::Move>) {
+    let start = Instant::now();
     let player = state.current_player();
-    if let Some(index) = minmax(state, evaluation, rng, player, depth, i32::MIN, i32::MAX).0 {
-        // TODO: children called twice - once in minmax and once here...
-        // The might get different bags due to rng
-        let children = state.children(rng);
-        Some(children[index].clone())
-    } else {
-        None
+    let mut searcher = Searcher::new(evaluation, rng, table, player, None);
+    searcher.excluded = excluded;
+    searcher.stop = options.stop.clone();
+    searcher.deduplicate = options.deduplicate;
+    searcher.reductions = options.late_move_reductions;
+    searcher.pruning = options.pruning;
+    searcher.chance_samples = options.chance_samples;
+    if options.common_random_numbers {
+        searcher.chance_seed = Some(searcher.rng.gen());
+    }
+    let mut root = state.clone();
+    let mut best = None;
+    let (mut score, mut pv, mut completed) = (0, Vec::new(), 0);
+    let first = match options.time_budget {
+        Some(_) => 1.min(options.depth),
+        None => options.depth,
+    };
+    for depth in first..=options.depth {
+        // the first level must complete
+        searcher.deadline = options
+            .time_budget
+            .filter(|_| depth > first)
+            .map(|budget| start + budget);
+        let result = match completed {
+            0 => searcher.minmax(&mut root, depth, i32::MIN, i32::MAX),
+            _ => searcher.aspiration(&mut root, depth, score),
+        };
+        match result {
+            Some((m, value)) => {
+                best = m;
+                score = value;
+                pv = match m {
+                    Some(m) => searcher.principal_variation(&mut root, m),
+                    None => Vec::new(),
+                };
+                completed = depth;
+            }
+            // out of time or stopped, keep result from previous depth
+            None if completed > 0 => break,
+            // stopped during the first depth, take what it found so far
+            None => {
+                let excluded = &searcher.excluded;
+                best = searcher.root_best.or_else(|| {
+                    let moves = state.legal_moves();
+                    moves.into_iter().find(|m| !excluded.contains(m))
+                });
+                pv = best.into_iter().collect();
+                break;
+            }
+        }
     }
+    (
+        best,
+        SearchInfo::new(&searcher, score, pv, completed, start),
+    )
 }
 
-pub fn random_move<S: GameState, R: Rng>(state: &S, rng: &mut R) -> S {
-    let children = state.children(rng);
-    children.choose(rng).unwrap().clone()
+/// Returns a uniformly random child of `state`.
+pub fn random_move<S: StochasticGameState, R: Rng>(state: &S, rng: &mut R) -> S {
+    let moves = state.legal_moves();
+    let m = moves.choose(rng).unwrap();
+    state.play(m).resolve(rng)
 }
@@ -0,0 +1,382 @@
+//! Compact single line notation for positions, similar to FEN in chess.
+//!
+//! Fields are separated by spaces:
+//!
+//! ```text
+//! <factories> <center> <lid> <player>... <moves> [round] [free]
+//! ```
+//!
+//! * Tiles are written as letters (`B`lack, `W`hite, `A`zul, `Y`ellow,
+//!   `R`ed), optionally prefixed with a count, e.g. `2BW` is two black and
+//!   one white tile. No tiles at all is written `-`.
+//! * Factories are separated by `/`.
+//! * Each player is `<points>:<pattern rows>:<wall>:<floor>`. The five
+//!   pattern rows are separated by `/` and each is empty (`-`) or a count
+//!   and a letter. The wall is five rows of five cells separated by `/`,
+//!   with a letter for each placed tile and `.` for empty cells.
+//! * `moves` is the number of moves made, so the player to move is
+//!   `moves % players`.
+//! * `round` is the round, e.g. `round3`, left out in the first round.
+//! * Games of the free wall variant end with `free`. Complete pattern rows
+//!   are followed by `@` and the wall column chosen for them, e.g. `3R@2`.
+//!
+//! The bag holds whatever tiles remain. A new two player game, dealt:
+//!
+//! ```text
+//! 2BWA/2WAY/3AR/BY2R/W2YR - - 0:-/-/-/-/-:...../...../...../...../.....:- 0:-/-/-/-/-:...../...../...../...../.....:- 0
+//! ```
+//!
+//! Moves are written `<origin> <tile> -> <destination>`, where the origin
+//! is `F1` to `F9` for a factory or `C` for the center, and the destination
+//! is `row1` to `row5` or `floor`. Taking red from the third factory to the
+//! second pattern row is `F3 R -> row2`. In the free variant moves
+//! completing a row add the wall column, e.g. `F3 R -> row2@4`. When
+//! parsing, case doesn't matter and the arrow may be left out.
+
+use crate::azul::{
+    Destination, Floor, Move, Origin, Player, State, Tile, TileSet, Wall, WallVariant, TILES, WALL,
+};
+use std::{fmt, iter, str::FromStr};
+
+/// Error in notation of a position or move.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotationError(pub String);
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "bad notation: {}", self.0)
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+fn error<T>(message: impl Into<String>) -> Result<T, NotationError> {
+    Err(NotationError(message.into()))
+}
+
+pub(crate) fn tiles_to_notation(tiles: &TileSet) -> String {
+    if tiles.len() == 0 {
+        return "-".to_string();
+    }
+    TILES
+        .iter()
+        .filter(|tile| tiles.get(**tile) > 0)
+        .map(|tile| match tiles.get(*tile) {
+            1 => tile.letter().to_string(),
+            count => format!("{}{}", count, tile.letter()),
+        })
+        .collect()
+}
+
+pub(crate) fn tiles_from_notation(text: &str) -> Result<TileSet, NotationError> {
+    let mut tiles = TileSet::new();
+    if text == "-" {
+        return Ok(tiles);
+    }
+    let mut count = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            count.push(c);
+            continue;
+        }
+        let Some(tile) = Tile::from_letter(c) else {
+            return error(format!("unknown tile '{}'", c));
+        };
+        let n = match count.as_str() {
+            "" => 1,
+            count => count
+                .parse::<usize>()
+                .or_else(|_| error(format!("bad count '{}'", count)))?,
+        };
+        if n > 20 - tiles.get(tile) {
+            return error(format!("more than 20 {} tiles", tile));
+        }
+        tiles.add(tile, n);
+        count.clear();
+    }
+    if !count.is_empty() {
+        return error(format!("count without tile in '{}'", text));
+    }
+    Ok(tiles)
+}
+
+fn player_to_notation(player: &Player) -> String {
+    let rows: Vec<_> = player
+        .rows
+        .iter()
+        .zip(player.columns)
+        .map(|(row, column)| match (row, column) {
+            (Some((tile, count)), Some(column)) => {
+                format!("{}{}@{}", count, tile.letter(), column + 1)
+            }
+            (Some((tile, count)), None) => format!("{}{}", count, tile.letter()),
+            (None, _) => "-".to_string(),
+        })
+        .collect();
+    let wall: Vec<String> = (0..5)
+        .map(|row| {
+            (0..5)
+                .map(|column| match player.wall.tile(row, column) {
+                    Some(tile) => tile.letter(),
+                    None => '.',
+                })
+                .collect()
+        })
+        .collect();
+    format!(
+        "{}:{}:{}:{}",
+        player.points,
+        rows.join("/"),
+        wall.join("/"),
+        tiles_to_notation(&player.floor.tiles)
+    )
+}
+
+fn player_from_notation(text: &str, variant: WallVariant) -> Result<Player, NotationError> {
+    let fields: Vec<_> = text.split(':').collect();
+    let [points, rows, wall, floor] = fields[..] else {
+        return error(format!("player '{}' needs four fields", text));
+    };
+    let mut player = Player::new();
+    player.points = points
+        .parse()
+        .or_else(|_| error(format!("bad points '{}'", points)))?;
+
+    let rows: Vec<_> = rows.split('/').collect();
+    if rows.len() != 5 {
+        return error("there must be five pattern rows");
+    }
+    for (row_index, row) in rows.iter().enumerate() {
+        if *row == "-" {
+            continue;
+        }
+        let (row, column) = match row.split_once('@') {
+            Some((row, column)) if variant == WallVariant::Free => {
+                let column = match column.parse::<usize>() {
+                    Ok(column @ 1..=5) => column - 1,
+                    _ => return error(format!("bad wall column '{}'", column)),
+                };
+                (row, Some(column))
+            }
+            _ => (*row, None),
+        };
+        let tiles = tiles_from_notation(row)?;
+        let Some(tile) = TILES.into_iter().find(|tile| tiles.get(*tile) > 0) else {
+            return error(format!("empty pattern row '{}'", row));
+        };
+        let count = tiles.get(tile);
+        if tiles.len() != count || count > row_index + 1 {
+            return error(format!("bad pattern row '{}'", row));
+        }
+        if column.is_some() && count <= row_index {
+            return error(format!("column chosen for partial pattern row '{}'", row));
+        }
+        player.rows[row_index] = Some((tile, count));
+        player.columns[row_index] = column;
+    }
+
+    let wall: Vec<_> = wall.split('/').collect();
+    if wall.len() != 5 || wall.iter().any(|row| row.chars().count() != 5) {
+        return error("the wall must be five rows of five cells");
+    }
+    let mut rows = [[None; 5]; 5];
+    for (row_index, row) in wall.iter().enumerate() {
+        for (column_index, cell) in row.chars().enumerate() {
+            rows[row_index][column_index] = match (cell, Tile::from_letter(cell)) {
+                ('.', _) => None,
+                // any color goes on the free wall
+                (_, Some(tile)) if variant == WallVariant::Free => Some(tile),
+                (_, Some(tile)) if WALL[row_index][column_index] == tile => Some(tile),
+                _ => return error(format!("bad wall cell '{}'", cell)),
+            };
+        }
+    }
+    player.wall = Wall::from(rows);
+
+    let tiles = tiles_from_notation(floor)?;
+    if tiles.len() > 7 {
+        return error("the floor line holds at most seven tiles");
+    }
+    player.floor = Floor { tiles };
+    Ok(player)
+}
+
+pub(crate) fn factories_to_notation(factories: &[TileSet]) -> String {
+    match factories.is_empty() {
+        true => "-".to_string(),
+        false => factories
+            .iter()
+            .map(tiles_to_notation)
+            .collect::<Vec<_>>()
+            .join("/"),
+    }
+}
+
+pub(crate) fn factories_from_notation(text: &str) -> Result<Vec<TileSet>, NotationError> {
+    match text {
+        "-" => Ok(Vec::new()),
+        factories => factories.split('/').map(tiles_from_notation).collect(),
+    }
+}
+
+impl State {
+    /// The position in notation, see the `notation` module.
+    pub fn to_notation(&self) -> String {
+        iter::once(factories_to_notation(&self.factories))
+            .chain(iter::once(tiles_to_notation(&self.center)))
+            .chain(iter::once(tiles_to_notation(&self.lid)))
+            .chain(self.players.iter().map(player_to_notation))
+            .chain(iter::once(self.moves.to_string()))
+            .chain((self.round != 1).then(|| format!("round{}", self.round)))
+            .chain((self.variant == WallVariant::Free).then(|| "free".to_string()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Parses a position written by `to_notation`.
+    pub fn from_notation(text: &str) -> Result<State, NotationError> {
+        let mut fields: Vec<_> = text.split_whitespace().collect();
+        let variant = match fields.last() {
+            Some(&"free") => {
+                fields.pop();
+                WallVariant::Free
+            }
+            _ => WallVariant::Colored,
+        };
+        let round = match fields.last().and_then(|field| field.strip_prefix("round")) {
+            Some(round) => {
+                fields.pop();
+                round
+                    .parse()
+                    .or_else(|_| error(format!("bad round '{}'", round)))?
+            }
+            None => 1,
+        };
+        if fields.len() < 5 {
+            return error("expected factories, center, lid, players and moves");
+        }
+        let factories = factories_from_notation(fields[0])?;
+        let center = tiles_from_notation(fields[1])?;
+        let lid = tiles_from_notation(fields[2])?;
+        let players = fields[3..fields.len() - 1]
+            .iter()
+            .map(|player| player_from_notation(player, variant))
+            .collect::<Result<Vec<_>, _>>()?;
+        let moves = fields[fields.len() - 1];
+        let moves = moves
+            .parse()
+            .or_else(|_| error(format!("bad move count '{}'", moves)))?;
+
+        // the bag holds the rest
+        let mut used = [0; 5];
+        for tiles in factories
+            .iter()
+            .chain([&center, &lid])
+            .chain(players.iter().map(|player| &player.floor.tiles))
+        {
+            for tile in TILES {
+                used[tile as usize] += tiles.get(tile);
+            }
+        }
+        for player in &players {
+            for (tile, count) in player.rows.iter().flatten() {
+                used[*tile as usize] += count;
+            }
+            for (row, column) in (0..5).flat_map(|row| (0..5).map(move |column| (row, column))) {
+                if let Some(tile) = player.wall.tile(row, column) {
+                    used[tile as usize] += 1;
+                }
+            }
+        }
+        let mut bag = TileSet::new();
+        for tile in TILES {
+            if used[tile as usize] > 20 {
+                return error(format!("more than 20 {} tiles", tile));
+            }
+            bag.add(tile, 20 - used[tile as usize]);
+        }
+        let mut state = State::from_parts(bag, factories, center, lid, players, moves, variant)
+            .or_else(|e| error(e.to_string()))?;
+        state.round = round;
+        Ok(state)
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.origin {
+            Origin::Factory(index) => write!(f, "F{}", index + 1)?,
+            Origin::Center => f.write_str("C")?,
+        }
+        write!(f, " {} -> ", self.tile.letter())?;
+        match self.destination {
+            Destination::Row(index) => write!(f, "row{}", index + 1),
+            Destination::Wall(index, column) => write!(f, "row{}@{}", index + 1, column + 1),
+            Destination::Floor => f.write_str("floor"),
+        }
+    }
+}
+
+// one based number after a prefix, e.g. the 3 of F3
+fn index_after(text: &str, prefix: &str) -> Option<usize> {
+    let number: usize = text.strip_prefix(prefix)?.parse().ok()?;
+    number.checked_sub(1)
+}
+
+impl Move {
+    /// Parses a move in notation, e.g. `F3 R -> row2` or `C Y floor`. See
+    /// the `notation` module.
+    pub fn parse(text: &str) -> Result<Move, NotationError> {
+        let lower = text.to_ascii_lowercase();
+        let fields: Vec<_> = lower
+            .split_whitespace()
+            .filter(|field| *field != "->")
+            .collect();
+        let [origin, tile, destination] = fields[..] else {
+            return error(format!(
+                "expected origin, tile and destination in '{}'",
+                text
+            ));
+        };
+        let origin = match origin {
+            "c" => Origin::Center,
+            origin => match index_after(origin, "f") {
+                Some(index) => Origin::Factory(index),
+                None => return error(format!("bad origin '{}'", origin)),
+            },
+        };
+        let letters: Vec<_> = tile.to_ascii_uppercase().chars().collect();
+        let Some(tile) = (match letters[..] {
+            [letter] => Tile::from_letter(letter),
+            _ => None,
+        }) else {
+            return error(format!("unknown tile '{}'", tile));
+        };
+        let destination = match destination {
+            "floor" => Destination::Floor,
+            destination => match destination.split_once('@') {
+                Some((row, column)) => match (index_after(row, "row"), index_after(column, "")) {
+                    (Some(index), Some(column)) => Destination::Wall(index, column),
+                    _ => return error(format!("bad destination '{}'", destination)),
+                },
+                None => match index_after(destination, "row") {
+                    Some(index) => Destination::Row(index),
+                    None => return error(format!("bad destination '{}'", destination)),
+                },
+            },
+        };
+        Ok(Move {
+            origin,
+            tile,
+            destination,
+        })
+    }
+}
+
+impl FromStr for Move {
+    type Err = NotationError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Move::parse(text)
+    }
+}
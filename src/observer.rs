@@ -0,0 +1,326 @@
+//! Hooks on the progress of a game, so that logs, displays, statistics and
+//! broadcasts can follow games without changing the loop that plays them.
+//! `engine::play_observed` tells an observer about every move it makes.
+
+use crate::{
+    azul::{MoveDescription, ScoreEvent, State},
+    i18n::{Language, Text},
+    minmax::{DeterministicGameState, GameResult},
+    notation::factories_to_notation,
+};
+#[cfg(feature = "serde")]
+use serde_json::json;
+use std::io::{self, Write};
+
+/// Something following a game. Every callback does nothing by default.
+pub trait Observer {
+    /// The player to move in `before` made the move, leading to `after`,
+    /// before any new deal.
+    fn on_move(&mut self, _before: &State, _description: &MoveDescription, _after: &State) {}
+    /// The move ended the round, and `scoring` is how the points of every
+    /// player changed, in seating order.
+    fn on_round_end(&mut self, _state: &State, _scoring: &[Vec<ScoreEvent>]) {}
+    /// New factories were dealt for the next round.
+    fn on_deal(&mut self, _state: &State) {}
+    /// The game is over, with `state` the final position.
+    fn on_game_over(&mut self, _state: &State) {}
+}
+
+/// Observes nothing.
+impl Observer for () {}
+
+impl<O: Observer + ?Sized> Observer for &mut O {
+    fn on_move(&mut self, before: &State, description: &MoveDescription, after: &State) {
+        (**self).on_move(before, description, after);
+    }
+    fn on_round_end(&mut self, state: &State, scoring: &[Vec<ScoreEvent>]) {
+        (**self).on_round_end(state, scoring);
+    }
+    fn on_deal(&mut self, state: &State) {
+        (**self).on_deal(state);
+    }
+    fn on_game_over(&mut self, state: &State) {
+        (**self).on_game_over(state);
+    }
+}
+
+/// Observes if there is an observer.
+impl<O: Observer> Observer for Option<O> {
+    fn on_move(&mut self, before: &State, description: &MoveDescription, after: &State) {
+        if let Some(observer) = self {
+            observer.on_move(before, description, after);
+        }
+    }
+    fn on_round_end(&mut self, state: &State, scoring: &[Vec<ScoreEvent>]) {
+        if let Some(observer) = self {
+            observer.on_round_end(state, scoring);
+        }
+    }
+    fn on_deal(&mut self, state: &State) {
+        if let Some(observer) = self {
+            observer.on_deal(state);
+        }
+    }
+    fn on_game_over(&mut self, state: &State) {
+        if let Some(observer) = self {
+            observer.on_game_over(state);
+        }
+    }
+}
+
+/// Every observer in turn, to follow a game in several ways at once.
+impl Observer for Vec<Box<dyn Observer + '_>> {
+    fn on_move(&mut self, before: &State, description: &MoveDescription, after: &State) {
+        for observer in self {
+            observer.on_move(before, description, after);
+        }
+    }
+    fn on_round_end(&mut self, state: &State, scoring: &[Vec<ScoreEvent>]) {
+        for observer in self {
+            observer.on_round_end(state, scoring);
+        }
+    }
+    fn on_deal(&mut self, state: &State) {
+        for observer in self {
+            observer.on_deal(state);
+        }
+    }
+    fn on_game_over(&mut self, state: &State) {
+        for observer in self {
+            observer.on_game_over(state);
+        }
+    }
+}
+
+/// Prints the game to standard output as it's played.
+pub struct Console {
+    names: Vec<String>,
+    language: Language,
+}
+
+impl Console {
+    /// A console naming the players in seating order.
+    pub fn new(names: Vec<String>) -> Self {
+        Self {
+            names,
+            language: Language::English,
+        }
+    }
+
+    /// Prints in the language instead of English.
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+}
+
+impl Observer for Console {
+    fn on_move(&mut self, before: &State, description: &MoveDescription, _after: &State) {
+        println!(
+            "{}: {} {} ({})",
+            before.moves,
+            self.names[description.player],
+            self.language.describe(description),
+            description.to_move()
+        );
+    }
+    fn on_round_end(&mut self, _state: &State, scoring: &[Vec<ScoreEvent>]) {
+        let language = self.language;
+        for (name, events) in self.names.iter().zip(scoring) {
+            let total: i32 = events.iter().map(ScoreEvent::points).sum();
+            let total = format!("{:+}", total);
+            println!("{}", language.format(Text::Scores, &[name, &total]));
+            for event in events {
+                println!("  {}", language.explain(event));
+            }
+        }
+    }
+    fn on_deal(&mut self, state: &State) {
+        let factories = factories_to_notation(&state.factories);
+        println!("{}", self.language.format(Text::Dealt, &[&factories]));
+    }
+    fn on_game_over(&mut self, state: &State) {
+        let language = self.language;
+        for (name, player) in self.names.iter().zip(&state.players) {
+            println!("{}", language.format(Text::Points, &[name, &player.points]));
+        }
+        match state.result() {
+            GameResult::Ongoing => {}
+            GameResult::Won { player, .. } => {
+                println!("{}", language.format(Text::Wins, &[&self.names[player]]))
+            }
+            GameResult::Draw { players, .. } => {
+                let names: Vec<_> = players
+                    .iter()
+                    .map(|index| &self.names[*index][..])
+                    .collect();
+                let and = format!(" {} ", language.text(Text::And));
+                let names = names.join(&and);
+                println!("{}", language.format(Text::ShareVictory, &[&names]));
+            }
+        }
+    }
+}
+
+/// Writes the scores of every round as CSV, a row per player:
+///
+/// ```text
+/// game,round,player,points,penalty,bonus,tiles,total
+/// 1,1,1,6,2,0,4,4
+/// ```
+///
+/// `points` are the wall points gained, `penalty` the points lost to the
+/// floor line, `bonus` the end of game bonuses, `tiles` the tiles placed on
+/// the wall and `total` the points after the round. Games, rounds and
+/// players count from one. Writing stops at the first error, which
+/// `finish` returns.
+pub struct Csv<W: Write> {
+    out: W,
+    error: Option<io::Error>,
+    game: usize,
+    round: usize,
+}
+
+impl<W: Write> Csv<W> {
+    pub fn new(out: W) -> Self {
+        let mut csv = Self {
+            out,
+            error: None,
+            game: 0,
+            round: 0,
+        };
+        csv.write("game,round,player,points,penalty,bonus,tiles,total");
+        csv
+    }
+
+    /// The writer, or the error that stopped writing to it.
+    pub fn finish(self) -> io::Result<W> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.out),
+        }
+    }
+
+    fn write(&mut self, line: &str) {
+        if self.error.is_none() {
+            self.error = writeln!(self.out, "{}", line).err();
+        }
+    }
+}
+
+impl<W: Write> Observer for Csv<W> {
+    // a new game starts with its first move, also after one ended early
+    fn on_move(&mut self, before: &State, _description: &MoveDescription, _after: &State) {
+        if before.moves == 0 {
+            self.game += 1;
+            self.round = 0;
+        }
+    }
+    fn on_round_end(&mut self, state: &State, scoring: &[Vec<ScoreEvent>]) {
+        self.round += 1;
+        for (index, events) in scoring.iter().enumerate() {
+            let (mut points, mut penalty, mut bonus, mut tiles) = (0, 0, 0, 0);
+            for event in events {
+                match event {
+                    ScoreEvent::Placed { points: placed, .. } => {
+                        points += placed;
+                        tiles += 1;
+                    }
+                    ScoreEvent::Floor { penalty: lost } => penalty += lost,
+                    ScoreEvent::Discarded { .. } => {}
+                    _ => bonus += event.points(),
+                }
+            }
+            let line = format!(
+                "{},{},{},{},{},{},{},{}",
+                self.game,
+                self.round,
+                index + 1,
+                points,
+                penalty,
+                bonus,
+                tiles,
+                state.players[index].points
+            );
+            self.write(&line);
+        }
+        if self.error.is_none() {
+            self.error = self.out.flush().err();
+        }
+    }
+}
+
+/// Writes every event of the game as a line of JSON, e.g.
+///
+/// ```text
+/// {"event":"move","ply":0,"player":0,"move":"F1 B -> row2","position":"..."}
+/// {"event":"round_end","scoring":[[{"placed":{...}}],[]]}
+/// {"event":"deal","factories":"BBWY/AAYR/..."}
+/// {"event":"game_over","points":[52,47],"winner":0,"winners":[0]}
+/// ```
+///
+/// `position` is the position after the move, in notation. After a draw
+/// `winner` is null and `winners` lists the players sharing it. Writing
+/// stops at the first error, which `finish` returns.
+#[cfg(feature = "serde")]
+pub struct JsonLines<W: Write> {
+    out: W,
+    error: Option<io::Error>,
+}
+
+#[cfg(feature = "serde")]
+impl<W: Write> JsonLines<W> {
+    pub fn new(out: W) -> Self {
+        Self { out, error: None }
+    }
+
+    /// The writer, or the error that stopped writing to it.
+    pub fn finish(self) -> io::Result<W> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.out),
+        }
+    }
+
+    fn write(&mut self, line: serde_json::Value) {
+        if self.error.is_some() {
+            return;
+        }
+        let result = serde_json::to_writer(&mut self.out, &line)
+            .map_err(io::Error::from)
+            .and_then(|()| writeln!(self.out))
+            .and_then(|()| self.out.flush());
+        self.error = result.err();
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<W: Write> Observer for JsonLines<W> {
+    fn on_move(&mut self, before: &State, description: &MoveDescription, after: &State) {
+        self.write(json!({
+            "event": "move",
+            "ply": before.moves,
+            "player": description.player,
+            "move": description.to_move().to_string(),
+            "position": after.to_notation(),
+        }));
+    }
+    fn on_round_end(&mut self, _state: &State, scoring: &[Vec<ScoreEvent>]) {
+        self.write(json!({ "event": "round_end", "scoring": scoring }));
+    }
+    fn on_deal(&mut self, state: &State) {
+        self.write(json!({
+            "event": "deal",
+            "factories": factories_to_notation(&state.factories),
+        }));
+    }
+    fn on_game_over(&mut self, state: &State) {
+        let points: Vec<_> = state.players.iter().map(|player| player.points).collect();
+        self.write(json!({
+            "event": "game_over",
+            "points": points,
+            "winner": state.winner(),
+            "winners": state.winners(),
+        }));
+    }
+}
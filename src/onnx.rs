@@ -0,0 +1,77 @@
+//! Evaluation by a model in [ONNX](https://onnx.ai) format, e.g. a neural
+//! network trained on data from [`selfplay`](crate::selfplay), run with
+//! [tract](https://github.com/sonos/tract).
+//!
+//! The model takes the features of a position from `features::encode` as a
+//! `float32` tensor of shape `(1, FEATURES)` and gives the value of the
+//! position for the player to encode it for, in points, as its first
+//! output, e.g. trained to predict the final point difference in
+//! `results.npy`.
+
+use crate::{
+    azul::State,
+    features::{encode, FEATURES},
+    minmax::Evaluation,
+};
+use std::{fmt, path::Path};
+use tract_onnx::prelude::*;
+
+/// Error loading or running a model.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModelError(pub String);
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "bad model: {}", self.0)
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+impl From<TractError> for ModelError {
+    fn from(error: TractError) -> Self {
+        ModelError(format!("{:#}", error))
+    }
+}
+
+type Model = TypedSimplePlan<TypedModel>;
+
+/// Evaluation by an ONNX model, see the module documentation. Values are
+/// in hundredths of a point, like `BoardEvaluation`.
+pub struct OnnxEvaluation {
+    model: Model,
+}
+
+impl OnnxEvaluation {
+    /// Loads and optimizes the model, and checks that it evaluates a
+    /// position.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ModelError> {
+        let model = tract_onnx::onnx()
+            .model_for_path(path)?
+            .with_input_fact(0, f32::fact([1, FEATURES]).into())?
+            .into_optimized()?
+            .into_runnable()?;
+        let evaluation = Self { model };
+        evaluation.value(&State::new(2), 0)?;
+        Ok(evaluation)
+    }
+
+    // value of the position for the player, in points
+    fn value(&self, state: &State, player: usize) -> Result<f32, ModelError> {
+        let input = Tensor::from_shape(&[1, FEATURES], &encode(state, player))?;
+        let outputs = self.model.run(tvec!(input.into()))?;
+        let output = outputs
+            .first()
+            .ok_or_else(|| ModelError("no output".to_string()))?;
+        let value = output.as_slice::<f32>()?.first().copied();
+        value.ok_or_else(|| ModelError("empty output".to_string()))
+    }
+}
+
+impl Evaluation<State> for OnnxEvaluation {
+    fn evaulate(&self, state: &State, player: usize) -> i32 {
+        // the model evaluated a position when it was loaded
+        let value = self.value(state, player).expect("model failed");
+        (100.0 * value).round() as i32
+    }
+}
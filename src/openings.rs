@@ -0,0 +1,143 @@
+//! Opening book learned from self-play. For positions of the first round
+//! the book keeps how well each move did, so that the engine can play the
+//! most successful one instead of searching. Search is expensive and
+//! shallow in the opening, where hardly anything has been scored yet.
+//!
+//! Positions recur between games with the same deal, so the book learns
+//! from many games over a few deals.
+
+use crate::{
+    azul::{Move, State},
+    board_evaluation::BoardEvaluation,
+    minmax::{self, DeterministicGameState, Evaluation, StochasticGameState},
+};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+// games a move needs before the book plays it
+const MIN_GAMES: usize = 3;
+// share of first round moves made at random while learning, so that more
+// than one move is tried in each position
+const EXPLORATION: f64 = 0.25;
+
+/// How a move did in a position.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    pub games: usize,
+    /// Games won by the player making the move, with draws as half a win
+    pub score: f64,
+}
+
+/// Moves by position, both in notation.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Book {
+    pub positions: BTreeMap<String, BTreeMap<String, Stats>>,
+}
+
+// 1 if the player won, 0.5 if tied for the win and 0 otherwise
+fn result(state: &State, player: usize) -> f64 {
+    let points = state.players[player].points;
+    let best = state.players.iter().map(|p| p.points).max().unwrap_or(0);
+    let tied = state.players.iter().filter(|p| p.points == best).count();
+    match points == best {
+        true => 1.0 / tied as f64,
+        false => 0.0,
+    }
+}
+
+impl Book {
+    /// Adds the result of a game to the moves made in it, given as the
+    /// positions and moves of the first round and the final position.
+    pub fn add(&mut self, opening: &[(State, Move)], end: &State) {
+        for (state, m) in opening {
+            let stats = self
+                .positions
+                .entry(state.to_notation())
+                .or_default()
+                .entry(m.to_string())
+                .or_default();
+            stats.games += 1;
+            stats.score += result(end, state.current_player());
+        }
+    }
+
+    /// The move with the best average score in the position, if any move
+    /// has been played often enough.
+    pub fn probe(&self, state: &State) -> Option<Move> {
+        let moves = self.positions.get(&state.to_notation())?;
+        let (m, _) = moves
+            .iter()
+            .filter(|(_, stats)| stats.games >= MIN_GAMES)
+            .max_by(|(_, a), (_, b)| {
+                let a = a.score / a.games as f64;
+                let b = b.score / b.games as f64;
+                a.total_cmp(&b)
+            })?;
+        Move::parse(m).ok().filter(|m| state.apply(m).is_ok())
+    }
+
+    /// Plays `games` games of minmax to `depth` against itself, cycling
+    /// through `deals` random deals, and adds their first rounds to the
+    /// book. `progress` is called after each game.
+    pub fn learn<R: Rng>(
+        &mut self,
+        games: usize,
+        deals: usize,
+        depth: usize,
+        rng: &mut R,
+        mut progress: impl FnMut(usize),
+    ) {
+        let seeds: Vec<u64> = (0..deals.max(1)).map(|_| rng.gen()).collect();
+        let mut evaluation = BoardEvaluation::default();
+        for game in 0..games {
+            let mut state = State::new(2);
+            state.deal(&mut StdRng::seed_from_u64(seeds[game % seeds.len()]));
+            let mut opening = Vec::new();
+            let mut first_round = true;
+            while !state.result().is_over() {
+                let m = if first_round && rng.gen_bool(EXPLORATION) {
+                    *state.legal_moves().choose(rng).unwrap()
+                } else {
+                    minmax::search(&state, &mut evaluation, rng, depth).unwrap()
+                };
+                let next = state.play(&m);
+                if first_round {
+                    first_round = !next.needs_deal();
+                    opening.push((state, m));
+                }
+                state = next.resolve(rng);
+            }
+            self.add(&opening, &state);
+            progress(game);
+        }
+    }
+
+    /// Reads a book written by `save`, or an empty book if the file doesn't
+    /// exist yet.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Writes the book as JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+    }
+}
+
+/// Like `minmax::search`, but plays the book move when there is one.
+pub fn search<E: Evaluation<State>, R: Rng>(
+    book: &Book,
+    state: &State,
+    evaluation: &mut E,
+    rng: &mut R,
+    depth: usize,
+) -> Option<Move> {
+    book.probe(state)
+        .or_else(|| minmax::search(state, evaluation, rng, depth))
+}
@@ -0,0 +1,67 @@
+//! Perft: the number of move sequences of each length from a position.
+//! Comparing the counts before and after a change to the rules or the move
+//! generation shows whether it changed which moves are legal.
+
+use crate::minmax::StochasticGameState;
+use rand::Rng;
+
+/// How chance events, e.g. deals, are resolved while counting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Chance {
+    /// Follow one random outcome
+    Sample,
+    /// Follow every outcome when there are at most this many of them, and
+    /// one random outcome otherwise
+    Enumerate(usize),
+}
+
+/// Number of move sequences of each length from 1 to `depth`. Chance
+/// events don't count as moves.
+pub fn perft<S: StochasticGameState, R: Rng>(
+    state: &S,
+    depth: usize,
+    chance: Chance,
+    rng: &mut R,
+) -> Vec<u64> {
+    let mut counts = vec![0; depth];
+    count(&mut state.clone(), &mut counts, chance, rng);
+    counts
+}
+
+// adds the sequences from the state to counts, where counts[0] is for the
+// next move
+fn count<S: StochasticGameState, R: Rng>(
+    state: &mut S,
+    counts: &mut [u64],
+    chance: Chance,
+    rng: &mut R,
+) {
+    let Some((first, rest)) = counts.split_first_mut() else {
+        return;
+    };
+    if state.is_chance() {
+        let outcomes = match chance {
+            Chance::Enumerate(limit) => state.outcomes(limit),
+            Chance::Sample => None,
+        };
+        match outcomes {
+            Some(outcomes) => {
+                for (_, mut outcome) in outcomes {
+                    count(&mut outcome, counts, chance, rng);
+                }
+            }
+            None => count(&mut state.sample(rng), counts, chance, rng),
+        }
+        return;
+    }
+    let moves = state.legal_moves();
+    *first += moves.len() as u64;
+    if rest.is_empty() {
+        return;
+    }
+    for m in &moves {
+        let undo = state.apply_in_place(m);
+        count(state, rest, chance, rng);
+        state.undo(undo);
+    }
+}
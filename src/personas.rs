@@ -0,0 +1,218 @@
+//! Players of a game declared in a TOML file, e.g. `players.toml`, with
+//! the engine, depth or time, evaluation weights and name of every seat,
+//! so that experiments and demos don't need long command lines:
+//!
+//! ```toml
+//! [[player]]
+//! name = "Ada"
+//! engine = "minmax"
+//! depth = 3
+//!
+//! [player.weights]
+//! floor = 1.5
+//!
+//! [[player]]
+//! name = "Bo"
+//! engine = "fish"
+//! movetime = 500
+//!
+//! [[player]]
+//! engine = "human"
+//! ```
+//!
+//! `engine` is `human` or an engine of the registry, e.g. `random`,
+//! `minmax:4`, `mcts:500` or `level:3`. `depth` and `movetime`, in
+//! milliseconds, apply to `minmax` and `fish`, and `weights` to the
+//! evaluation of `minmax`, see `Weights`. Players are seated in the order
+//! of the file.
+
+use crate::{
+    azul::{Fish, Move, State},
+    board_evaluation::{BoardEvaluation, Weights},
+    engine::{Engine, Registered, Spec},
+    minmax::{search_with_options, Evaluation, SearchOptions},
+};
+use rand::Rng;
+use serde::Deserialize;
+use std::{fmt, fs, io, path::Path, time::Duration};
+
+/// A seat as the file declares it.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Persona {
+    /// Name to show, by default the engine
+    pub name: Option<String>,
+    /// `human`, or an engine of the registry
+    pub engine: String,
+    /// Search depth of `minmax` and `fish`, instead of the one in `engine`
+    pub depth: Option<usize>,
+    /// Milliseconds to think about a move, for `minmax` and `fish`,
+    /// instead of a fixed depth
+    pub movetime: Option<u64>,
+    /// Weights of the evaluation of `minmax`, the default ones for any
+    /// left out
+    pub weights: Option<Weights>,
+}
+
+// the file, a table for every player
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct File {
+    player: Vec<Persona>,
+}
+
+/// The players declared in the text of a file, checking that every seat
+/// makes sense.
+pub fn parse(text: &str) -> Result<Vec<Persona>, String> {
+    let file: File = toml::from_str(text).map_err(|error| error.message().to_string())?;
+    for (index, persona) in file.player.iter().enumerate() {
+        persona
+            .engine()
+            .map_err(|error| format!("player {}: {}", index + 1, error))?;
+    }
+    Ok(file.player)
+}
+
+/// Reads the players declared in a file.
+pub fn load(path: impl AsRef<Path>) -> io::Result<Vec<Persona>> {
+    let text = fs::read_to_string(path)?;
+    parse(&text).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+impl Persona {
+    /// The name to show.
+    pub fn name(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => self.engine.clone(),
+        }
+    }
+
+    /// The engine of the seat, or None if a human sits there.
+    pub fn engine(&self) -> Result<Option<Configured>, String> {
+        if self.engine == "human" {
+            if self.depth.is_some() || self.movetime.is_some() || self.weights.is_some() {
+                return Err("a human takes no depth, movetime or weights".to_string());
+            }
+            return Ok(None);
+        }
+        let (fish, depth, reductions) = match self.engine.parse()? {
+            Spec::Minmax { depth, reductions } => (false, depth, reductions),
+            Spec::Fish { depth, reductions } => (true, depth, reductions),
+            spec if self.depth.is_none() && self.movetime.is_none() && self.weights.is_none() => {
+                return Ok(Some(Configured::Spec(spec)));
+            }
+            _ => {
+                return Err(format!(
+                    "{} takes no depth, movetime or weights",
+                    self.engine
+                ))
+            }
+        };
+        if fish && self.weights.is_some() {
+            return Err("fish takes no weights".to_string());
+        }
+        let depth = self.depth.unwrap_or(depth);
+        if self.movetime.is_none() && self.weights.is_none() {
+            return Ok(Some(Configured::Spec(match fish {
+                true => Spec::Fish { depth, reductions },
+                false => Spec::Minmax { depth, reductions },
+            })));
+        }
+        let options = match self.movetime {
+            Some(movetime) => SearchOptions::timed(Duration::from_millis(movetime)),
+            None => SearchOptions::depth(depth),
+        };
+        Ok(Some(Configured::Search {
+            fish,
+            weights: self.weights.clone(),
+            options: SearchOptions {
+                late_move_reductions: reductions,
+                ..options
+            },
+        }))
+    }
+}
+
+/// An engine as a persona configures it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Configured {
+    /// An engine of the registry as it is
+    Spec(Spec),
+    /// Minmax with weights or a time of its own, using `Fish` or else
+    /// `BoardEvaluation`
+    Search {
+        fish: bool,
+        weights: Option<Weights>,
+        options: SearchOptions,
+    },
+}
+
+impl fmt::Display for Configured {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Configured::Spec(spec) => spec.fmt(f),
+            Configured::Search {
+                fish,
+                weights,
+                options,
+            } => {
+                f.write_str(if *fish { "fish" } else { "minmax" })?;
+                match options.time_budget {
+                    Some(budget) => write!(f, " {} ms", budget.as_millis())?,
+                    None => write!(f, ":{}", options.depth)?,
+                }
+                if weights.is_some() {
+                    f.write_str(" with weights")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Configured {
+    /// The engine, built once for the moves of a game so that it keeps
+    /// what it learns from move to move, e.g. the evaluations `Fish` has
+    /// cached.
+    pub fn build(&self) -> ConfiguredEngine {
+        match self {
+            Configured::Spec(spec) => ConfiguredEngine::Registered(spec.build()),
+            Configured::Search {
+                fish,
+                weights,
+                options,
+            } => ConfiguredEngine::Search {
+                evaluation: match (fish, weights) {
+                    (true, _) => Box::new(Fish::new()),
+                    (false, Some(weights)) => Box::new(BoardEvaluation::new(weights.clone())),
+                    (false, None) => Box::new(BoardEvaluation::default()),
+                },
+                options: options.clone(),
+            },
+        }
+    }
+}
+
+/// An engine as a persona configures it, built by `Configured::build`.
+pub enum ConfiguredEngine {
+    Registered(Registered),
+    Search {
+        evaluation: Box<dyn Evaluation<State>>,
+        options: SearchOptions,
+    },
+}
+
+impl Engine for ConfiguredEngine {
+    fn choose<R: Rng>(&mut self, state: &State, rng: &mut R) -> Move {
+        match self {
+            ConfiguredEngine::Registered(engine) => engine.choose(state, rng),
+            ConfiguredEngine::Search {
+                evaluation,
+                options,
+            } => search_with_options(state, evaluation, rng, options)
+                .0
+                .unwrap(),
+        }
+    }
+}
@@ -0,0 +1,345 @@
+//! Games against the engine at a prompt in the terminal: the boards are
+//! printed before every move and the human types their moves, by number
+//! or in move notation.
+//!
+//! Instead of a move the human can enter `hint` for the move a short
+//! search suggests, `show <color>` to see which pattern rows can take the
+//! color, `undo` and `redo` to take back and replay their last move and the
+//! replies to it, or `save <file>` to continue the game later with
+//! `SavedGame`.
+
+use crate::{
+    azul::{describe_move, describe_scoring, Move, ScoreEvent, State, Tile},
+    azul_fmt::{render_changes, render_state_with_clocks, RenderOptions},
+    board_evaluation::BoardEvaluation,
+    clock::Clock,
+    engine::{Dealer, Opponent},
+    history::History,
+    i18n::{Language, Text},
+    minmax::{search_with_options, DeterministicGameState, Evaluation, SearchOptions},
+    openings::Book,
+    record::SavedGame,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    io::{self, BufRead, Write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// What the human wants to do.
+pub enum Input {
+    Move(Move),
+    Undo,
+    Redo,
+    Save(PathBuf),
+}
+
+/// Help the human can ask for without moving.
+pub enum Help {
+    Hint,
+    /// The rows that can take tiles of the color
+    Show(Tile),
+}
+
+// a color by letter, or by name in English or the language
+fn parse_tile(text: &str, language: Language) -> Option<Tile> {
+    let mut tiles = (0..5).map(|index| Tile::try_from(index).unwrap());
+    tiles.find(|tile| {
+        let letter = tile.letter().to_string();
+        text.eq_ignore_ascii_case(&tile.to_string())
+            || text.eq_ignore_ascii_case(language.color(*tile))
+            || text.eq_ignore_ascii_case(&letter)
+    })
+}
+
+/// Lists the legal moves and asks the human for one until a valid one is
+/// given, calling `help` when asked for it. None at the end of the input.
+pub fn read_move(
+    state: &State,
+    language: Language,
+    input: &mut impl BufRead,
+    mut help: impl FnMut(Help),
+) -> Option<Input> {
+    let moves = state.legal_moves();
+    for (index, m) in moves.iter().enumerate() {
+        println!(
+            "{:3}: {:14} {}",
+            index + 1,
+            m.to_string(),
+            language.describe(&state.describe(m))
+        );
+    }
+    loop {
+        print!("{}", language.text(Text::YourMove));
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if input.read_line(&mut line).unwrap() == 0 {
+            return None;
+        }
+        match line.trim() {
+            "u" | "undo" => return Some(Input::Undo),
+            "r" | "redo" => return Some(Input::Redo),
+            "h" | "hint" => help(Help::Hint),
+            line if line.starts_with("show") => {
+                match parse_tile(line["show".len()..].trim(), language) {
+                    Some(tile) => help(Help::Show(tile)),
+                    None => println!("{}", language.text(Text::ShowWhichColor)),
+                }
+            }
+            "save" => println!("{}", language.text(Text::SaveWhere)),
+            line if line.starts_with("save ") => {
+                return Some(Input::Save(PathBuf::from(line["save ".len()..].trim())))
+            }
+            line => match line.parse::<usize>() {
+                Ok(number) if (1..=moves.len()).contains(&number) => {
+                    return Some(Input::Move(moves[number - 1]))
+                }
+                Ok(_) => println!("{}", language.format(Text::NumberBetween, &[&moves.len()])),
+                Err(_) => match Move::parse(line) {
+                    Ok(m) if moves.contains(&m) => return Some(Input::Move(m)),
+                    Ok(m) => println!("{}", language.format(Text::NotLegal, &[&m])),
+                    Err(error) => println!("{}", error),
+                },
+            },
+        }
+    }
+}
+
+/// Suggests a move to the human, from a short search.
+pub fn print_hint<R: Rng>(state: &State, language: Language, rng: &mut R) {
+    const HINT_DEPTH: usize = 2;
+    let mut evaluation = BoardEvaluation::default();
+    let options = SearchOptions::depth(HINT_DEPTH);
+    let (_, info) = search_with_options(state, &mut evaluation, rng, &options);
+    if let Some(m) = info.pv.first() {
+        let description = language.describe(&state.describe(m));
+        let score = format!("{:+.2}", info.score as f64 / 100.0);
+        println!(
+            "{}",
+            language.format(Text::Hint, &[&description, m, &score])
+        );
+    }
+}
+
+/// Moves in notation, separated by commas.
+pub fn format_pv(pv: &[Move]) -> String {
+    pv.iter()
+        .map(Move::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// chance of at least one to five tiles of each color in the next deal, the
+// most a pattern row needs
+fn print_draw_probabilities(state: &State) {
+    println!("next deal, chance of at least 1 to 5 tiles");
+    for (index, distribution) in state.draw_probabilities().iter().enumerate() {
+        let tile = Tile::try_from(index).unwrap();
+        let chances: Vec<_> = (1..=5)
+            .map(|wanted| {
+                let chance: f64 = distribution.iter().skip(wanted).sum();
+                format!("{:3.0}%", 100.0 * chance.min(1.0))
+            })
+            .collect();
+        println!("  {} {}", tile.letter(), chances.join(" "));
+    }
+}
+
+/// Prints how the points of every player changed at the end of a round.
+pub fn print_scoring(events: &[Vec<ScoreEvent>], names: &[&str], language: Language) {
+    for (name, events) in names.iter().zip(events) {
+        let total: i32 = events.iter().map(ScoreEvent::points).sum();
+        let total = format!("{:+}", total);
+        println!("{}", language.format(Text::Scores, &[name, &total]));
+        for event in events {
+            println!("  {}", language.explain(event));
+        }
+    }
+}
+
+/// A game of a human against the engine, which plays every other seat.
+pub struct Game<E> {
+    /// The positions so far, played on from the last one
+    pub history: History<State>,
+    /// Seat of the human
+    pub human: usize,
+    /// Names of the players, in seating order
+    pub names: Vec<String>,
+    pub clock: Option<Clock>,
+    pub opponent: Opponent<E>,
+    /// Moves the engine plays without searching
+    pub book: Book,
+    pub render: RenderOptions,
+    /// Mark what the last move changed on the boards
+    pub changes: bool,
+    /// Print the statistics of every search and the chances of the next
+    /// deal
+    pub verbose: bool,
+    pub language: Language,
+}
+
+impl<E: Evaluation<State> + Send + 'static> Game<E> {
+    /// Plays until the game is over, a clock runs out or `input` ends,
+    /// dealing with a generator seeded from `rng`. `on_move` is called with
+    /// the position before and after every move, dealt, the score the
+    /// engine found for it, if it searched, and the time the move took.
+    pub fn play(
+        &mut self,
+        mut rng: StdRng,
+        input: &mut impl BufRead,
+        mut on_move: impl FnMut(&State, &State, Option<i32>, Duration),
+    ) {
+        let (human, language, render) = (self.human, self.language, self.render);
+        let names: Vec<_> = self.names.iter().map(String::as_str).collect();
+        let mut dealer = Dealer::new(rng.gen());
+        if self.history.current().needs_deal() {
+            let mut state = self.history.current().clone();
+            dealer.deal(&mut state);
+            self.history = History::new(state);
+        }
+        let mut hints = 0;
+        // the position before the last move, unless moves were taken back
+        let mut previous: Option<State> = None;
+        while !self.history.current().result().is_over() {
+            let state = self.history.current().clone();
+            let clocks = self.clock.as_ref().map_or(&[][..], Clock::remaining);
+            let board = match previous.as_ref().filter(|_| self.changes) {
+                Some(previous) => render_changes(previous, &state, &names, clocks, &render),
+                None => render_state_with_clocks(&state, &names, clocks, &render),
+            };
+            println!("\n{}", board);
+            println!("position {}", state.to_notation());
+            if self.verbose {
+                print_draw_probabilities(&state);
+            }
+            let start = Instant::now();
+            let next = if state.current_player() == human {
+                let help = |help| match help {
+                    Help::Hint => {
+                        hints += 1;
+                        print_hint(&state, language, &mut rng);
+                    }
+                    Help::Show(tile) => {
+                        let render = RenderOptions {
+                            highlight: Some(tile),
+                            ..render
+                        };
+                        let board = render_state_with_clocks(&state, &names, clocks, &render);
+                        println!("\n{}", board);
+                    }
+                };
+                match read_move(&state, language, input, help) {
+                    None => return,
+                    // back to before the last move of the human
+                    Some(Input::Undo) => {
+                        self.history.undo_to(human);
+                        previous = None;
+                        continue;
+                    }
+                    Some(Input::Redo) => {
+                        self.history.redo_to(human);
+                        previous = None;
+                        continue;
+                    }
+                    // the generators can't be saved, so both the game saved
+                    // and this one continue from a new seed
+                    Some(Input::Save(path)) => {
+                        let seed = rng.gen();
+                        rng = StdRng::seed_from_u64(seed);
+                        dealer = Dealer::new(rng.gen());
+                        let saved = SavedGame::new(&self.history, human, seed, self.clock.as_ref());
+                        match saved.save(&path) {
+                            Ok(()) => {
+                                let path = path.display();
+                                println!("{}", language.format(Text::Saved, &[&path]))
+                            }
+                            Err(error) => {
+                                eprintln!("could not save {}: {}", path.display(), error)
+                            }
+                        }
+                        continue;
+                    }
+                    Some(Input::Move(m)) => {
+                        let mut next = state.apply(&m).unwrap();
+                        dealer.deal(&mut next);
+                        on_move(&state, &next, None, start.elapsed());
+                        next
+                    }
+                }
+            } else {
+                let (m, info) = match self.book.probe(&state) {
+                    Some(m) => (m, None),
+                    None => self.opponent.choose(&state, self.clock.as_ref(), &mut rng),
+                };
+                let mut next = state.apply(&m).unwrap();
+                dealer.deal(&mut next);
+                let score = info.as_ref().map(|info| info.score);
+                on_move(&state, &next, score, start.elapsed());
+                if let Some(description) = describe_move(&state, &next) {
+                    println!(
+                        "{} {} ({})",
+                        names[state.current_player()],
+                        language.describe(&description),
+                        description.to_move()
+                    );
+                }
+                if let Some(info) = info {
+                    let pv = format_pv(&info.pv);
+                    println!("{}", language.format(Text::Expecting, &[&pv]));
+                    if self.verbose {
+                        println!(
+                            "depth {} ({} with extensions), score {}, {} nodes, {} table hits, {} cutoffs, {} re-searches, {} pruned, {} evaluations ({} cached) in {:.2?}",
+                            info.depth,
+                            info.max_depth,
+                            info.score,
+                            info.nodes,
+                            info.tt_hits,
+                            info.cutoffs,
+                            info.researches,
+                            info.pruned,
+                            info.evaluations,
+                            info.cache_hits,
+                            info.elapsed
+                        );
+                    }
+                }
+                next
+            };
+            if let Some(clock) = &mut self.clock {
+                if !clock.punch(state.current_player(), start.elapsed()) {
+                    let name = names[state.current_player()];
+                    println!("{}", language.format(Text::OutOfTime, &[&name]));
+                    break;
+                }
+            }
+            if let Some((_, events)) = describe_scoring(&state, &next) {
+                print_scoring(&events, &names, language);
+            }
+            self.history.push(next);
+            previous = Some(state);
+        }
+        let state = self.history.current();
+        let clocks = self.clock.as_ref().map_or(&[][..], Clock::remaining);
+        println!(
+            "\n{}",
+            render_state_with_clocks(state, &names, clocks, &render)
+        );
+        for (index, player) in state.players.iter().enumerate() {
+            let points = language.format(Text::Points, &[&names[index], &player.points]);
+            println!("{}", points);
+        }
+        match hints {
+            0 => {}
+            1 => println!("{}", language.text(Text::OneHintTaken)),
+            hints => println!("{}", language.format(Text::HintsTaken, &[&hints])),
+        }
+        if let Some(winner) = self
+            .clock
+            .as_ref()
+            .and_then(|clock| clock.flagged().and(clock.winner(state)))
+        {
+            println!("{}", language.format(Text::WinsOnTime, &[&names[winner]]));
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! Pondering: thinking on the opponent's time.
+//!
+//! After the engine moves, it expects a reply, the second move of its
+//! principal variation. While the opponent thinks, [`Ponderer`] searches the
+//! position after that reply in a background thread. The transposition
+//! table it fills is handed to the engine's next search, which is then
+//! mostly done if the opponent replied as expected, and still finds the
+//! positions the two have in common if not.
+
+use crate::minmax::{
+    search_with_table, DeterministicGameState, Evaluation, SearchInfo, SearchOptions,
+    StochasticGameState, StopToken, TranspositionTable,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::thread::{self, JoinHandle};
+
+/// The position to ponder on after the principal variation `pv` of a search
+/// of `state`: the one after the best move and the expected reply, if the
+/// searching player is to move then, rather than a chance event or another
+/// player.
+pub fn expected_position<S: StochasticGameState>(state: &S, pv: &[S::Move]) -> Option<S> {
+    let [m, reply, ..] = pv else {
+        return None;
+    };
+    let after = state.play(m);
+    if after.is_chance() || after.result().is_over() {
+        return None;
+    }
+    let expected = after.play(reply);
+    let ours = !expected.is_chance() && expected.current_player() == state.current_player();
+    ours.then_some(expected)
+}
+
+// the search of the expected position in the background
+struct Background<E, M> {
+    // player to move in the expected position
+    player: usize,
+    stop: StopToken,
+    thread: JoinHandle<(E, TranspositionTable<M>)>,
+}
+
+/// A searching engine that can ponder between its searches.
+pub struct Ponderer<S: DeterministicGameState, E> {
+    // the evaluation and table, unless lent to the background search
+    idle: Option<(E, TranspositionTable<S::Move>)>,
+    background: Option<Background<E, S::Move>>,
+    // player the table was filled for by pondering, if it was
+    pondered: Option<usize>,
+}
+
+impl<S, E> Ponderer<S, E>
+where
+    S: StochasticGameState + Send + 'static,
+    S::Move: Send,
+    E: Evaluation<S> + Send + 'static,
+{
+    pub fn new(evaluation: E) -> Self {
+        Self {
+            idle: Some((evaluation, TranspositionTable::default())),
+            background: None,
+            pondered: None,
+        }
+    }
+
+    /// Stops pondering, e.g. because the opponent moved, keeping what it
+    /// found for the next search.
+    pub fn stop(&mut self) {
+        if let Some(background) = self.background.take() {
+            background.stop.stop();
+            let idle = background.thread.join().expect("pondering panicked");
+            self.idle = Some(idle);
+            self.pondered = Some(background.player);
+        }
+    }
+
+    /// Searches `state`, with the table filled by pondering since the
+    /// previous search if it was for the same player, or else from scratch.
+    pub fn search<R: Rng>(
+        &mut self,
+        state: &S,
+        rng: &mut R,
+        options: &SearchOptions,
+    ) -> (Option<S::Move>, SearchInfo<S::Move>) {
+        self.stop();
+        let (evaluation, table) = self.idle.as_mut().expect("evaluation not returned");
+        if self.pondered.take() != Some(state.current_player()) {
+            *table = TranspositionTable::default();
+        }
+        search_with_table(state, evaluation, rng, options, table)
+    }
+
+    /// Starts searching `expected` in the background, one depth at a time
+    /// up to the depth of `options`, until the next search or `stop`. The
+    /// first depth always completes.
+    pub fn ponder(&mut self, expected: S, options: &SearchOptions, seed: u64) {
+        self.stop();
+        let (mut evaluation, mut table) = self.idle.take().expect("evaluation not returned");
+        let player = expected.current_player();
+        let stop = StopToken::new();
+        let token = stop.clone();
+        let options = options.clone();
+        let thread = thread::spawn(move || {
+            let mut rng = StdRng::seed_from_u64(seed);
+            for depth in 1..=options.depth {
+                let options = SearchOptions {
+                    depth,
+                    time_budget: None,
+                    stop: (depth > 1).then(|| token.clone()),
+                    ..options.clone()
+                };
+                search_with_table(&expected, &mut evaluation, &mut rng, &options, &mut table);
+                if token.is_stopped() {
+                    break;
+                }
+            }
+            (evaluation, table)
+        });
+        self.background = Some(Background {
+            player,
+            stop,
+            thread,
+        });
+    }
+}
+
+impl<S: DeterministicGameState, E> Drop for Ponderer<S, E> {
+    // don't leave the thread searching
+    fn drop(&mut self) {
+        if let Some(background) = &self.background {
+            background.stop.stop();
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! AzulUCI, a line based text protocol on stdin and stdout for driving the
+//! engine from another program, modelled on the UCI protocol of chess
+//! engines.
+//!
+//! Commands from the GUI, one per line:
+//!
+//! * `azuluci` - the engine identifies itself with `id name ...` and
+//!   `id author ...` lines followed by `azuluciok`
+//! * `isready` - answered with `readyok`
+//! * `newgame [players]` - start and deal a new game, two players by default
+//! * `position <notation>` - set up a position in notation, see
+//!   [`notation`](crate::notation)
+//! * `move <move>` - make a move in move notation, e.g. `move F1 B -> row2`.
+//!   A new round is dealt by the engine if needed
+//! * `go depth <n>` or `go movetime <milliseconds>` - search the position,
+//!   answered with `info depth <n> seldepth <n> score <score> nodes <n>
+//!   time <milliseconds> pv <moves>`, where `seldepth` counts the moves
+//!   searched past the depth until the end of the round and `pv` is the
+//!   expected moves separated by commas, and `bestmove <move>` (or just `bestmove none`
+//!   when the game is over)
+//! * `show` - answered with `position <notation>` of the current position
+//! * `quit`
+//!
+//! Problems are reported as `info string <message>`.
+
+use crate::{
+    azul::{Move, State},
+    board_evaluation::BoardEvaluation,
+    minmax::{search_timed_with_info, search_with_info},
+};
+use rand::Rng;
+use std::{
+    io::{self, BufRead, Write},
+    time::Duration,
+};
+
+// search depth of go without limits
+const DEFAULT_DEPTH: usize = 4;
+
+/// An engine speaking AzulUCI.
+pub struct Protocol<R> {
+    state: State,
+    evaluation: BoardEvaluation,
+    rng: R,
+}
+
+fn info(message: impl std::fmt::Display) -> Vec<String> {
+    vec![format!("info string {}", message)]
+}
+
+impl<R: Rng> Protocol<R> {
+    /// An engine with a dealt two player game as the current position.
+    pub fn new(mut rng: R) -> Self {
+        let mut state = State::new(2);
+        state.deal(&mut rng);
+        Self {
+            state,
+            evaluation: BoardEvaluation::default(),
+            rng,
+        }
+    }
+
+    /// The reply to a command line, or None on `quit`.
+    pub fn handle(&mut self, line: &str) -> Option<Vec<String>> {
+        let line = line.trim();
+        let (command, arguments) = line.split_once(' ').unwrap_or((line, ""));
+        let arguments = arguments.trim();
+        let reply = match command {
+            "" => Vec::new(),
+            "azuluci" => vec![
+                "id name azul".to_string(),
+                "id author Samuel Carlsson".to_string(),
+                "azuluciok".to_string(),
+            ],
+            "isready" => vec!["readyok".to_string()],
+            "newgame" => self.new_game(arguments),
+            "position" => match State::from_notation(arguments) {
+                Ok(state) => {
+                    self.state = state;
+                    Vec::new()
+                }
+                Err(error) => info(error),
+            },
+            "move" => self.make_move(arguments),
+            "go" => self.go(arguments),
+            "show" => vec![format!("position {}", self.state.to_notation())],
+            "quit" => return None,
+            _ => info(format!("unknown command '{}'", command)),
+        };
+        Some(reply)
+    }
+
+    fn new_game(&mut self, arguments: &str) -> Vec<String> {
+        let players = match arguments {
+            "" => 2,
+            players => match players.parse() {
+                Ok(players) if (2..=4).contains(&players) => players,
+                _ => return info(format!("bad number of players '{}'", players)),
+            },
+        };
+        self.state = State::new(players);
+        self.state.deal(&mut self.rng);
+        Vec::new()
+    }
+
+    fn make_move(&mut self, arguments: &str) -> Vec<String> {
+        let m = match Move::parse(arguments) {
+            Ok(m) => m,
+            Err(error) => return info(error),
+        };
+        match self.state.apply(&m) {
+            Ok(mut state) => {
+                if state.needs_deal() {
+                    state.deal(&mut self.rng);
+                }
+                self.state = state;
+                Vec::new()
+            }
+            Err(error) => info(error),
+        }
+    }
+
+    fn go(&mut self, arguments: &str) -> Vec<String> {
+        if self.state.needs_deal() {
+            self.state.deal(&mut self.rng);
+        }
+        let words: Vec<_> = arguments.split_whitespace().collect();
+        let (best, search_info) = match words[..] {
+            [] => search_with_info(
+                &self.state,
+                &mut self.evaluation,
+                &mut self.rng,
+                DEFAULT_DEPTH,
+            ),
+            ["depth", depth] => match depth.parse() {
+                Ok(depth) => {
+                    search_with_info(&self.state, &mut self.evaluation, &mut self.rng, depth)
+                }
+                Err(_) => return info(format!("bad depth '{}'", depth)),
+            },
+            ["movetime", milliseconds] => match milliseconds.parse() {
+                Ok(milliseconds) => search_timed_with_info(
+                    &self.state,
+                    &mut self.evaluation,
+                    Duration::from_millis(milliseconds),
+                    &mut self.rng,
+                ),
+                Err(_) => return info(format!("bad movetime '{}'", milliseconds)),
+            },
+            _ => return info(format!("bad go arguments '{}'", arguments)),
+        };
+        let Some(best) = best else {
+            return vec!["bestmove none".to_string()];
+        };
+        let pv: Vec<_> = search_info.pv.iter().map(|m| m.to_string()).collect();
+        vec![
+            format!(
+                "info depth {} seldepth {} score {} nodes {} time {} pv {}",
+                search_info.depth,
+                search_info.max_depth,
+                search_info.score,
+                search_info.nodes,
+                search_info.elapsed.as_millis(),
+                pv.join(", ")
+            ),
+            format!("bestmove {}", best),
+        ]
+    }
+}
+
+/// Speaks AzulUCI on the input and output until `quit` or end of input.
+pub fn run<R: Rng>(rng: R, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut protocol = Protocol::new(rng);
+    for line in input.lines() {
+        let Some(reply) = protocol.handle(&line?) else {
+            break;
+        };
+        for line in reply {
+            writeln!(output, "{}", line)?;
+        }
+        output.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,122 @@
+//! Elo ratings of engine configurations, kept in a file across matches so
+//! the strength of engines can be followed over time.
+
+use crate::tournament::Summary;
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, collections::BTreeMap, fmt, fs, io, path::Path};
+
+/// Rating of engines without any games.
+pub const INITIAL_ELO: f64 = 1500.0;
+// how much a single game moves the ratings
+const K: f64 = 16.0;
+
+/// Rating and results of one engine configuration.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rating {
+    pub elo: f64,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Self {
+            elo: INITIAL_ELO,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+        }
+    }
+}
+
+impl Rating {
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+}
+
+/// Ratings by engine configuration, e.g. `minmax:4`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Ratings {
+    pub engines: BTreeMap<String, Rating>,
+}
+
+// expected score of a player rated a against one rated b
+fn expected(a: f64, b: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((b - a) / 400.0))
+}
+
+impl Ratings {
+    /// Updates the ratings after a game between `a` and `b`, where `score`
+    /// is 1 if `a` won, 0.5 for a draw and 0 if `b` won.
+    pub fn update(&mut self, a: &str, b: &str, score: f64) {
+        let rating_a = self.get(a).elo;
+        let rating_b = self.get(b).elo;
+        let change = K * (score - expected(rating_a, rating_b));
+        for (name, change, score) in [(a, change, score), (b, -change, 1.0 - score)] {
+            let rating = self.engines.entry(name.to_string()).or_default();
+            rating.elo += change;
+            match score {
+                score if score > 0.5 => rating.wins += 1,
+                score if score < 0.5 => rating.losses += 1,
+                _ => rating.draws += 1,
+            }
+        }
+    }
+
+    /// Updates the ratings after every game of a match between `a` and `b`.
+    pub fn record(&mut self, a: &str, b: &str, summary: &Summary) {
+        for result in summary.results() {
+            let score = match result {
+                Ordering::Greater => 1.0,
+                Ordering::Equal => 0.5,
+                Ordering::Less => 0.0,
+            };
+            self.update(a, b, score);
+        }
+    }
+
+    /// The rating of an engine, initial if it hasn't played.
+    pub fn get(&self, name: &str) -> Rating {
+        self.engines.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Engines sorted by rating, best first.
+    pub fn ranking(&self) -> Vec<(&str, &Rating)> {
+        let mut ranking: Vec<_> = self
+            .engines
+            .iter()
+            .map(|(name, rating)| (name.as_str(), rating))
+            .collect();
+        ranking.sort_by(|(_, a), (_, b)| b.elo.total_cmp(&a.elo));
+        ranking
+    }
+
+    /// Reads ratings written by `save`, or no ratings if the file doesn't
+    /// exist yet.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Writes the ratings as JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+    }
+}
+
+// the ranking as a table
+impl fmt::Display for Ratings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:>6} {:>6}  engine", "elo", "games")?;
+        for (name, rating) in self.ranking() {
+            writeln!(f, "{:>6.0} {:>6}  {}", rating.elo, rating.games(), name)?;
+        }
+        Ok(())
+    }
+}
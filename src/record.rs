@@ -0,0 +1,246 @@
+//! Game records: everything needed to replay a game move by move.
+
+use crate::{
+    azul::{describe_move, AzulError, Move, State},
+    clock::Clock,
+    history::History,
+    notation::{factories_from_notation, factories_to_notation, NotationError},
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs, io, path::Path, time::Duration};
+
+/// A played game. Starts from a dealt position and lists each move, plus
+/// the factories of every later deal since those can't be derived from
+/// the moves.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameRecord {
+    /// Seed of the random generator used, if known
+    pub seed: Option<u64>,
+    /// The dealt position the game started from, in notation
+    pub initial: String,
+    /// Every move, in move notation
+    pub moves: Vec<String>,
+    /// Factories dealt at the start of each round after the first, in
+    /// notation
+    pub deals: Vec<String>,
+    /// Marks on the moves judged worse than the engine's, see
+    /// [`annotate`](crate::annotate)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+}
+
+/// A game paused to be continued later: the record so far, and what the
+/// record doesn't hold that continuing needs.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedGame {
+    pub record: GameRecord,
+    /// Seat of the human player
+    pub human: usize,
+    /// Seed to continue the random generators from, as their state can't
+    /// be saved
+    pub seed: u64,
+    /// Time control of the clocks, e.g. `5+3`, if the game has clocks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_control: Option<String>,
+    /// Time left on the clock of each player, in milliseconds
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clocks: Vec<u64>,
+}
+
+impl SavedGame {
+    /// Pauses a game at the current position of `history`, to continue it
+    /// from `seed`, with the seat of the human and the clocks, if any.
+    pub fn new(history: &History<State>, human: usize, seed: u64, clock: Option<&Clock>) -> Self {
+        Self {
+            record: GameRecord::from_states(None, history.states()),
+            human,
+            seed,
+            time_control: clock.map(|clock| clock.control().to_string()),
+            clocks: clock.map_or(Vec::new(), |clock| {
+                let remaining = clock.remaining().iter();
+                remaining
+                    .map(|remaining| remaining.as_millis() as u64)
+                    .collect()
+            }),
+        }
+    }
+
+    /// The positions and clocks to continue the game with. Fails if the
+    /// record doesn't replay, or the seat of the human or the clocks don't
+    /// fit the game.
+    pub fn restore(&self) -> Result<(History<State>, Option<Clock>), String> {
+        let states = self.record.replay().map_err(|error| error.to_string())?;
+        let players = states[0].players.len();
+        if self.human >= players {
+            return Err(format!("no seat {} in a game of {}", self.human, players));
+        }
+        let mut history = History::new(states[0].clone());
+        for state in &states[1..] {
+            history.push(state.clone());
+        }
+        let clock = match &self.time_control {
+            None => None,
+            Some(_) if self.clocks.len() != players => {
+                return Err(format!("expected {} clocks", players))
+            }
+            Some(control) => {
+                let remaining = self.clocks.iter().map(|ms| Duration::from_millis(*ms));
+                Some(Clock::with_remaining(control.parse()?, remaining.collect()))
+            }
+        };
+        Ok((history, clock))
+    }
+
+    /// Writes the game as JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+    }
+
+    /// Reads a game written by `save`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+/// How bad a move is, written like in chess annotations.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Mark {
+    /// `?!`
+    #[serde(rename = "?!")]
+    Dubious,
+    /// `??`
+    #[serde(rename = "??")]
+    Blunder,
+}
+
+impl fmt::Display for Mark {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Mark::Dubious => "?!",
+            Mark::Blunder => "??",
+        })
+    }
+}
+
+/// A mark on a move, with the move the engine would have made instead.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Annotation {
+    /// Index of the move in `moves`
+    pub index: usize,
+    pub mark: Mark,
+    /// The engine's move, in move notation
+    pub better: String,
+    /// How much worse the move is than the engine's, in hundredths of a
+    /// point
+    pub loss: i32,
+}
+
+/// Reasons a record can't be replayed.
+#[derive(Debug)]
+pub enum RecordError {
+    Notation(NotationError),
+    /// The move at the index is illegal
+    Move(usize, AzulError),
+    /// A round ended but the record holds no more deals
+    MissingDeal,
+    /// The recorded deal isn't in the bag
+    BadDeal(String),
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordError::Notation(error) => write!(f, "{}", error),
+            RecordError::Move(index, error) => write!(f, "move {}: {}", index + 1, error),
+            RecordError::MissingDeal => f.write_str("deal missing from record"),
+            RecordError::BadDeal(deal) => write!(f, "can't deal {}", deal),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+impl From<NotationError> for RecordError {
+    fn from(error: NotationError) -> Self {
+        RecordError::Notation(error)
+    }
+}
+
+impl GameRecord {
+    /// Starts recording a game from a dealt position.
+    pub fn new(seed: Option<u64>, initial: &State) -> Self {
+        Self {
+            seed,
+            initial: initial.to_notation(),
+            moves: Vec::new(),
+            deals: Vec::new(),
+            annotations: Vec::new(),
+        }
+    }
+
+    /// Records a game through its positions, e.g. those of a `History`,
+    /// from the dealt first one on.
+    pub fn from_states(seed: Option<u64>, states: &[State]) -> Self {
+        let mut record = Self::new(seed, &states[0]);
+        for pair in states.windows(2) {
+            record.push(&pair[0], &pair[1]);
+        }
+        record
+    }
+
+    /// Records the move from `before` to `after`, including any new deal.
+    /// Returns false if there is no such move.
+    pub fn push(&mut self, before: &State, after: &State) -> bool {
+        let Some(description) = describe_move(before, after) else {
+            return false;
+        };
+        let m = description.to_move();
+        if before.apply(&m).is_ok_and(|state| state.needs_deal()) {
+            self.deals.push(factories_to_notation(&after.factories));
+        }
+        self.moves.push(m.to_string());
+        true
+    }
+
+    /// All positions of the game, starting with the initial one.
+    pub fn replay(&self) -> Result<Vec<State>, RecordError> {
+        let mut state = State::from_notation(&self.initial)?;
+        let mut deals = self.deals.iter();
+        let mut states = vec![state.clone()];
+        for (index, m) in self.moves.iter().enumerate() {
+            let m = Move::parse(m)?;
+            state = state
+                .apply(&m)
+                .map_err(|error| RecordError::Move(index, error))?;
+            if state.needs_deal() {
+                let deal = deals.next().ok_or(RecordError::MissingDeal)?;
+                if !state.deal_factories(factories_from_notation(deal)?) {
+                    return Err(RecordError::BadDeal(deal.clone()));
+                }
+            }
+            states.push(state.clone());
+        }
+        Ok(states)
+    }
+
+    /// The mark on the move at `index`, counting from zero, if it has one.
+    pub fn annotation(&self, index: usize) -> Option<&Annotation> {
+        self.annotations
+            .iter()
+            .find(|annotation| annotation.index == index)
+    }
+
+    /// Writes the record as JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+    }
+
+    /// Reads a record written by `save`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
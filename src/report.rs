@@ -0,0 +1,237 @@
+//! Self-contained HTML reports of recorded games, for reviewing bot games:
+//! a chart of the scores over time, and for every move the board after it
+//! and how the engine evaluated the position before it.
+
+use crate::{
+    azul::{Player, State, Tile, TileSet, WallVariant, TILES, WALL},
+    board_evaluation::BoardEvaluation,
+    minmax::{search_with_info, DeterministicGameState},
+    record::{GameRecord, RecordError},
+};
+use rand::{rngs::StdRng, SeedableRng};
+use std::fmt::Write;
+
+const STYLE: &str = "
+body { font-family: sans-serif; max-width: 60em; margin: auto; }
+.position { display: flex; flex-wrap: wrap; gap: 1em; align-items: flex-start; }
+.board { border: 1px solid #ccc; padding: 0.5em; }
+.tile { display: inline-block; width: 1em; height: 1em; margin: 1px; border: 1px solid #888; }
+.empty { opacity: 0.2; }
+.black { background: #333; }
+.white { background: #eee; }
+.azul { background: #2a6ebb; }
+.yellow { background: #f2c12e; }
+.red { background: #d33; }
+.hole { border-style: dotted; }
+table { border-collapse: collapse; }
+td { padding: 0; }
+.evaluation { color: #555; }
+";
+
+// line colors of the players in the chart
+const LINES: [&str; 4] = ["#2a6ebb", "#d33", "#3a3", "#a3a"];
+
+// a tile, or with empty set the faint color of an empty wall cell
+fn cell(out: &mut String, tile: Tile, empty: bool) {
+    let empty = if empty { " empty" } else { "" };
+    write!(out, r#"<span class="tile {}{}"></span>"#, tile, empty).unwrap();
+}
+
+fn tiles(out: &mut String, tiles: &TileSet) {
+    for tile in TILES {
+        for _ in 0..tiles.get(tile) {
+            cell(out, tile, false);
+        }
+    }
+}
+
+fn hole(out: &mut String) {
+    out.push_str(r#"<span class="tile hole"></span>"#);
+}
+
+fn board(out: &mut String, player: &Player, variant: WallVariant, name: &str) {
+    write!(
+        out,
+        r#"<div class="board"><b>{}</b> {} points<table>"#,
+        name, player.points
+    )
+    .unwrap();
+    for (row_index, row) in player.rows.iter().enumerate() {
+        // pattern row, right aligned
+        out.push_str(r#"<tr><td align="right">"#);
+        let (filled, count) = row.map_or((None, 0), |(tile, count)| (Some(tile), count));
+        for _ in count..row_index + 1 {
+            hole(out);
+        }
+        if let Some(filled) = filled {
+            for _ in 0..count {
+                cell(out, filled, false);
+            }
+        }
+        out.push_str("</td><td>&nbsp;</td><td>");
+        for (column_index, pattern) in WALL[row_index].iter().enumerate() {
+            match (player.wall.tile(row_index, column_index), variant) {
+                (Some(placed), _) => cell(out, placed, false),
+                (None, WallVariant::Colored) => cell(out, *pattern, true),
+                (None, WallVariant::Free) => hole(out),
+            }
+        }
+        out.push_str("</td></tr>");
+    }
+    out.push_str("</table>floor ");
+    tiles(out, &player.floor.tiles);
+    writeln!(out, " (-{})</div>", player.floor.penalty()).unwrap();
+}
+
+fn position(out: &mut String, state: &State, names: &[String]) {
+    out.push_str(r#"<div class="position"><div class="board">"#);
+    for (index, factory) in state.factories.iter().enumerate() {
+        write!(out, "{}: ", index + 1).unwrap();
+        tiles(out, factory);
+        out.push_str("<br>");
+    }
+    out.push_str("center: ");
+    tiles(out, &state.center);
+    out.push_str("</div>");
+    for (player, name) in state.players.iter().zip(names) {
+        board(out, player, state.variant, name);
+    }
+    out.push_str("</div>\n");
+}
+
+// points of every player after every move, as an SVG line chart
+fn chart(out: &mut String, states: &[State], names: &[String]) {
+    let (width, height) = (600.0, 200.0);
+    let max = states
+        .iter()
+        .flat_map(|state| state.players.iter().map(|player| player.points))
+        .max()
+        .unwrap_or(0)
+        .max(1) as f64;
+    let step = width / (states.len().max(2) - 1) as f64;
+    writeln!(
+        out,
+        r#"<svg width="{}" height="{}" viewBox="-5 -5 {} {}">"#,
+        width + 120.0,
+        height + 10.0,
+        width + 120.0,
+        height + 10.0
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "<line x1=\"0\" y1=\"{h}\" x2=\"{w}\" y2=\"{h}\" stroke=\"#888\"/>",
+        w = width,
+        h = height
+    )
+    .unwrap();
+    for (index, name) in names.iter().enumerate() {
+        let points: Vec<_> = states
+            .iter()
+            .enumerate()
+            .map(|(move_index, state)| {
+                let points = state.players[index].points as f64;
+                format!(
+                    "{:.1},{:.1}",
+                    move_index as f64 * step,
+                    height - height * points / max
+                )
+            })
+            .collect();
+        let color = LINES[index % LINES.len()];
+        writeln!(
+            out,
+            r#"<polyline fill="none" stroke="{}" stroke-width="2" points="{}"/>"#,
+            color,
+            points.join(" ")
+        )
+        .unwrap();
+        writeln!(
+            out,
+            r#"<text x="{}" y="{}" fill="{}">{}</text>"#,
+            width + 10.0,
+            20.0 * (index + 1) as f64,
+            color,
+            name
+        )
+        .unwrap();
+    }
+    out.push_str("</svg>\n");
+}
+
+/// The game as an HTML page. Every position is searched `depth` moves
+/// ahead for the engine's evaluation, skipped if `depth` is zero.
+pub fn render(record: &GameRecord, depth: usize) -> Result<String, RecordError> {
+    let states = record.replay()?;
+    let names: Vec<_> = (1..=states[0].players.len())
+        .map(|index| format!("Player {}", index))
+        .collect();
+    let mut rng = StdRng::seed_from_u64(record.seed.unwrap_or(0));
+    let mut evaluation = BoardEvaluation::default();
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Azul game</title><style>{}</style></head><body>",
+        STYLE
+    )
+    .unwrap();
+    out.push_str("<h1>Azul game</h1>\n<p>");
+    if let Some(seed) = record.seed {
+        write!(out, "seed {}, ", seed).unwrap();
+    }
+    let last = states.last().unwrap();
+    let scores: Vec<_> = last
+        .players
+        .iter()
+        .zip(&names)
+        .map(|(player, name)| format!("{} {} points", name, player.points))
+        .collect();
+    writeln!(
+        out,
+        "{} moves, {}</p>",
+        record.moves.len(),
+        scores.join(", ")
+    )
+    .unwrap();
+    chart(&mut out, &states, &names);
+
+    out.push_str("<h2>Start</h2>\n");
+    position(&mut out, &states[0], &names);
+    for (index, (m, pair)) in record.moves.iter().zip(states.windows(2)).enumerate() {
+        let (before, after) = (&pair[0], &pair[1]);
+        // the record replayed, so the move is valid
+        let played = m.parse().unwrap();
+        let description = before.describe(&played);
+        writeln!(
+            out,
+            "<h2>{}. {} {} ({})</h2>",
+            index + 1,
+            names[description.player],
+            description,
+            m
+        )
+        .unwrap();
+        if depth > 0 {
+            let (_, info) = search_with_info(before, &mut evaluation, &mut rng, depth);
+            let best = info
+                .pv
+                .first()
+                .map_or(String::new(), |best| match *best == played {
+                    true => ", the engine agrees".to_string(),
+                    false => format!(", the engine prefers {}", best),
+                });
+            writeln!(
+                out,
+                r#"<p class="evaluation">evaluation {:+.2} for {}{}</p>"#,
+                info.score as f64 / 100.0,
+                names[before.current_player()],
+                best
+            )
+            .unwrap();
+        }
+        position(&mut out, after, &names);
+    }
+    out.push_str("</body></html>\n");
+    Ok(out)
+}
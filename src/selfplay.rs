@@ -0,0 +1,214 @@
+//! Training data for learned evaluations, from games an engine plays
+//! against itself.
+//!
+//! Every position of every game is a sample, written to a directory as
+//! [NumPy `.npy` files](https://numpy.org/doc/stable/reference/generated/numpy.lib.format.html)
+//! with a row per sample:
+//!
+//! * `features.npy`: `float32`, `(samples, FEATURES)`, the position from
+//!   the point of view of the player to move, see [`features`](crate::features).
+//! * `moves.npy`: `int32`, `(samples, 4)`, the move the engine chose:
+//!   factory index or -1 for the center, tile (black, white, azul, yellow,
+//!   red), pattern row 0 to 4 or 5 for the floor line, and the wall column
+//!   in the free variant or -1.
+//! * `results.npy`: `float32`, `(samples, 2)`, how the game ended for the
+//!   player to move: 1 for a win, 0 for a draw and -1 for a loss, and the
+//!   final points minus those of the best opponent.
+//! * `games.npy`: `int32`, `(samples,)`, the game of the sample, to keep
+//!   the positions of a game together when splitting the data.
+//!
+//! Load them with `numpy.load`, or bundle them with `numpy.savez`.
+
+use crate::{
+    azul::{Destination, Move, Origin, State},
+    engine::{Dealer, Engine},
+    features::{encode, FEATURES},
+    minmax::DeterministicGameState,
+};
+use rand::Rng;
+use std::{
+    cmp::Ordering,
+    fs::{self, File},
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+    marker::PhantomData,
+    path::Path,
+};
+
+// the move as numbers, see the module documentation
+fn encode_move(m: &Move) -> [i32; 4] {
+    let origin = match m.origin {
+        Origin::Factory(index) => index as i32,
+        Origin::Center => -1,
+    };
+    let (row, column) = match m.destination {
+        Destination::Row(row) => (row as i32, -1),
+        Destination::Wall(row, column) => (row as i32, column as i32),
+        Destination::Floor => (5, -1),
+    };
+    [origin, m.tile as i32, row, column]
+}
+
+// a value of a .npy file
+trait Element: Copy {
+    const DESCR: &'static str;
+    fn write(self, out: &mut impl Write) -> io::Result<()>;
+}
+
+impl Element for f32 {
+    const DESCR: &'static str = "<f4";
+    fn write(self, out: &mut impl Write) -> io::Result<()> {
+        out.write_all(&self.to_le_bytes())
+    }
+}
+
+impl Element for i32 {
+    const DESCR: &'static str = "<i4";
+    fn write(self, out: &mut impl Write) -> io::Result<()> {
+        out.write_all(&self.to_le_bytes())
+    }
+}
+
+// .npy file written a row at a time. The header is padded to a fixed
+// length, so that it can be written again with the number of rows at the
+// end
+struct Npy<T> {
+    out: BufWriter<File>,
+    // None for a one dimensional array
+    columns: Option<usize>,
+    rows: usize,
+    element: PhantomData<T>,
+}
+
+const HEADER_LENGTH: usize = 128;
+
+impl<T: Element> Npy<T> {
+    fn create(path: &Path, columns: Option<usize>) -> io::Result<Self> {
+        let mut npy = Self {
+            out: BufWriter::new(File::create(path)?),
+            columns,
+            rows: 0,
+            element: PhantomData,
+        };
+        npy.write_header()?;
+        Ok(npy)
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        let shape = match self.columns {
+            Some(columns) => format!("({}, {})", self.rows, columns),
+            None => format!("({},)", self.rows),
+        };
+        let dictionary = format!(
+            "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+            T::DESCR,
+            shape
+        );
+        // magic, version and header length take the first ten bytes
+        let padding = HEADER_LENGTH - 10 - dictionary.len() - 1;
+        self.out.write_all(b"\x93NUMPY\x01\x00")?;
+        self.out
+            .write_all(&((HEADER_LENGTH - 10) as u16).to_le_bytes())?;
+        self.out.write_all(dictionary.as_bytes())?;
+        self.out.write_all(&vec![b' '; padding])?;
+        self.out.write_all(b"\n")
+    }
+
+    fn push(&mut self, row: &[T]) -> io::Result<()> {
+        assert_eq!(row.len(), self.columns.unwrap_or(1), "bad row length");
+        for value in row {
+            value.write(&mut self.out)?;
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        self.out.seek(SeekFrom::Start(0))?;
+        self.write_header()?;
+        self.out.flush()
+    }
+}
+
+// the files of the samples
+struct Samples {
+    features: Npy<f32>,
+    moves: Npy<i32>,
+    results: Npy<f32>,
+    games: Npy<i32>,
+}
+
+impl Samples {
+    fn create(directory: &Path) -> io::Result<Self> {
+        fs::create_dir_all(directory)?;
+        Ok(Self {
+            features: Npy::create(&directory.join("features.npy"), Some(FEATURES))?,
+            moves: Npy::create(&directory.join("moves.npy"), Some(4))?,
+            results: Npy::create(&directory.join("results.npy"), Some(2))?,
+            games: Npy::create(&directory.join("games.npy"), None)?,
+        })
+    }
+
+    fn finish(self) -> io::Result<()> {
+        self.features.finish()?;
+        self.moves.finish()?;
+        self.results.finish()?;
+        self.games.finish()
+    }
+}
+
+// outcome and final point difference for the player
+fn result(state: &State, player: usize) -> [f32; 2] {
+    let points = state.players[player].points;
+    let best_opponent = state
+        .players
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index != player)
+        .map(|(_, opponent)| opponent.points)
+        .max()
+        .unwrap_or(0);
+    let outcome = match points.cmp(&best_opponent) {
+        Ordering::Greater => 1.0,
+        Ordering::Equal => 0.0,
+        Ordering::Less => -1.0,
+    };
+    [outcome, points as f32 - best_opponent as f32]
+}
+
+/// Plays `games` two player games of the engine against itself and writes
+/// every position to `directory`, as described in the module
+/// documentation. `progress` is called after every game. Returns the
+/// number of samples.
+pub fn run<E: Engine, R: Rng>(
+    engine: &mut E,
+    games: usize,
+    directory: &Path,
+    rng: &mut R,
+    mut progress: impl FnMut(usize),
+) -> io::Result<usize> {
+    let mut samples = Samples::create(directory)?;
+    let mut count = 0;
+    for game in 0..games {
+        let mut dealer = Dealer::new(rng.gen());
+        let mut state = State::new(2);
+        dealer.deal(&mut state);
+        let mut played = Vec::new();
+        while !state.result().is_over() {
+            let player = state.current_player();
+            let m = engine.choose(&state, rng);
+            played.push((encode(&state, player), encode_move(&m), player));
+            state = state.apply(&m).unwrap();
+            dealer.deal(&mut state);
+        }
+        for (features, m, player) in played {
+            samples.features.push(&features)?;
+            samples.moves.push(&m)?;
+            samples.results.push(&result(&state, player))?;
+            samples.games.push(&[game as i32])?;
+            count += 1;
+        }
+        progress(game);
+    }
+    samples.finish()?;
+    Ok(count)
+}
@@ -0,0 +1,316 @@
+//! HTTP server exposing games and the engine as JSON, so that e.g. a web
+//! frontend can use this crate as its backend.
+//!
+//! | Request                     | Body                           | Response        |
+//! |-----------------------------|--------------------------------|-----------------|
+//! | `POST /games`               | `{"players": 2, "tc": "5+3"}`  | the new game    |
+//! | `GET /games/<id>`           |                                | the game        |
+//! | `POST /games/<id>/moves`    | `{"move": "F1 B -> row2"}`     | the game        |
+//! | `POST /games/<id>/engine`   | `{"movetime": 1000}`           | the game        |
+//! | `GET /games/<id>/png`       |                                | a PNG image     |
+//!
+//! A game is returned as its id, state, position notation, legal moves in
+//! move notation, the player to move and, once it is over, the `winner`
+//! and the `winners`, which list every player sharing a draw and leave
+//! `winner` null. The engine thinks for `movetime` milliseconds, and its move is included as
+//! `move`. Errors are returned as `{"error": "..."}`. Images of the boards,
+//! see [`azul_png`](crate::azul_png), need the `png` feature.
+//!
+//! With pondering, the engine goes on thinking about the game after its
+//! move until the next move is posted, see [`ponder`](crate::ponder).
+//!
+//! A game created with a time control `tc` has clocks, see
+//! [`clock`](crate::clock), included as `clocks` in milliseconds. The clock
+//! of the player to move runs from the previous move until theirs is
+//! posted, the engine thinks as long as its clock allows rather than
+//! `movetime`, and a player whose time runs out loses, as `flagged`.
+
+use crate::{
+    azul::{Move, State},
+    board_evaluation::BoardEvaluation,
+    clock::{Clock, TimeControl},
+    minmax::{DeterministicGameState, SearchOptions},
+    ponder::{expected_position, Ponderer},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    io,
+    time::{Duration, Instant},
+};
+
+/// The response to a request: HTTP status and JSON body.
+pub type Response = (u16, Value);
+
+fn header(content_type: &str) -> tiny_http::Header {
+    // a valid header, whatever the content type
+    format!("Content-Type: {}", content_type).parse().unwrap()
+}
+
+fn error(status: u16, message: impl Into<String>) -> Response {
+    (status, json!({ "error": message.into() }))
+}
+
+#[derive(Deserialize)]
+struct NewGame {
+    #[serde(default = "two")]
+    players: usize,
+    tc: Option<String>,
+}
+
+fn two() -> usize {
+    2
+}
+
+#[derive(Deserialize)]
+struct PostMove {
+    #[serde(rename = "move")]
+    m: String,
+}
+
+#[derive(Deserialize)]
+struct EngineMove {
+    #[serde(default = "one_second")]
+    movetime: u64,
+}
+
+fn one_second() -> u64 {
+    1000
+}
+
+// a game in progress and the engine playing in it
+struct Game {
+    state: State,
+    engine: Ponderer<State, BoardEvaluation>,
+    clock: Option<Clock>,
+    // when the player to move started thinking
+    turn: Instant,
+}
+
+impl Game {
+    // stops the clock of the player to move, if the game has clocks.
+    // false if their time ran out
+    fn punch(&mut self) -> bool {
+        let elapsed = self.turn.elapsed();
+        self.turn = Instant::now();
+        let player = self.state.current_player();
+        self.clock
+            .as_mut()
+            .is_none_or(|clock| clock.punch(player, elapsed))
+    }
+
+    fn is_over(&self) -> bool {
+        let flagged = self.clock.as_ref().and_then(Clock::flagged);
+        flagged.is_some() || self.state.result().is_over()
+    }
+}
+
+/// Games in progress, by id.
+pub struct Server {
+    games: HashMap<u64, Game>,
+    next_id: u64,
+    rng: StdRng,
+    ponder: bool,
+}
+
+impl Server {
+    /// A server without games, dealing with a generator seeded by `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            games: HashMap::new(),
+            next_id: 0,
+            rng: StdRng::seed_from_u64(seed),
+            ponder: false,
+        }
+    }
+
+    /// Lets the engine ponder on the games between requests.
+    pub fn with_pondering(mut self) -> Self {
+        self.ponder = true;
+        self
+    }
+
+    fn view(id: u64, game: &Game) -> Value {
+        let state = &game.state;
+        let moves: Vec<_> = state.legal_moves().iter().map(|m| m.to_string()).collect();
+        let mut view = json!({
+            "id": id,
+            "state": state,
+            "notation": state.to_notation(),
+            "legal_moves": moves,
+            "current_player": state.current_player(),
+            "winner": state.winner(),
+            "winners": state.winners(),
+        });
+        if let Some(clock) = &game.clock {
+            let clocks: Vec<_> = clock.remaining().iter().map(Duration::as_millis).collect();
+            view["clocks"] = json!(clocks);
+            view["flagged"] = json!(clock.flagged());
+            view["winner"] = json!(clock.winner(state));
+            view["winners"] = json!(clock.winners(state));
+        }
+        view
+    }
+
+    /// Handles a request, where `body` is JSON (or empty).
+    pub fn handle(&mut self, method: &str, path: &str, body: &str) -> Response {
+        let ponder = self.ponder;
+        let body = if body.trim().is_empty() { "{}" } else { body };
+        let segments: Vec<_> = path.trim_matches('/').split('/').collect();
+        match (method, &segments[..]) {
+            ("POST", ["games"]) => match serde_json::from_str::<NewGame>(body) {
+                Ok(request) => match request.tc.as_deref().map(str::parse).transpose() {
+                    Ok(control) => self.create(request.players, control),
+                    Err(e) => error(400, e),
+                },
+                Err(e) => error(400, e.to_string()),
+            },
+            ("GET", ["games", id]) => self.with_game(id, |_, id, game| (200, Self::view(id, game))),
+            ("POST", ["games", id, "moves"]) => match serde_json::from_str::<PostMove>(body) {
+                Ok(request) => self.with_game(id, |rng, id, game| {
+                    let m = match Move::parse(&request.m) {
+                        Ok(m) => m,
+                        Err(e) => return error(400, e.to_string()),
+                    };
+                    if game.is_over() {
+                        return error(400, "the game is over");
+                    }
+                    let next = match game.state.apply(&m) {
+                        Ok(next) => next,
+                        Err(e) => return error(400, e.to_string()),
+                    };
+                    game.engine.stop();
+                    if game.punch() {
+                        game.state = next;
+                        if game.state.needs_deal() {
+                            game.state.deal(rng);
+                        }
+                    }
+                    (200, Self::view(id, game))
+                }),
+                Err(e) => error(400, e.to_string()),
+            },
+            ("POST", ["games", id, "engine"]) => match serde_json::from_str::<EngineMove>(body) {
+                Ok(request) => self.with_game(id, |rng, id, game| {
+                    if game.is_over() {
+                        return error(400, "the game is over");
+                    }
+                    let player = game.state.current_player();
+                    let budget = match &game.clock {
+                        Some(clock) => clock.budget(player),
+                        None => Duration::from_millis(request.movetime),
+                    };
+                    let options = SearchOptions::timed(budget);
+                    let (m, info) = game.engine.search(&game.state, rng, &options);
+                    let Some(m) = m else {
+                        return error(400, "the game is over");
+                    };
+                    if !game.punch() {
+                        return (200, Self::view(id, game));
+                    }
+                    if ponder {
+                        if let Some(expected) = expected_position(&game.state, &info.pv) {
+                            game.engine.ponder(expected, &options, rng.gen());
+                        }
+                    }
+                    game.state = game.state.play(&m);
+                    if game.state.needs_deal() {
+                        game.state.deal(rng);
+                    }
+                    let mut view = Self::view(id, game);
+                    view["move"] = json!(m.to_string());
+                    (200, view)
+                }),
+                Err(e) => error(400, e.to_string()),
+            },
+            _ => error(404, format!("no such endpoint {} {}", method, path)),
+        }
+    }
+
+    fn create(&mut self, players: usize, control: Option<TimeControl>) -> Response {
+        if !(2..=4).contains(&players) {
+            return error(400, "a game has two to four players");
+        }
+        let mut state = State::new(players);
+        state.deal(&mut self.rng);
+        let id = self.next_id;
+        self.next_id += 1;
+        let game = Game {
+            state,
+            engine: Ponderer::new(BoardEvaluation::default()),
+            clock: control.map(|control| Clock::new(control, players)),
+            turn: Instant::now(),
+        };
+        let view = Self::view(id, &game);
+        self.games.insert(id, game);
+        (201, view)
+    }
+
+    fn with_game(
+        &mut self,
+        id: &str,
+        f: impl FnOnce(&mut StdRng, u64, &mut Game) -> Response,
+    ) -> Response {
+        let Ok(id) = id.parse() else {
+            return error(404, format!("bad game id '{}'", id));
+        };
+        match self.games.get_mut(&id) {
+            Some(game) => f(&mut self.rng, id, game),
+            None => error(404, format!("no game {}", id)),
+        }
+    }
+
+    /// The boards of a game as a PNG image.
+    #[cfg(feature = "png")]
+    pub fn png(&self, id: &str) -> Result<Vec<u8>, Response> {
+        let Ok(id) = id.parse() else {
+            return Err(error(404, format!("bad game id '{}'", id)));
+        };
+        match self.games.get(&id) {
+            Some(game) => Ok(crate::azul_png::encode_png(&game.state)),
+            None => Err(error(404, format!("no game {}", id))),
+        }
+    }
+
+    // the response to a request, as it's sent
+    fn respond(
+        &mut self,
+        request: &mut tiny_http::Request,
+    ) -> tiny_http::Response<io::Cursor<Vec<u8>>> {
+        let path = request.url().trim_matches('/').to_string();
+        let segments: Vec<_> = path.split('/').collect();
+        let (status, value) = match (request.method(), &segments[..]) {
+            #[cfg(feature = "png")]
+            (tiny_http::Method::Get, ["games", id, "png"]) => match self.png(id) {
+                Ok(image) => {
+                    return tiny_http::Response::from_data(image).with_header(header("image/png"))
+                }
+                Err(response) => response,
+            },
+            _ => {
+                let mut body = String::new();
+                match request.as_reader().read_to_string(&mut body) {
+                    Ok(_) => self.handle(request.method().as_str(), request.url(), &body),
+                    Err(e) => error(400, e.to_string()),
+                }
+            }
+        };
+        tiny_http::Response::from_string(value.to_string())
+            .with_status_code(status)
+            .with_header(header("application/json"))
+    }
+
+    /// Serves requests on the port until the process is stopped. Requests
+    /// are handled one at a time.
+    pub fn serve(&mut self, port: u16) -> io::Result<()> {
+        let server = tiny_http::Server::http(("0.0.0.0", port)).map_err(io::Error::other)?;
+        for mut request in server.incoming_requests() {
+            let response = self.respond(&mut request);
+            // the client may have gone away, which is no reason to stop
+            let _ = request.respond(response);
+        }
+        Ok(())
+    }
+}
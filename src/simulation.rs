@@ -0,0 +1,110 @@
+//! Batches of games between engines of the registry, for experiments that
+//! need many games, e.g. how much going first is worth. Unlike the matches
+//! of the `tournament` module, games can have up to four players.
+
+use crate::{
+    azul::State,
+    engine::{self, Dealer, Spec},
+    minmax::{DeterministicGameState, GameResult},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// How a game of a simulation went. Engines are indexed as passed to
+/// `simulate`, whatever their seats.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct SimulatedGame {
+    /// The engine in each seat, in seating order
+    pub seats: Vec<usize>,
+    /// Final points of each engine
+    pub points: Vec<usize>,
+    /// The winning engine, by the rules, or None if engines share the
+    /// victory
+    pub winner: Option<usize>,
+    /// Moves made in the game
+    pub moves: usize,
+}
+
+/// Results of every game of a simulation, and statistics over them.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct SimulationReport {
+    pub games: Vec<SimulatedGame>,
+    /// Games won by each engine
+    pub wins: Vec<usize>,
+    /// Average points of each engine
+    pub average_points: Vec<f64>,
+    /// Average moves of a game
+    pub average_moves: f64,
+    /// Share of the games won by the player in each seat
+    pub seat_wins: Vec<f64>,
+}
+
+impl SimulationReport {
+    fn new(engines: usize, games: Vec<SimulatedGame>) -> Self {
+        let count = games.len().max(1) as f64;
+        let mut wins = vec![0; engines];
+        let mut points = vec![0; engines];
+        let mut seat_wins = vec![0.0; engines];
+        for game in &games {
+            if let Some(winner) = game.winner {
+                wins[winner] += 1;
+                let seat = game.seats.iter().position(|engine| *engine == winner);
+                seat_wins[seat.unwrap()] += 1.0 / count;
+            }
+            for (total, points) in points.iter_mut().zip(&game.points) {
+                *total += points;
+            }
+        }
+        Self {
+            wins,
+            average_points: points.iter().map(|points| *points as f64 / count).collect(),
+            average_moves: games.iter().map(|game| game.moves).sum::<usize>() as f64 / count,
+            seat_wins,
+            games,
+        }
+    }
+}
+
+/// Plays `games` games between the engines, one player each, rotating the
+/// seats from game to game so every engine starts as often. The same seed
+/// gives the same games. Panics unless there are two to four engines.
+pub fn simulate(engines: &[Spec], games: usize, seed: u64) -> SimulationReport {
+    assert!(
+        (2..=4).contains(&engines.len()),
+        "a game has two to four players"
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+    // built once, so that they keep what they learn from game to game
+    let mut built: Vec<_> = engines.iter().map(Spec::build).collect();
+    let results = (0..games)
+        .map(|game| {
+            let seats: Vec<_> = (0..engines.len())
+                .map(|seat| (seat + game) % engines.len())
+                .collect();
+            let mut dealer = Dealer::new(rng.gen());
+            let mut state = State::new(engines.len());
+            dealer.deal(&mut state);
+            while !state.result().is_over() {
+                let player = state.current_player();
+                state = engine::play(&mut built[seats[player]], &state, &mut rng, &mut dealer);
+            }
+            let mut points = vec![0; engines.len()];
+            for (seat, engine) in seats.iter().enumerate() {
+                points[*engine] = state.players[seat].points;
+            }
+            SimulatedGame {
+                winner: match state.result() {
+                    GameResult::Won { player, .. } => Some(seats[player]),
+                    _ => None,
+                },
+                points,
+                moves: state.moves,
+                seats,
+            }
+        })
+        .collect();
+    SimulationReport::new(engines.len(), results)
+}
@@ -0,0 +1,144 @@
+//! Statistics over many recorded games: how the scores and floor penalties
+//! are distributed, how long games last, which colors are taken and how
+//! much going first is worth.
+
+use crate::{
+    azul::{describe_scoring, ScoreEvent, TILES},
+    minmax::{DeterministicGameState, GameResult},
+    record::{GameRecord, RecordError},
+};
+use std::{collections::BTreeMap, fmt};
+
+// width of the buckets of the score histogram, in points
+const BUCKET: usize = 10;
+
+/// Statistics of the games added so far.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub games: usize,
+    /// Final points of every player, by the lowest score of its bucket of
+    /// ten points
+    pub scores: BTreeMap<usize, usize>,
+    /// Points lost to the floor line by a player in a round, and how often
+    pub penalties: BTreeMap<usize, usize>,
+    /// Moves taking each color, indexed by `Tile as usize`
+    pub picks: [usize; 5],
+    /// Rounds played in all games
+    pub rounds: usize,
+    /// Games won by the first player
+    pub first_player_wins: usize,
+    // games with a single winner
+    decided: usize,
+}
+
+impl Stats {
+    /// Adds the statistics of a recorded game.
+    pub fn add(&mut self, record: &GameRecord) -> Result<(), RecordError> {
+        let states = record.replay()?;
+        for pair in states.windows(2) {
+            let Some((description, scoring)) = describe_scoring(&pair[0], &pair[1]) else {
+                continue;
+            };
+            self.picks[description.tile as usize] += 1;
+            if scoring.is_empty() {
+                continue;
+            }
+            self.rounds += 1;
+            for events in &scoring {
+                let penalty = events
+                    .iter()
+                    .map(|event| match event {
+                        ScoreEvent::Floor { penalty } => *penalty,
+                        _ => 0,
+                    })
+                    .sum();
+                *self.penalties.entry(penalty).or_default() += 1;
+            }
+        }
+        let last = states.last().unwrap();
+        for player in &last.players {
+            *self
+                .scores
+                .entry(player.points / BUCKET * BUCKET)
+                .or_default() += 1;
+        }
+        if let GameResult::Won { player, .. } = last.result() {
+            self.decided += 1;
+            if player == 0 {
+                self.first_player_wins += 1;
+            }
+        }
+        self.games += 1;
+        Ok(())
+    }
+
+    /// Average rounds of a game.
+    pub fn average_rounds(&self) -> f64 {
+        self.rounds as f64 / self.games.max(1) as f64
+    }
+
+    /// Share of the moves taking each color, indexed by `Tile as usize`.
+    pub fn pick_frequencies(&self) -> [f64; 5] {
+        let total = self.picks.iter().sum::<usize>().max(1) as f64;
+        self.picks.map(|picks| picks as f64 / total)
+    }
+
+    /// Share of the games with a single winner that the first player won.
+    pub fn first_player_advantage(&self) -> f64 {
+        self.first_player_wins as f64 / self.decided.max(1) as f64
+    }
+
+    /// The statistics as CSV, a row for every value of a table.
+    pub fn to_csv(&self) -> String {
+        let mut rows = vec!["table,key,value".to_string()];
+        for (points, count) in &self.scores {
+            rows.push(format!("score,{},{}", points, count));
+        }
+        for (penalty, count) in &self.penalties {
+            rows.push(format!("floor penalty,{},{}", penalty, count));
+        }
+        for (tile, frequency) in TILES.iter().zip(self.pick_frequencies()) {
+            rows.push(format!("picks,{},{:.4}", tile, frequency));
+        }
+        rows.push(format!("games,games,{}", self.games));
+        rows.push(format!("games,rounds,{:.4}", self.average_rounds()));
+        rows.push(format!(
+            "games,first player wins,{:.4}",
+            self.first_player_advantage()
+        ));
+        rows.join("\n") + "\n"
+    }
+}
+
+// a bar of a histogram, as long as the share of the largest count
+fn bar(count: usize, largest: usize) -> String {
+    "#".repeat((40 * count).div_ceil(largest.max(1)))
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} games, {:.1} rounds on average, the first player won {:.1}%",
+            self.games,
+            self.average_rounds(),
+            100.0 * self.first_player_advantage()
+        )?;
+        writeln!(f, "\nfinal points")?;
+        let largest = self.scores.values().copied().max().unwrap_or(0);
+        for (points, count) in &self.scores {
+            let range = format!("{}-{}", points, points + BUCKET - 1);
+            writeln!(f, "{:>7} {:>5} {}", range, count, bar(*count, largest))?;
+        }
+        writeln!(f, "\nfloor penalty a round")?;
+        let largest = self.penalties.values().copied().max().unwrap_or(0);
+        for (penalty, count) in &self.penalties {
+            writeln!(f, "{:>7} {:>5} {}", penalty, count, bar(*count, largest))?;
+        }
+        writeln!(f, "\ncolors taken")?;
+        for (tile, frequency) in TILES.iter().zip(self.pick_frequencies()) {
+            writeln!(f, "{:>7} {:>5.1}%", tile.to_string(), 100.0 * frequency)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,533 @@
+//! Engine versus engine matches, to measure whether a change to an engine
+//! makes it stronger, and tournaments of matches between more engines,
+//! round-robin or Swiss, summed up in a cross-table.
+
+use crate::{
+    azul::State,
+    board_evaluation::BoardEvaluation,
+    engine::{self, Dealer, Engine, Spec, Temperature, Tempered},
+    minmax::{DeterministicGameState, Evaluation},
+    observer::Observer,
+};
+use rand::Rng;
+use std::{cmp::Ordering, collections::BTreeMap, fmt, str::FromStr};
+
+/// Plays a two player game between the engines, `engines[i]` in seat `i`,
+/// and returns the final position.
+pub fn play_game<E: Engine, R: Rng>(engines: &mut [E; 2], rng: &mut R) -> State {
+    play_adjudicated(engines, rng, &Adjudication::default(), &mut ()).0
+}
+
+/// Rules for ending hopeless games early, by the evaluation of the
+/// position by `BoardEvaluation` in hundredths of a point. Rules without a
+/// threshold are off.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Adjudication {
+    /// A player resigns when it has been behind by this much for
+    /// `resign_moves` of its moves in a row
+    pub resign: Option<i32>,
+    pub resign_moves: usize,
+    /// A player wins when it has been ahead by this much for
+    /// `adjudicate_moves` moves in a row, of both players
+    pub adjudicate: Option<i32>,
+    pub adjudicate_moves: usize,
+}
+
+/// How a game ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ending {
+    /// Played to the end, won on points
+    Played,
+    /// The player in the seat resigned
+    Resigned(usize),
+    /// Adjudicated as won by the player in the seat
+    Adjudicated(usize),
+}
+
+/// Like `play_game`, but ending the game early if the rules say so, and
+/// telling `observer` how the game goes.
+pub fn play_adjudicated<E: Engine, R: Rng>(
+    engines: &mut [E; 2],
+    rng: &mut R,
+    rules: &Adjudication,
+    observer: &mut impl Observer,
+) -> (State, Ending) {
+    let evaluation = BoardEvaluation::default();
+    let mut dealer = Dealer::new(rng.gen());
+    let mut state = State::new(2);
+    dealer.deal(&mut state);
+    // moves in a row each player was far behind on its turn
+    let mut behind = [0; 2];
+    // the player that was far ahead, and for how many moves in a row
+    let mut ahead = (0, 0);
+    while !state.result().is_over() {
+        let player = state.current_player();
+        let value = evaluation.evaulate(&state, player);
+        if let Some(resign) = rules.resign {
+            behind[player] = if value <= -resign {
+                behind[player] + 1
+            } else {
+                0
+            };
+            if behind[player] >= rules.resign_moves.max(1) {
+                return (state, Ending::Resigned(player));
+            }
+        }
+        if let Some(adjudicate) = rules.adjudicate {
+            let leader = if value >= 0 { player } else { 1 - player };
+            ahead = match ahead {
+                _ if value.abs() < adjudicate => (leader, 0),
+                (previous, moves) if previous == leader => (leader, moves + 1),
+                _ => (leader, 1),
+            };
+            if ahead.1 >= rules.adjudicate_moves.max(1) {
+                return (state, Ending::Adjudicated(leader));
+            }
+        }
+        let engine = &mut engines[player];
+        state = engine::play_observed(engine, &state, rng, &mut dealer, observer);
+    }
+    (state, Ending::Played)
+}
+
+/// Results of a match, from the point of view of the first engine.
+#[derive(Clone, Debug, Default)]
+pub struct Summary {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    /// Games a loser resigned
+    pub resignations: usize,
+    /// Games decided by adjudication
+    pub adjudications: usize,
+    // points of each engine, per game
+    points: Vec<(usize, usize)>,
+    // result of the first engine, per game
+    results: Vec<Ordering>,
+}
+
+// mean and half width of the 95% confidence interval of the mean
+fn mean_interval(values: impl Iterator<Item = f64> + Clone) -> (f64, f64) {
+    let n = values.clone().count() as f64;
+    if n == 0.0 {
+        return (0.0, 0.0);
+    }
+    let mean = values.clone().sum::<f64>() / n;
+    if n < 2.0 {
+        return (mean, 0.0);
+    }
+    let variance = values.map(|value| (value - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, 1.96 * (variance / n).sqrt())
+}
+
+impl Summary {
+    /// Adds a game where the first engine scored `a` points and the second
+    /// `b`. Equal points is a draw.
+    pub fn add(&mut self, a: usize, b: usize) {
+        self.add_result(a, b, a.cmp(&b));
+    }
+
+    /// Adds a game the first engine won, drew or lost as `result` says,
+    /// e.g. by resignation, whatever the points.
+    pub fn add_result(&mut self, a: usize, b: usize, result: Ordering) {
+        match result {
+            Ordering::Greater => self.wins += 1,
+            Ordering::Equal => self.draws += 1,
+            Ordering::Less => self.losses += 1,
+        }
+        self.points.push((a, b));
+        self.results.push(result);
+    }
+
+    pub fn games(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Points of each engine, per game in the order they were played.
+    pub fn points(&self) -> &[(usize, usize)] {
+        &self.points
+    }
+
+    /// Result of the first engine, per game in the order they were played.
+    pub fn results(&self) -> &[Ordering] {
+        &self.results
+    }
+
+    /// Share of the available points won, counting draws as half, with its
+    /// 95% confidence interval.
+    pub fn score(&self) -> (f64, f64) {
+        mean_interval(self.results.iter().map(|result| match result {
+            Ordering::Greater => 1.0,
+            Ordering::Equal => 0.5,
+            Ordering::Less => 0.0,
+        }))
+    }
+
+    /// Average points of each engine.
+    pub fn average_points(&self) -> (f64, f64) {
+        (
+            mean_interval(self.points.iter().map(|(a, _)| *a as f64)).0,
+            mean_interval(self.points.iter().map(|(_, b)| *b as f64)).0,
+        )
+    }
+
+    /// Average points the first engine won by, with its 95% confidence
+    /// interval.
+    pub fn margin(&self) -> (f64, f64) {
+        mean_interval(self.points.iter().map(|(a, b)| *a as f64 - *b as f64))
+    }
+
+    /// The same match from the point of view of the second engine.
+    pub fn swapped(&self) -> Self {
+        Self {
+            wins: self.losses,
+            draws: self.draws,
+            losses: self.wins,
+            resignations: self.resignations,
+            adjudications: self.adjudications,
+            points: self.points.iter().map(|(a, b)| (*b, *a)).collect(),
+            results: self.results.iter().map(|result| result.reverse()).collect(),
+        }
+    }
+
+    // the games of another match between the same engines
+    fn extend(&mut self, other: Summary) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+        self.resignations += other.resignations;
+        self.adjudications += other.adjudications;
+        self.points.extend(other.points);
+        self.results.extend(other.results);
+    }
+
+    // wins and half the draws
+    fn game_points(&self) -> f64 {
+        self.wins as f64 + 0.5 * self.draws as f64
+    }
+}
+
+/// Plays `games` games between `a` and `b`, alternating seats with `a`
+/// first in the first game, ending hopeless games early as `rules` say.
+/// The engines sample their first moves by `temperature`, if it's on.
+#[allow(clippy::too_many_arguments)]
+pub fn run<R: Rng>(
+    a: &Spec,
+    b: &Spec,
+    games: usize,
+    rules: &Adjudication,
+    temperature: &Temperature,
+    rng: &mut R,
+    observer: &mut impl Observer,
+    mut progress: impl FnMut(usize, &Summary),
+) -> Summary {
+    let mut summary = Summary::default();
+    // built once, so that they keep what they learn from game to game
+    let mut engines = [a, b].map(|spec| Tempered {
+        engine: spec.build(),
+        temperature: *temperature,
+    });
+    for game in 0..games {
+        let seat = game % 2;
+        // the engines change seats every game
+        if game > 0 {
+            engines.swap(0, 1);
+        }
+        let (state, ending) = play_adjudicated(&mut engines, rng, rules, observer);
+        let (points_a, points_b) = (state.players[seat].points, state.players[1 - seat].points);
+        // ties are broken by complete rows, as by the rules
+        let rows = |seat: usize| state.players[seat].complete_rows();
+        let result = match ending {
+            Ending::Played => points_a
+                .cmp(&points_b)
+                .then(rows(seat).cmp(&rows(1 - seat))),
+            Ending::Resigned(loser) => {
+                summary.resignations += 1;
+                if loser == seat {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                }
+            }
+            Ending::Adjudicated(winner) => {
+                summary.adjudications += 1;
+                if winner == seat {
+                    Ordering::Greater
+                } else {
+                    Ordering::Less
+                }
+            }
+        };
+        summary.add_result(points_a, points_b, result);
+        progress(game, &summary);
+    }
+    summary
+}
+
+/// The results of a match between `a` and `b` as a table, with the score
+/// of `a` and the margin, and how many games were cut short if `rules`
+/// allowed it.
+pub fn format_match(a: &str, b: &str, summary: &Summary, rules: &Adjudication) -> String {
+    let (points_a, points_b) = summary.average_points();
+    let width = a.len().max(b.len());
+    let mut lines = vec![format!(
+        "{:width$} {:>6} {:>6} {:>6} {:>8}",
+        "engine", "wins", "draws", "losses", "points"
+    )];
+    for (name, wins, losses, points) in [
+        (a, summary.wins, summary.losses, points_a),
+        (b, summary.losses, summary.wins, points_b),
+    ] {
+        lines.push(format!(
+            "{:width$} {:>6} {:>6} {:>6} {:>8.1}",
+            name, wins, summary.draws, losses, points
+        ));
+    }
+    let (score, score_interval) = summary.score();
+    let (margin, margin_interval) = summary.margin();
+    lines.push(format!(
+        "score of {}: {:.1}% ± {:.1}%",
+        a,
+        100.0 * score,
+        100.0 * score_interval
+    ));
+    lines.push(format!(
+        "margin: {:.1} ± {:.1} points",
+        margin, margin_interval
+    ));
+    if rules.resign.is_some() || rules.adjudicate.is_some() {
+        lines.push(format!(
+            "{} resigned, {} adjudicated",
+            summary.resignations, summary.adjudications
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Which engines of a tournament play matches against each other, written
+/// `round-robin` or `swiss:<rounds>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Schedule {
+    /// Every engine plays every other
+    RoundRobin,
+    /// In every round engines play an engine close to them in the
+    /// standings they haven't played yet. With an odd number of engines,
+    /// the lowest engine without one gets a bye, worth winning every game
+    /// of a match
+    Swiss(usize),
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Schedule::RoundRobin => f.write_str("round-robin"),
+            Schedule::Swiss(rounds) => write!(f, "swiss:{}", rounds),
+        }
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.split_once(':') {
+            None if text == "round-robin" => Ok(Schedule::RoundRobin),
+            Some(("swiss", rounds)) => rounds
+                .parse()
+                .map(Schedule::Swiss)
+                .map_err(|_| format!("bad number of rounds '{}' in '{}'", rounds, text)),
+            _ => Err(format!(
+                "unknown schedule '{}', expected round-robin or swiss:<rounds>",
+                text
+            )),
+        }
+    }
+}
+
+/// Results of a tournament: the match between every pair of engines that
+/// met, and the standings.
+#[derive(Clone, Debug, Default)]
+pub struct CrossTable {
+    pub engines: Vec<Spec>,
+    /// Games of every match
+    pub games: usize,
+    // matches by the engines in them, the first with the lower index
+    matches: BTreeMap<(usize, usize), Summary>,
+    // byes of each engine
+    byes: Vec<usize>,
+}
+
+impl CrossTable {
+    /// A table of engines that haven't played yet.
+    pub fn new(engines: Vec<Spec>, games: usize) -> Self {
+        Self {
+            byes: vec![0; engines.len()],
+            engines,
+            games,
+            matches: BTreeMap::new(),
+        }
+    }
+
+    /// The match between engines `a` and `b`, from the point of view of
+    /// `a`, if they met.
+    pub fn summary(&self, a: usize, b: usize) -> Option<Summary> {
+        if a < b {
+            self.matches.get(&(a, b)).cloned()
+        } else {
+            self.matches.get(&(b, a)).map(Summary::swapped)
+        }
+    }
+
+    /// Adds the match between engines `a` and `b`, from the point of view
+    /// of `a`, to any games they played before.
+    pub fn add(&mut self, a: usize, b: usize, summary: Summary) {
+        let (key, summary) = if a < b {
+            ((a, b), summary)
+        } else {
+            ((b, a), summary.swapped())
+        };
+        self.matches.entry(key).or_default().extend(summary);
+    }
+
+    /// Counts a bye for the engine.
+    pub fn add_bye(&mut self, engine: usize) {
+        self.byes[engine] += 1;
+    }
+
+    /// Every match played, as the engines in it and the match from the
+    /// point of view of the first.
+    pub fn matches(&self) -> impl Iterator<Item = (usize, usize, &Summary)> {
+        self.matches
+            .iter()
+            .map(|((a, b), summary)| (*a, *b, summary))
+    }
+
+    /// Games won by the engine, counting draws as half and a bye as
+    /// winning every game of a match.
+    pub fn score(&self, engine: usize) -> f64 {
+        let played: f64 = (0..self.engines.len())
+            .filter_map(|other| self.summary(engine, other))
+            .map(|summary| summary.game_points())
+            .sum();
+        played + (self.byes[engine] * self.games) as f64
+    }
+
+    /// Engines by score, best first, the sum of the scores of their
+    /// opponents breaking ties and then the order they were given in.
+    pub fn standings(&self) -> Vec<usize> {
+        let tiebreak = |engine: usize| -> f64 {
+            (0..self.engines.len())
+                .filter(|other| self.summary(engine, *other).is_some())
+                .map(|other| self.score(other))
+                .sum()
+        };
+        let mut standings: Vec<_> = (0..self.engines.len()).collect();
+        standings.sort_by(|a, b| {
+            self.score(*b)
+                .total_cmp(&self.score(*a))
+                .then(tiebreak(*b).total_cmp(&tiebreak(*a)))
+                .then(a.cmp(b))
+        });
+        standings
+    }
+}
+
+/// The standings, with the games each engine won against every other, by
+/// its place in the standings. Engines that didn't meet show a dot.
+impl fmt::Display for CrossTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let standings = self.standings();
+        let names: Vec<_> = self.engines.iter().map(Spec::to_string).collect();
+        let width = names.iter().map(String::len).max().unwrap_or(0).max(6);
+        write!(f, "{:>3} {:width$}", "", "engine")?;
+        for place in 1..=standings.len() {
+            write!(f, " {:>5}", place)?;
+        }
+        writeln!(f, " {:>6}", "score")?;
+        for (place, engine) in standings.iter().enumerate() {
+            write!(f, "{:>3} {:width$}", place + 1, names[*engine])?;
+            for other in &standings {
+                match self.summary(*engine, *other) {
+                    _ if other == engine => write!(f, " {:>5}", "-")?,
+                    Some(summary) => write!(f, " {:>5.1}", summary.game_points())?,
+                    None => write!(f, " {:>5}", ".")?,
+                }
+            }
+            writeln!(f, " {:>6.1}", self.score(*engine))?;
+        }
+        Ok(())
+    }
+}
+
+/// Plays a tournament between the engines, a match of `games` games for
+/// every pairing of the schedule, as `run` plays them. `progress` is told
+/// about every game, with the engines of the match.
+#[allow(clippy::too_many_arguments)]
+pub fn run_tournament<R: Rng>(
+    engines: &[Spec],
+    schedule: Schedule,
+    games: usize,
+    rules: &Adjudication,
+    temperature: &Temperature,
+    rng: &mut R,
+    observer: &mut impl Observer,
+    mut progress: impl FnMut(usize, usize, usize, &Summary),
+) -> CrossTable {
+    let mut table = CrossTable::new(engines.to_vec(), games);
+    let rounds = match schedule {
+        Schedule::RoundRobin => 1,
+        Schedule::Swiss(rounds) => rounds,
+    };
+    for _ in 0..rounds {
+        let pairings = match schedule {
+            Schedule::RoundRobin => round_robin(engines.len()),
+            Schedule::Swiss(_) => swiss_round(&mut table),
+        };
+        for (a, b) in pairings {
+            let summary = run(
+                &engines[a],
+                &engines[b],
+                games,
+                rules,
+                temperature,
+                rng,
+                observer,
+                |game, summary| progress(a, b, game, summary),
+            );
+            table.add(a, b, summary);
+        }
+    }
+    table
+}
+
+// every pair of engines once
+fn round_robin(engines: usize) -> Vec<(usize, usize)> {
+    (0..engines)
+        .flat_map(|a| (a + 1..engines).map(move |b| (a, b)))
+        .collect()
+}
+
+// pairs the engines down the standings, each with the next engine it
+// hasn't met, or the next engine if it has met them all. The lowest engine
+// without a bye sits out when the number is odd
+fn swiss_round(table: &mut CrossTable) -> Vec<(usize, usize)> {
+    let mut unpaired = table.standings();
+    if unpaired.len() % 2 == 1 {
+        let fewest = unpaired.iter().map(|engine| table.byes[*engine]).min();
+        let bye = unpaired
+            .iter()
+            .rposition(|engine| Some(table.byes[*engine]) == fewest)
+            .unwrap();
+        table.add_bye(unpaired.remove(bye));
+    }
+    let mut pairings = Vec::new();
+    while !unpaired.is_empty() {
+        let a = unpaired.remove(0);
+        let b = unpaired
+            .iter()
+            .position(|b| table.summary(a, *b).is_none())
+            .unwrap_or(0);
+        pairings.push((a, unpaired.remove(b)));
+    }
+    pairings
+}
@@ -0,0 +1,451 @@
+//! Full screen terminal interface: the boards side by side, moves picked
+//! with the keyboard, live analysis by the engine and a log of the moves.
+//!
+//! A move is picked by its origin (`1` to `9` for a factory, `c` for the
+//! center), its color (`b`, `w`, `a`, `y` or `r`) and where the tiles go
+//! (`1` to `5` for a pattern row, `f` for the floor line), followed by the
+//! wall column (`1` to `5`) when the move completes a row in the free
+//! variant. `Esc` starts the move over, `PageUp` and `PageDown` scroll the
+//! log and `q` quits.
+
+use crate::{
+    azul::{Destination, Move, Origin, Player, State, Tile, WallVariant, TILES, WALL},
+    board_evaluation::BoardEvaluation,
+    minmax::{
+        search_with_info, search_with_options, DeterministicGameState, GameResult, SearchInfo,
+        SearchOptions, StopToken,
+    },
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
+    DefaultTerminal, Frame,
+};
+use std::{
+    io,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+
+fn color(tile: Tile) -> Color {
+    match tile {
+        Tile::BLACK => Color::DarkGray,
+        Tile::WHITE => Color::White,
+        Tile::AZUL => Color::Blue,
+        Tile::YELLOW => Color::Yellow,
+        Tile::RED => Color::Red,
+    }
+}
+
+fn tile_span(tile: Tile) -> Span<'static> {
+    Span::styled(tile.letter().to_string(), Style::new().fg(color(tile)))
+}
+
+fn tiles_spans(tiles: &[(Tile, usize)]) -> Vec<Span<'static>> {
+    tiles
+        .iter()
+        .flat_map(|(tile, count)| std::iter::repeat_n(tile_span(*tile), *count))
+        .collect()
+}
+
+// the tiles of a factory, the center or a floor line, by color
+fn counts(get: impl Fn(Tile) -> usize) -> Vec<(Tile, usize)> {
+    TILES.iter().map(|tile| (*tile, get(*tile))).collect()
+}
+
+// a move being picked, one part at a time
+#[derive(Default)]
+struct Selection {
+    origin: Option<Origin>,
+    tile: Option<Tile>,
+    row: Option<usize>,
+}
+
+impl Selection {
+    fn describe(&self) -> String {
+        let origin = self
+            .origin
+            .map_or("?".to_string(), |origin| origin.to_string());
+        let tile = self.tile.map_or("?".to_string(), |tile| tile.to_string());
+        let row = self
+            .row
+            .map_or("?".to_string(), |row| format!("row {}", row + 1));
+        format!("{}, {}, {}", origin, tile, row)
+    }
+}
+
+// the deepest analysis of a position so far
+struct Analysis {
+    key: u64,
+    info: SearchInfo<Move>,
+}
+
+struct App {
+    state: State,
+    human: usize,
+    names: Vec<String>,
+    depth: usize,
+    rng: StdRng,
+    selection: Selection,
+    log: Vec<String>,
+    // lines scrolled up from the end of the log
+    scroll: usize,
+    message: String,
+    analysis: Option<Analysis>,
+    // stops the analysis of the previous position
+    stop: StopToken,
+    sender: Sender<Analysis>,
+    receiver: Receiver<Analysis>,
+}
+
+impl App {
+    fn name(&self, player: usize) -> &str {
+        self.names.get(player).map_or("?", String::as_str)
+    }
+
+    // analyses the current position one level deeper at a time in the
+    // background, until the depth is reached or the position changes
+    fn analyze(&mut self) {
+        self.stop.stop();
+        self.stop = StopToken::new();
+        if self.state.result().is_over() {
+            return;
+        }
+        let state = self.state.clone();
+        let key = state.zobrist();
+        let depth = self.depth;
+        let sender = self.sender.clone();
+        let stop = self.stop.clone();
+        let mut rng = StdRng::seed_from_u64(self.rng.gen());
+        thread::spawn(move || {
+            let mut evaluation = BoardEvaluation::default();
+            for depth in 1..=depth {
+                let options = SearchOptions {
+                    stop: Some(stop.clone()),
+                    ..SearchOptions::depth(depth)
+                };
+                let (_, info) = search_with_options(&state, &mut evaluation, &mut rng, &options);
+                // the position changed, or the interface is gone
+                if stop.is_stopped() || sender.send(Analysis { key, info }).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+
+    // keeps the latest analysis of the current position
+    fn receive(&mut self) {
+        for analysis in self.receiver.try_iter() {
+            if analysis.key == self.state.zobrist() {
+                self.analysis = Some(analysis);
+            }
+        }
+    }
+
+    // logs and plays the move, dealing if the round ends
+    fn play(&mut self, m: &Move) {
+        let player = self.state.current_player();
+        let description = self.state.describe(m);
+        self.log
+            .push(format!("{} {} ({})", self.name(player), description, m));
+        let mut next = self.state.apply(m).unwrap();
+        if next.needs_deal() {
+            next.deal(&mut self.rng);
+            self.log.push("new round".to_string());
+        }
+        self.state = next;
+        self.selection = Selection::default();
+        self.analysis = None;
+        self.scroll = 0;
+        self.analyze();
+    }
+
+    fn engine_move(&mut self) {
+        let mut evaluation = BoardEvaluation::default();
+        let (m, _) = search_with_info(&self.state, &mut evaluation, &mut self.rng, self.depth);
+        self.play(&m.unwrap());
+    }
+
+    // legal moves matching the selection so far
+    fn matching(&self) -> Vec<Move> {
+        let selection = &self.selection;
+        self.state
+            .legal_moves()
+            .into_iter()
+            .filter(|m| selection.origin.is_none_or(|origin| m.origin == origin))
+            .filter(|m| selection.tile.is_none_or(|tile| m.tile == tile))
+            .filter(|m| match (selection.row, m.destination) {
+                (None, _) => true,
+                (Some(row), Destination::Row(index) | Destination::Wall(index, _)) => row == index,
+                (Some(_), Destination::Floor) => false,
+            })
+            .collect()
+    }
+
+    // the move, if it's legal
+    fn try_play(&mut self, destination: Destination) {
+        let m = self
+            .matching()
+            .into_iter()
+            .find(|m| m.destination == destination);
+        match m {
+            Some(m) => self.play(&m),
+            None => self.message = format!("can't place on {}", destination),
+        }
+    }
+
+    fn key(&mut self, code: KeyCode) {
+        self.message.clear();
+        let selection = &self.selection;
+        let digit = match code {
+            KeyCode::Char(c) => c.to_digit(10).map(|digit| digit as usize),
+            _ => None,
+        };
+        match (code, digit) {
+            (KeyCode::Esc, _) => self.selection = Selection::default(),
+            (KeyCode::PageUp, _) => {
+                self.scroll = (self.scroll + 5).min(self.log.len().saturating_sub(1))
+            }
+            (KeyCode::PageDown, _) => self.scroll = self.scroll.saturating_sub(5),
+            // the origin
+            (KeyCode::Char('c'), _) if selection.origin.is_none() => {
+                self.selection.origin = Some(Origin::Center)
+            }
+            (_, Some(number @ 1..=9)) if selection.origin.is_none() => {
+                self.selection.origin = Some(Origin::Factory(number - 1))
+            }
+            // the color
+            (KeyCode::Char(c), _) if selection.origin.is_some() && selection.tile.is_none() => {
+                match Tile::from_letter(c.to_ascii_uppercase()) {
+                    Some(tile) => self.selection.tile = Some(tile),
+                    None => self.message = format!("no tile '{}'", c),
+                }
+            }
+            // the destination
+            (KeyCode::Char('f'), _) if selection.tile.is_some() && selection.row.is_none() => {
+                self.try_play(Destination::Floor)
+            }
+            (_, Some(number @ 1..=5)) if selection.tile.is_some() && selection.row.is_none() => {
+                let row = number - 1;
+                self.selection.row = Some(row);
+                // the column follows when the move completes the row
+                let chooses = self
+                    .matching()
+                    .iter()
+                    .any(|m| matches!(m.destination, Destination::Wall(..)));
+                if !chooses {
+                    self.selection.row = None;
+                    self.try_play(Destination::Row(row));
+                }
+            }
+            (_, Some(number @ 1..=5)) if selection.row.is_some() => {
+                let row = selection.row.unwrap();
+                self.try_play(Destination::Wall(row, number - 1))
+            }
+            _ => self.message = "press Esc to start the move over".to_string(),
+        }
+        if self.selection.origin.is_some() && self.matching().is_empty() {
+            self.message = format!("no move from {}", self.selection.describe());
+            self.selection = Selection::default();
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [top, boards, bottom, status] = Layout::vertical([
+            Constraint::Length(4),
+            Constraint::Length(9),
+            Constraint::Min(5),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        // factories and center
+        let mut factories: Vec<Span> = Vec::new();
+        for (index, factory) in self.state.factories.iter().enumerate() {
+            factories.push(Span::raw(format!(" {}: ", index + 1)));
+            factories.extend(tiles_spans(&counts(|tile| factory.get(tile))));
+        }
+        let mut center = vec![Span::raw(" center: ")];
+        center.extend(tiles_spans(&counts(|tile| self.state.center.get(tile))));
+        let title = format!(" azul, move {} ", self.state.moves + 1);
+        frame.render_widget(
+            Paragraph::new(vec![Line::from(factories), Line::from(center)])
+                .block(Block::bordered().title(title)),
+            top,
+        );
+
+        // boards side by side
+        let columns =
+            vec![Constraint::Ratio(1, self.state.players.len() as u32); self.state.players.len()];
+        let areas = Layout::horizontal(columns).split(boards);
+        for (index, player) in self.state.players.iter().enumerate() {
+            let mut style = Style::new();
+            if index == self.state.current_player() && !self.state.result().is_over() {
+                style = style.add_modifier(Modifier::BOLD);
+            }
+            let title = format!(" {} {} points ", self.name(index), player.points);
+            frame.render_widget(
+                Paragraph::new(board_lines(player, self.state.variant))
+                    .block(Block::bordered().title(Span::styled(title, style))),
+                areas[index],
+            );
+        }
+
+        // analysis and log
+        let [analysis, log] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(bottom);
+        let lines: Vec<Line> = match &self.analysis {
+            Some(Analysis { info, .. }) => vec![
+                Line::raw(format!("depth {}", info.depth)),
+                Line::raw(format!("score {:+.2}", info.score as f64 / 100.0)),
+                Line::raw(format!("{} nodes in {:.2?}", info.nodes, info.elapsed)),
+                Line::raw("expecting"),
+            ]
+            .into_iter()
+            .chain(info.pv.iter().map(|m| Line::raw(format!("  {}", m))))
+            .collect(),
+            None => vec![Line::raw("thinking...")],
+        };
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" analysis ")),
+            analysis,
+        );
+        let height = log.height.saturating_sub(2) as usize;
+        let end = self.log.len() - self.scroll.min(self.log.len());
+        let start = end.saturating_sub(height);
+        let lines: Vec<Line> = self.log[start..end].iter().map(Line::raw).collect();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" moves ")),
+            log,
+        );
+
+        // what to do next
+        let prompt = match self.state.result() {
+            GameResult::Won { player, .. } => {
+                format!("{} won, press q to quit", self.name(player))
+            }
+            GameResult::Draw { players, .. } => {
+                let names: Vec<_> = players.iter().map(|player| self.name(*player)).collect();
+                format!("{} share the victory, press q to quit", names.join(" and "))
+            }
+            GameResult::Ongoing if self.state.current_player() != self.human => {
+                format!("{} is thinking...", self.name(self.state.current_player()))
+            }
+            GameResult::Ongoing if self.selection.origin.is_none() => {
+                "pick a factory (1-9) or the center (c)".to_string()
+            }
+            GameResult::Ongoing if self.selection.tile.is_none() => {
+                "pick a color (b, w, a, y, r)".to_string()
+            }
+            GameResult::Ongoing if self.selection.row.is_none() => {
+                "pick a pattern row (1-5) or the floor (f)".to_string()
+            }
+            GameResult::Ongoing => "pick a wall column (1-5)".to_string(),
+        };
+        let text = match self.message.is_empty() {
+            true => format!("{}  [{}]", prompt, self.selection.describe()),
+            false => format!("{}  ({})", prompt, self.message),
+        };
+        frame.render_widget(Paragraph::new(text), status);
+    }
+}
+
+// pattern rows, wall and floor line of a board
+fn board_lines(player: &Player, variant: WallVariant) -> Vec<Line<'static>> {
+    let dim = Style::new().add_modifier(Modifier::DIM);
+    let mut lines = Vec::new();
+    for (row_index, row) in player.rows.iter().enumerate() {
+        let row_size = row_index + 1;
+        let (tile, count) = row.map_or((None, 0), |(tile, count)| (Some(tile), count));
+        let mut spans = vec![Span::raw(" ".repeat(6 - row_size))];
+        spans.push(Span::styled(".".repeat(row_size - count), dim));
+        if let Some(tile) = tile {
+            spans.extend(tiles_spans(&[(tile, count)]));
+        }
+        spans.push(Span::raw(" |"));
+        for (column_index, tile) in WALL[row_index].iter().enumerate() {
+            spans.push(Span::raw(" "));
+            spans.push(match (player.wall.tile(row_index, column_index), variant) {
+                (Some(tile), _) => {
+                    tile_span(tile).style(Style::new().fg(color(tile)).add_modifier(Modifier::BOLD))
+                }
+                (None, WallVariant::Colored) => Span::styled(
+                    tile.letter().to_ascii_lowercase().to_string(),
+                    dim.fg(color(*tile)),
+                ),
+                (None, WallVariant::Free) => Span::styled(".", dim),
+            });
+        }
+        lines.push(Line::from(spans));
+    }
+    let mut floor = vec![Span::raw(" floor: ")];
+    floor.extend(tiles_spans(&counts(|tile| player.floor.tiles.get(tile))));
+    floor.push(Span::raw(format!(" (-{})", player.floor.penalty())));
+    lines.push(Line::from(floor));
+    lines
+}
+
+/// Plays a dealt game in the terminal, where the human has the seat
+/// `human` and the engine searching `depth` moves ahead plays the others.
+/// Returns the position the game was left in.
+pub fn run(
+    state: State,
+    human: usize,
+    names: Vec<String>,
+    depth: usize,
+    seed: u64,
+) -> io::Result<State> {
+    let (sender, receiver) = mpsc::channel();
+    let mut app = App {
+        state,
+        human,
+        names,
+        depth,
+        rng: StdRng::seed_from_u64(seed),
+        selection: Selection::default(),
+        log: Vec::new(),
+        scroll: 0,
+        message: String::new(),
+        analysis: None,
+        stop: StopToken::new(),
+        sender,
+        receiver,
+    };
+    app.analyze();
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app);
+    ratatui::restore();
+    result.map(|_| app.state)
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> io::Result<()> {
+    loop {
+        app.receive();
+        terminal.draw(|frame| app.draw(frame))?;
+        let over = app.state.result().is_over();
+        if !over && app.state.current_player() != app.human {
+            app.engine_move();
+            continue;
+        }
+        // redraw now and then for the analysis
+        if !event::poll(Duration::from_millis(100))? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') => return Ok(()),
+                _ if over => {}
+                code => app.key(code),
+            }
+        }
+    }
+}
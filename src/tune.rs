@@ -0,0 +1,127 @@
+//! Tuning the weights of [`LinearEval`] by playing games, with simultaneous
+//! perturbation stochastic approximation (SPSA): every iteration plays
+//! the weights nudged in a random direction against the weights nudged the
+//! opposite way, and moves the weights towards the side that won.
+
+use crate::{
+    engine::Minmax, features::FEATURES, linear_evaluation::LinearEval, tournament::play_game,
+};
+use rand::Rng;
+use std::cmp::Ordering;
+#[cfg(feature = "serde")]
+use std::{io, path::Path};
+
+/// Settings of the tuning.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spsa {
+    pub iterations: usize,
+    /// Pairs of games per iteration, each engine starting one of a pair
+    pub games: usize,
+    /// Search depth of the engines in the games
+    pub depth: usize,
+    /// Size of the first step, in points per unit of a feature
+    pub step: f64,
+    /// Size of the first perturbation, in points per unit of a feature
+    pub perturbation: f64,
+}
+
+impl Default for Spsa {
+    fn default() -> Self {
+        Self {
+            iterations: 100,
+            games: 2,
+            depth: 1,
+            step: 20.0,
+            perturbation: 5.0,
+        }
+    }
+}
+
+// share of the points won by the first engine over the second, from -1 for
+// losing every game to 1 for winning every one
+fn play_match<R: Rng>(a: &LinearEval, b: &LinearEval, spsa: &Spsa, rng: &mut R) -> f64 {
+    let engine = |evaluation: &LinearEval| Minmax {
+        depth: spsa.depth,
+        evaluation: evaluation.clone(),
+        late_move_reductions: true,
+    };
+    let mut total = 0.0;
+    for _ in 0..spsa.games {
+        for a_starts in [true, false] {
+            let mut engines = match a_starts {
+                true => [engine(a), engine(b)],
+                false => [engine(b), engine(a)],
+            };
+            let state = play_game(&mut engines, rng);
+            let (points_a, points_b) = match a_starts {
+                true => (state.players[0].points, state.players[1].points),
+                false => (state.players[1].points, state.players[0].points),
+            };
+            total += match points_a.cmp(&points_b) {
+                Ordering::Greater => 1.0,
+                Ordering::Equal => 0.0,
+                Ordering::Less => -1.0,
+            };
+        }
+    }
+    total / (2 * spsa.games).max(1) as f64
+}
+
+/// Tunes the weights, starting from `start`. `progress` is called with
+/// the weights after every iteration, e.g. to save them.
+pub fn tune<R: Rng>(
+    start: LinearEval,
+    spsa: &Spsa,
+    rng: &mut R,
+    mut progress: impl FnMut(usize, &LinearEval),
+) -> LinearEval {
+    // the usual gain sequences, decaying slowly enough to converge
+    const ALPHA: f64 = 0.602;
+    const GAMMA: f64 = 0.101;
+    let stability = spsa.iterations as f64 / 10.0;
+    let mut weights = start;
+    for iteration in 0..spsa.iterations {
+        let k = iteration as f64 + 1.0;
+        let step = spsa.step / (k + stability).powf(ALPHA);
+        let perturbation = spsa.perturbation / k.powf(GAMMA);
+        let direction: Vec<f64> = (0..FEATURES)
+            .map(|_| if rng.gen() { 1.0 } else { -1.0 })
+            .collect();
+        let (mut plus, mut minus) = (weights.clone(), weights.clone());
+        for (index, sign) in direction.iter().enumerate() {
+            plus.weights[index] += (perturbation * sign) as f32;
+            minus.weights[index] -= (perturbation * sign) as f32;
+        }
+        let result = play_match(&plus, &minus, spsa, rng);
+        // the gradient estimate is result / (2 perturbation sign), and a
+        // sign is its own inverse
+        for (index, sign) in direction.iter().enumerate() {
+            weights.weights[index] += (step * result / (2.0 * perturbation) * sign) as f32;
+        }
+        progress(iteration, &weights);
+    }
+    weights
+}
+
+/// Tunes the weights saved in `path`, or the default ones if there are
+/// none yet, and saves them there after every iteration, so tuning picks
+/// up from them when run again. `progress` is called with the iteration.
+/// Fails if the weights can't be loaded, or couldn't be saved after an
+/// iteration.
+#[cfg(feature = "serde")]
+pub fn tune_saved<R: Rng>(
+    path: &Path,
+    spsa: &Spsa,
+    rng: &mut R,
+    mut progress: impl FnMut(usize),
+) -> io::Result<LinearEval> {
+    let start = LinearEval::load(path)?;
+    let mut saved = Ok(());
+    let weights = tune(start, spsa, rng, |iteration, weights| {
+        progress(iteration);
+        if saved.is_ok() {
+            saved = weights.save(path);
+        }
+    });
+    saved.map(|()| weights)
+}
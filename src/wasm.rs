@@ -0,0 +1,107 @@
+//! JavaScript API for running the engine in a browser, built with
+//! `wasm-pack build --features wasm`.
+//!
+//! ```js
+//! const game = new Game(2, 1234);
+//! game.apply(game.legalMoves()[0]);
+//! game.apply(game.search(4));
+//! ```
+
+use crate::{
+    azul::{Move, State},
+    board_evaluation::BoardEvaluation,
+    minmax::{search, DeterministicGameState},
+};
+use rand::{rngs::StdRng, SeedableRng};
+use wasm_bindgen::prelude::*;
+
+/// A game in progress, with moves and positions in notation.
+#[wasm_bindgen]
+pub struct Game {
+    state: State,
+    rng: StdRng,
+}
+
+#[wasm_bindgen]
+impl Game {
+    /// A new, dealt game. Deals are random from `seed`. Throws unless
+    /// there are two to four players.
+    #[wasm_bindgen(constructor)]
+    pub fn new(players: usize, seed: u32) -> Result<Game, JsError> {
+        if !(2..=4).contains(&players) {
+            return Err(JsError::new("a game has two to four players"));
+        }
+        let mut rng = StdRng::seed_from_u64(seed.into());
+        let mut state = State::new(players);
+        state.deal(&mut rng);
+        Ok(Game { state, rng })
+    }
+
+    /// A game from a position in notation.
+    #[wasm_bindgen(js_name = fromNotation)]
+    pub fn from_notation(notation: &str, seed: u32) -> Result<Game, JsError> {
+        let mut rng = StdRng::seed_from_u64(seed.into());
+        let mut state = State::from_notation(notation)?;
+        if state.needs_deal() {
+            state.deal(&mut rng);
+        }
+        Ok(Game { state, rng })
+    }
+
+    /// The position in notation.
+    pub fn notation(&self) -> String {
+        self.state.to_notation()
+    }
+
+    /// The state as JSON.
+    pub fn state(&self) -> String {
+        serde_json::to_string(&self.state).unwrap()
+    }
+
+    /// Legal moves in move notation.
+    #[wasm_bindgen(js_name = legalMoves)]
+    pub fn legal_moves(&self) -> Vec<String> {
+        self.state
+            .legal_moves()
+            .iter()
+            .map(|m| m.to_string())
+            .collect()
+    }
+
+    /// Makes a move in move notation, dealing a new round if needed.
+    pub fn apply(&mut self, m: &str) -> Result<(), JsError> {
+        let m = Move::parse(m)?;
+        let mut state = self.state.apply(&m)?;
+        if state.needs_deal() {
+            state.deal(&mut self.rng);
+        }
+        self.state = state;
+        Ok(())
+    }
+
+    /// The engine's move, searching `depth` moves ahead, without making
+    /// it. Undefined when the game is over.
+    pub fn search(&mut self, depth: usize) -> Option<String> {
+        let mut evaluation = BoardEvaluation::default();
+        search(&self.state, &mut evaluation, &mut self.rng, depth).map(|m| m.to_string())
+    }
+
+    #[wasm_bindgen(getter, js_name = currentPlayer)]
+    pub fn current_player(&self) -> usize {
+        self.state.current_player()
+    }
+
+    /// The winning player once the game is over, undefined while it goes
+    /// on and after a draw.
+    #[wasm_bindgen(getter)]
+    pub fn winner(&self) -> Option<usize> {
+        self.state.winner()
+    }
+
+    /// Every player sharing the victory once the game is over, empty while
+    /// it goes on.
+    #[wasm_bindgen(getter)]
+    pub fn winners(&self) -> Vec<usize> {
+        self.state.winners()
+    }
+}
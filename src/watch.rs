@@ -0,0 +1,141 @@
+//! Following a game from another terminal while it's played. A spectator
+//! reads the lines of a game log written with `--log`, of an event stream
+//! written with `--events`, or the messages of a live game, see
+//! [`live`](crate::live), and keeps the position they lead to:
+//!
+//! ```text
+//! {"ply":0,"player":0,"position":"...","move":"F1 B -> row2",...}
+//! {"event":"move","ply":0,"player":0,"move":"F1 B -> row2","position":"..."}
+//! {"type":"state","notation":"...","last_move":"F1 B -> row2",...}
+//! ```
+//!
+//! Files are followed like `tail -f`, so a game can be watched from its
+//! first move until its end.
+
+use crate::{
+    azul::{Move, State},
+    minmax::DeterministicGameState,
+    notation::factories_from_notation,
+};
+use serde_json::Value;
+use std::{
+    io::{self, BufRead},
+    thread,
+    time::Duration,
+};
+
+/// The position of a game followed from its messages.
+#[derive(Clone, Default)]
+pub struct Spectator {
+    state: Option<State>,
+    last_move: Option<String>,
+    over: bool,
+}
+
+// a string field of a message
+fn field<'a>(message: &'a Value, name: &str) -> Result<&'a str, String> {
+    message[name]
+        .as_str()
+        .ok_or_else(|| format!("expected '{}' in {}", name, message))
+}
+
+impl Spectator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The position, once a message told it.
+    pub fn state(&self) -> Option<&State> {
+        self.state.as_ref()
+    }
+
+    /// The move leading to the position, in move notation.
+    pub fn last_move(&self) -> Option<&str> {
+        self.last_move.as_deref()
+    }
+
+    /// Whether the game is over.
+    pub fn is_over(&self) -> bool {
+        self.over
+    }
+
+    /// Follows a line of a game log or an event stream. Blank lines are
+    /// skipped. Returns whether the position changed.
+    pub fn update_line(&mut self, line: &str) -> Result<bool, String> {
+        if line.trim().is_empty() {
+            return Ok(false);
+        }
+        let message = serde_json::from_str(line).map_err(|e| e.to_string())?;
+        self.update(&message)
+    }
+
+    /// Follows a message of a game log, an event stream or a live game.
+    /// Messages that say nothing about the position are skipped. Returns
+    /// whether the position changed.
+    pub fn update(&mut self, message: &Value) -> Result<bool, String> {
+        let position = |text: &str| State::from_notation(text).map_err(|e| e.to_string());
+        if let Some(event) = message["event"].as_str() {
+            match event {
+                "move" => {
+                    self.state = Some(position(field(message, "position")?)?);
+                    self.last_move = Some(field(message, "move")?.to_string());
+                }
+                "deal" => {
+                    let factories = factories_from_notation(field(message, "factories")?)
+                        .map_err(|e| e.to_string())?;
+                    let Some(state) = &mut self.state else {
+                        // the first deal, before any position
+                        return Ok(false);
+                    };
+                    if !state.deal_factories(factories) {
+                        return Err("dealt tiles that aren't in the bag".to_string());
+                    }
+                }
+                "game_over" => self.over = true,
+                _ => return Ok(false),
+            }
+        } else if let Some(kind) = message["type"].as_str() {
+            match kind {
+                "state" => {
+                    self.state = Some(position(field(message, "notation")?)?);
+                    self.last_move = message["last_move"].as_str().map(str::to_string);
+                    self.over = !message["winner"].is_null();
+                }
+                "error" => return Err(field(message, "message")?.to_string()),
+                _ => return Ok(false),
+            }
+        } else {
+            // a line of a game log, with the position before the move
+            let before = position(field(message, "position")?)?;
+            let text = field(message, "move")?;
+            let m = Move::parse(text).map_err(|e| e.to_string())?;
+            let after = before.apply(&m).map_err(|e| e.to_string())?;
+            self.over = after.result().is_over();
+            self.state = Some(after);
+            self.last_move = Some(text.to_string());
+        }
+        Ok(true)
+    }
+}
+
+/// Calls `f` with every line of `reader` as it's written, waiting `poll`
+/// for more at the end, until `f` returns false. Lines are passed without
+/// their newline, and only once complete.
+pub fn tail(
+    mut reader: impl BufRead,
+    poll: Duration,
+    mut f: impl FnMut(&str) -> bool,
+) -> io::Result<()> {
+    let mut line = String::new();
+    loop {
+        if reader.read_line(&mut line)? == 0 || !line.ends_with('\n') {
+            // the rest of the line is still being written
+            thread::sleep(poll);
+            continue;
+        }
+        if !f(line.trim_end_matches(['\n', '\r'])) {
+            return Ok(());
+        }
+        line.clear();
+    }
+}
@@ -0,0 +1,28 @@
+//! Zobrist hashing. Every feature of a position (e.g. "three red tiles in
+//! factory two") gets a pseudo random key and a position hashes to the xor
+//! of the keys of its features, so a change of a few features only needs
+//! the keys of those to be xored in and out.
+
+// locations of features
+pub(crate) const BAG: u64 = 1;
+pub(crate) const CENTER: u64 = 2;
+pub(crate) const LID: u64 = 3;
+pub(crate) const TURN: u64 = 4;
+pub(crate) const FACTORY: u64 = 16; // + copies of the factory before it
+pub(crate) const ROW: u64 = 64; // + 5 * player + row
+pub(crate) const FLOOR: u64 = 128; // + player
+pub(crate) const WALL: u64 = 160; // + player
+pub(crate) const POINTS: u64 = 192; // + player
+
+// splitmix64 finalizer
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Key of the feature `(a, b)` at `location`.
+pub(crate) fn key(location: u64, a: usize, b: usize) -> u64 {
+    mix(location << 32 | (a as u64) << 16 | b as u64)
+}
@@ -0,0 +1,41 @@
+#![cfg(feature = "serde")]
+
+use azul::{
+    annotate::{annotate, Thresholds},
+    random_move, DeterministicGameState, GameRecord, Mark, State,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use std::{env, fs};
+
+#[test]
+fn random_moves_are_marked() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    let mut record = GameRecord::new(Some(2), &state);
+    while !state.result().is_over() {
+        let next = random_move(&state, &mut rng);
+        record.push(&state, &next);
+        state = next;
+    }
+    let thresholds = Thresholds::default();
+    let annotated = annotate(&record, 1, &thresholds).unwrap();
+    assert!(!annotated.annotations.is_empty());
+    for annotation in &annotated.annotations {
+        assert_ne!(annotation.better, record.moves[annotation.index]);
+        assert!(annotation.loss >= thresholds.dubious);
+        let blunder = annotation.loss >= thresholds.blunder;
+        assert_eq!(annotation.mark == Mark::Blunder, blunder);
+    }
+    assert!(annotated.replay().unwrap() == record.replay().unwrap());
+
+    // annotations are saved, and left out of records without them
+    let path = env::temp_dir().join(format!("azul-annotated-{}.json", std::process::id()));
+    annotated.save(&path).unwrap();
+    let json = fs::read_to_string(&path).unwrap();
+    assert!(json.contains("\"??\"") || json.contains("\"?!\""));
+    assert_eq!(GameRecord::load(&path).unwrap(), annotated);
+    record.save(&path).unwrap();
+    assert!(!fs::read_to_string(&path).unwrap().contains("annotations"));
+    fs::remove_file(&path).unwrap();
+}
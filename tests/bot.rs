@@ -0,0 +1,59 @@
+#![cfg(feature = "bot")]
+
+use azul::{
+    bot::{Bot, Reply},
+    engine::Spec,
+    DeterministicGameState,
+};
+
+fn texts(replies: &[Reply]) -> Vec<String> {
+    replies.iter().flat_map(Reply::lines).collect()
+}
+
+#[test]
+fn the_bot_plays_a_game_in_a_channel() {
+    let mut bot = Bot::new(Spec::Greedy, 1);
+    assert!(bot.handle("#azul", "ada", "hello").is_empty());
+    assert!(bot.handle("#azul", "ada", "!azulbot").is_empty());
+    let replies = bot.handle("#azul", "ada", "!azul show");
+    assert_eq!(texts(&replies), ["no game here, start one with !azul new"]);
+    let replies = bot.handle("#azul", "ada", "!azul new 3");
+    assert!(matches!(&replies[1], Reply::Board(_)));
+    assert!(replies[1].to_markdown().starts_with("```\n"));
+    assert_eq!(bot.state("#azul").unwrap().players.len(), 3);
+    assert!(bot.state("#other").is_none());
+    // others can look, but not play
+    let replies = bot.handle("#azul", "bo", "!azul new");
+    assert_eq!(
+        texts(&replies),
+        ["ada is playing here, wait for the game to end"]
+    );
+    assert_eq!(bot.handle("#azul", "bo", "!azul show").len(), 1);
+    let m = bot.state("#azul").unwrap().legal_moves()[0];
+    let replies = bot.handle("#azul", "bo", &format!("!azul {}", m));
+    assert_eq!(texts(&replies), ["it's ada's game"]);
+    let replies = bot.handle("#azul", "ada", "!azul F9 B row1");
+    assert!(matches!(&replies[..], [Reply::Text(_)]));
+    // both engines answer every move, until the game ends
+    let mut said = Vec::new();
+    while let Some(state) = bot.state("#azul") {
+        assert_eq!(state.current_player(), 0);
+        let m = state.legal_moves()[0];
+        said.extend(texts(&bot.handle("#azul", "ada", &format!("!azul {}", m))));
+    }
+    assert!(said.last().unwrap().starts_with("game over, ada "));
+    assert!(said.iter().any(|line| line.starts_with("greedy #3 took")));
+}
+
+#[test]
+fn users_resign_their_own_games() {
+    let mut bot = Bot::new(Spec::Random, 2);
+    bot.handle("#azul", "ada", "!azul new");
+    let replies = bot.handle("#azul", "bo", "!azul resign");
+    assert_eq!(texts(&replies), ["it's ada's game"]);
+    let replies = bot.handle("#azul", "ada", "!azul resign");
+    assert_eq!(texts(&replies), ["ada resigns"]);
+    assert!(bot.state("#azul").is_none());
+    let replies = bot.handle("#azul", "bo", "!azul new 5");
+    assert_eq!(texts(&replies), ["a game has two to four players"]);
+}
@@ -0,0 +1,46 @@
+use azul::{
+    clock::{Clock, TimeControl},
+    render_state_with_clocks, RenderOptions, State,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use std::time::Duration;
+
+#[test]
+fn time_controls_are_minutes_plus_seconds() {
+    let control: TimeControl = "5+3".parse().unwrap();
+    assert_eq!(control.base, Duration::from_secs(300));
+    assert_eq!(control.increment, Duration::from_secs(3));
+    assert_eq!(control.to_string().parse::<TimeControl>(), Ok(control));
+    let sudden: TimeControl = "0.5".parse().unwrap();
+    assert_eq!(sudden.base, Duration::from_secs(30));
+    assert_eq!(sudden.increment, Duration::ZERO);
+    assert!("five".parse::<TimeControl>().is_err());
+    assert!("-1+2".parse::<TimeControl>().is_err());
+}
+
+#[test]
+fn running_out_of_time_loses() {
+    let mut state = State::new(2);
+    state.deal(&mut StdRng::seed_from_u64(1));
+    let mut clock = Clock::new("1+2".parse().unwrap(), 2);
+    assert!(clock.budget(0) < Duration::from_secs(60));
+    assert!(clock.punch(0, Duration::from_secs(10)));
+    assert_eq!(clock.remaining()[0], Duration::from_secs(52));
+    assert_eq!(clock.winner(&state), None);
+    assert!(!clock.punch(1, Duration::from_secs(61)));
+    assert_eq!(clock.flagged(), Some(1));
+    assert_eq!(clock.remaining()[1], Duration::ZERO);
+    assert_eq!(clock.winner(&state), Some(0));
+    assert_eq!(clock.winners(&state), vec![0]);
+}
+
+#[test]
+fn boards_show_the_clocks() {
+    let mut state = State::new(2);
+    state.deal(&mut StdRng::seed_from_u64(1));
+    let clocks = [Duration::from_secs(272), Duration::from_secs(5)];
+    let rendered =
+        render_state_with_clocks(&state, &["one", "two"], &clocks, &RenderOptions::plain());
+    assert!(rendered.contains("one 0 points, 4:32"));
+    assert!(rendered.contains("two 0 points, 0:05"));
+}
@@ -0,0 +1,58 @@
+use azul::{
+    features::{encode, BOARD, BOARDS, FEATURES},
+    random_move, DeterministicGameState, State,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+#[test]
+fn features_have_a_fixed_size() {
+    assert_eq!(FEATURES, 269);
+    for players in 2..=4 {
+        let mut rng = StdRng::seed_from_u64(players as u64);
+        let mut state = State::new(players);
+        state.deal(&mut rng);
+        while !state.result().is_over() {
+            for player in 0..players {
+                let features = encode(&state, player);
+                assert_eq!(features.len(), FEATURES);
+                assert!(features
+                    .iter()
+                    .all(|value| value.is_finite() && *value >= 0.0));
+                assert_eq!(
+                    features[BOARDS - 1],
+                    (state.current_player() == player) as u8 as f32
+                );
+                // no boards past the players
+                assert!(features[BOARDS + players * BOARD..]
+                    .iter()
+                    .all(|value| *value == 0.0));
+            }
+            state = random_move(&state, &mut rng);
+        }
+    }
+}
+
+#[test]
+fn boards_start_with_the_player() {
+    let state = State::from_notation(
+        "2BW/AYRR/- - - 7:-/2R/-/-/-:...../...../...../...../.....:B 3:-/1W/-/-/-:A..../...../...../...../.....:- 2",
+    )
+    .unwrap();
+    let (first, second) = (encode(&state, 0), encode(&state, 1));
+    assert_eq!(
+        first[BOARDS..BOARDS + BOARD],
+        second[BOARDS + BOARD..BOARDS + 2 * BOARD]
+    );
+    assert_eq!(
+        second[BOARDS..BOARDS + BOARD],
+        first[BOARDS + BOARD..BOARDS + 2 * BOARD]
+    );
+    let board = &first[BOARDS..BOARDS + BOARD];
+    // the second pattern row holds red, the last color, and is full
+    assert_eq!(board[5 + 4], 1.0);
+    assert_eq!(board[2 * 25], 1.0 / 7.0);
+    assert_eq!(board[2 * 25 + 1], 0.07);
+    // the first factory and the wall of the second player
+    assert_eq!(first[..5], [0.5, 0.25, 0.0, 0.0, 0.0]);
+    assert_eq!(second[BOARDS + 25], 1.0);
+}
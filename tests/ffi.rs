@@ -0,0 +1,43 @@
+#![cfg(feature = "ffi")]
+
+use azul::ffi::{
+    azul_free, azul_from_notation, azul_result, azul_winner, azul_winners, AzulResult,
+};
+use std::{ffi::CString, ptr};
+
+#[test]
+fn header_is_generated_from_the_ffi_module() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/azul.h"));
+    let committed = include_str!("../include/azul.h");
+    assert!(
+        generated == committed,
+        "include/azul.h is out of date, copy it from {}/azul.h",
+        env!("OUT_DIR")
+    );
+}
+
+#[test]
+fn draws_report_every_winner() {
+    let notation = |second: &str| {
+        CString::new(format!(
+            "- - - 10:-/-/-/-/-:AYRBW/WAYRB/...../...../.....:- 10:-/-/-/-/-:AYRBW/{}/...../...../.....:- 10",
+            second
+        ))
+        .unwrap()
+    };
+    unsafe {
+        let won = azul_from_notation(notation("WAYR.").as_ptr(), 0);
+        assert_eq!(azul_result(won), AzulResult::Won);
+        assert_eq!(azul_winner(won), 0);
+        azul_free(won);
+        // both players have two complete rows and ten points
+        let draw = azul_from_notation(notation("WAYRB").as_ptr(), 0);
+        assert_eq!(azul_result(draw), AzulResult::Draw);
+        assert_eq!(azul_winner(draw), -1);
+        assert_eq!(azul_winners(draw, ptr::null_mut(), 0), 2);
+        let mut winners = [9; 2];
+        assert_eq!(azul_winners(draw, winners.as_mut_ptr(), 2), 2);
+        assert_eq!(winners, [0, 1]);
+        azul_free(draw);
+    }
+}
@@ -0,0 +1,107 @@
+use azul::{
+    describe_move, describe_scoring,
+    engine::{self, Dealer, Greedy},
+    i18n::{Language, Text, LANGUAGES},
+    BoardEvaluation, Destination, DeterministicGameState, MoveDescription, Origin, ScoreEvent,
+    State, Tile,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+fn took(count: usize, tile: Tile, origin: Origin, destination: Destination) -> MoveDescription {
+    MoveDescription {
+        player: 0,
+        origin,
+        tile,
+        count,
+        destination,
+    }
+}
+
+#[test]
+fn languages_parse_from_codes_and_names() {
+    for language in LANGUAGES {
+        assert_eq!(language.to_string().parse(), Ok(language));
+    }
+    assert_eq!("Svenska".parse(), Ok(Language::Swedish));
+    assert_eq!("german".parse(), Ok(Language::German));
+    assert!("fr".parse::<Language>().is_err());
+    assert_eq!(Language::default(), Language::English);
+}
+
+#[test]
+fn moves_are_described_in_every_language() {
+    let one = took(1, Tile::RED, Origin::Factory(0), Destination::Row(1));
+    let two = took(2, Tile::RED, Origin::Center, Destination::Floor);
+    let cases = [
+        (Language::English, "took 1 red from factory 1 to row 2"),
+        (Language::Swedish, "tog 1 röd från fabrik 1 till rad 2"),
+        (
+            Language::German,
+            "nahm 1 rote Fliese aus Fabrik 1 in Reihe 2",
+        ),
+    ];
+    for (language, expected) in cases {
+        assert_eq!(language.describe(&one), expected);
+    }
+    let cases = [
+        (Language::English, "took 2 red from the center to the floor"),
+        (Language::Swedish, "tog 2 röda från mitten till golvet"),
+        (
+            Language::German,
+            "nahm 2 rote Fliesen aus der Mitte in die Bodenreihe",
+        ),
+    ];
+    for (language, expected) in cases {
+        assert_eq!(language.describe(&two), expected);
+    }
+    assert_eq!(one.to_string(), Language::English.describe(&one));
+}
+
+#[test]
+fn scoring_is_explained_in_every_language() {
+    let bonus = ScoreEvent::ColorBonus { tile: Tile::YELLOW };
+    assert_eq!(Language::English.explain(&bonus), "all yellow tiles: +10");
+    assert_eq!(Language::Swedish.explain(&bonus), "alla gula plattor: +10");
+    assert_eq!(Language::German.explain(&bonus), "alle gelben Fliesen: +10");
+    let floor = ScoreEvent::Floor { penalty: 3 };
+    assert_eq!(Language::Swedish.explain(&floor), "golv: -3");
+    assert_eq!(
+        Language::German.format(Text::Points, &[&"Ada", &12]),
+        "Ada: 12 Punkte"
+    );
+}
+
+#[test]
+fn every_move_and_event_of_a_game_is_translated() {
+    let mut rng = StdRng::seed_from_u64(6);
+    let mut dealer = Dealer::new(7);
+    let mut state = State::new(2);
+    dealer.deal(&mut state);
+    let mut engine = Greedy {
+        evaluation: BoardEvaluation::default(),
+    };
+    while !state.result().is_over() {
+        let next = engine::play(&mut engine, &state, &mut rng, &mut dealer);
+        let description = describe_move(&state, &next).unwrap();
+        let events = describe_scoring(&state, &next).map_or(Vec::new(), |(_, events)| events);
+        let events: Vec<_> = events.iter().flatten().collect();
+        assert_eq!(
+            Language::English.describe(&description),
+            description.to_string()
+        );
+        for event in &events {
+            assert_eq!(Language::English.explain(event), event.to_string());
+        }
+        for language in &LANGUAGES[1..] {
+            let text = language.describe(&description);
+            assert!(!text.contains("{}"), "{}", text);
+            assert_ne!(text, description.to_string());
+            for event in &events {
+                let text = language.explain(event);
+                assert!(!text.contains("{}"), "{}", text);
+                assert_ne!(text, event.to_string());
+            }
+        }
+        state = next;
+    }
+}
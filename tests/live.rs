@@ -0,0 +1,113 @@
+#![cfg(feature = "live")]
+
+use azul::live::{serve_live_on, spectate};
+use serde_json::{json, Value};
+use std::{
+    net::{SocketAddr, TcpListener, TcpStream},
+    thread,
+};
+use tungstenite::{stream::MaybeTlsStream, Message, WebSocket};
+
+type Client = WebSocket<MaybeTlsStream<TcpStream>>;
+
+// a live server on a free port
+fn server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || serve_live_on(listener, 1));
+    address
+}
+
+fn connect(address: SocketAddr) -> Client {
+    tungstenite::connect(format!("ws://{}", address)).unwrap().0
+}
+
+fn send(client: &mut Client, message: Value) {
+    client.send(Message::Text(message.to_string())).unwrap();
+}
+
+fn receive(client: &mut Client) -> Value {
+    loop {
+        if let Message::Text(text) = client.read().unwrap() {
+            return serde_json::from_str(&text).unwrap();
+        }
+    }
+}
+
+fn join(client: &mut Client, game: &str) -> Value {
+    send(client, json!({ "type": "join", "game": game }));
+    receive(client)
+}
+
+fn play(client: &mut Client, m: &Value) {
+    send(client, json!({ "type": "move", "move": m }));
+}
+
+#[test]
+fn two_players_take_turns() {
+    let address = server();
+    let mut first = connect(address);
+    let mut second = connect(address);
+    let joined = join(&mut first, "friday");
+    assert_eq!(
+        joined,
+        json!({ "type": "joined", "game": "friday", "seat": 0 })
+    );
+    play(&mut first, &json!("F1 B row1"));
+    let waiting = receive(&mut first);
+    assert_eq!(waiting["type"], "error");
+    assert_eq!(waiting["message"], "waiting for the other player");
+    assert_eq!(join(&mut second, "friday")["seat"], 1);
+    // both players are sent the dealt game
+    let state = receive(&mut first);
+    assert_eq!(state, receive(&mut second));
+    assert_eq!(state["type"], "state");
+    assert_eq!(state["current_player"], 0);
+    assert!(state["last_move"].is_null());
+    let m = &state["legal_moves"][0];
+    play(&mut second, m);
+    assert_eq!(receive(&mut second)["message"], "it's not your turn");
+    play(&mut first, &json!("nonsense"));
+    assert_eq!(receive(&mut first)["type"], "error");
+    play(&mut first, m);
+    let state = receive(&mut first);
+    assert_eq!(state, receive(&mut second));
+    assert_eq!(state["current_player"], 1);
+    assert_eq!(&state["last_move"], m);
+    // the same move again is no longer legal
+    play(&mut second, m);
+    assert_eq!(receive(&mut second)["type"], "error");
+}
+
+#[test]
+fn full_games_are_watched_and_seats_freed() {
+    let address = server();
+    let mut first = connect(address);
+    let mut second = connect(address);
+    join(&mut first, "friday");
+    join(&mut second, "friday");
+    let state = receive(&mut first);
+    receive(&mut second);
+    let mut third = connect(address);
+    let full = join(&mut third, "friday");
+    assert_eq!(full["message"], "game friday is full");
+    play(&mut third, &state["legal_moves"][0]);
+    assert_eq!(receive(&mut third)["message"], "join a game first");
+    send(&mut third, json!({ "type": "dance" }));
+    assert_eq!(receive(&mut third)["type"], "error");
+    // spectators see the game as it is
+    let mut messages = Vec::new();
+    let url = format!("ws://{}", address);
+    spectate(&url, "friday", |message| {
+        messages.push(message.clone());
+        messages.len() < 2
+    })
+    .unwrap();
+    assert_eq!(messages[0], json!({ "type": "watching", "game": "friday" }));
+    assert_eq!(messages[1], state);
+    // a player who leaves can be replaced
+    second.close(None).unwrap();
+    assert_eq!(receive(&mut first), json!({ "type": "left", "seat": 1 }));
+    assert_eq!(join(&mut third, "friday")["seat"], 1);
+    assert_eq!(receive(&mut third), state);
+}
@@ -0,0 +1,139 @@
+use azul::{
+    engine::{self, Dealer, Greedy, Random, Spec},
+    observer::{Csv, Observer},
+    BoardEvaluation, DeterministicGameState, MoveDescription, ScoreEvent, State,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+// counts the events, and adds up the points scored
+#[derive(Default)]
+struct Counter {
+    moves: usize,
+    rounds: usize,
+    deals: usize,
+    games: usize,
+    points: Vec<i32>,
+}
+
+impl Observer for Counter {
+    fn on_move(&mut self, before: &State, description: &MoveDescription, after: &State) {
+        assert_eq!(before.moves, self.moves);
+        assert_eq!(after.moves, self.moves + 1);
+        assert_eq!(description.player, self.moves % 2);
+        self.moves += 1;
+    }
+    fn on_round_end(&mut self, _state: &State, scoring: &[Vec<ScoreEvent>]) {
+        self.rounds += 1;
+        self.points.resize(scoring.len(), 0);
+        for (points, events) in self.points.iter_mut().zip(scoring) {
+            *points += events.iter().map(ScoreEvent::points).sum::<i32>();
+        }
+    }
+    fn on_deal(&mut self, state: &State) {
+        assert!(!state.needs_deal());
+        self.deals += 1;
+    }
+    fn on_game_over(&mut self, _state: &State) {
+        self.games += 1;
+    }
+}
+
+// greedy versus random, observed
+fn game(observer: &mut impl Observer) -> State {
+    let mut rng = StdRng::seed_from_u64(2);
+    let mut dealer = Dealer::new(3);
+    let mut state = State::new(2);
+    dealer.deal(&mut state);
+    let mut greedy = Greedy {
+        evaluation: BoardEvaluation::default(),
+    };
+    while !state.result().is_over() {
+        state = match state.moves % 2 {
+            0 => engine::play_observed(&mut greedy, &state, &mut rng, &mut dealer, observer),
+            _ => engine::play_observed(&mut Random, &state, &mut rng, &mut dealer, observer),
+        };
+    }
+    state
+}
+
+#[test]
+fn observers_follow_the_whole_game() {
+    let mut counter = Counter::default();
+    let state = game(&mut counter);
+    assert_eq!(counter.moves, state.moves);
+    assert_eq!(counter.games, 1);
+    // every round but the last is followed by a deal
+    assert_eq!(counter.deals + 1, counter.rounds);
+    let points: Vec<_> = state.players.iter().map(|p| p.points as i32).collect();
+    assert_eq!(counter.points, points);
+}
+
+#[test]
+fn played_games_call_back_after_every_move() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let mut dealer = Dealer::new(3);
+    let mut state = State::new(2);
+    dealer.deal(&mut state);
+    let mut engines = [Spec::Greedy.build(), Spec::Random.build()];
+    let mut counter = Counter::default();
+    let mut moves = 0;
+    let state = engine::play_game(
+        &mut engines,
+        state,
+        &mut rng,
+        &mut dealer,
+        &mut counter,
+        |before, after, _| {
+            assert_eq!(before.moves + 1, after.moves);
+            // the next round is dealt before the callback
+            assert!(!after.needs_deal());
+            moves += 1;
+        },
+    )
+    .unwrap();
+    assert!(state.result().is_over());
+    assert_eq!((moves, counter.moves), (state.moves, state.moves));
+    assert_eq!(counter.games, 1);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn json_lines_have_a_line_per_event() {
+    let mut counter = Counter::default();
+    let mut lines = azul::observer::JsonLines::new(Vec::new());
+    game(&mut counter);
+    game(&mut lines);
+    let out = String::from_utf8(lines.finish().unwrap()).unwrap();
+    let events: Vec<serde_json::Value> = out
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let count = |event: &str| events.iter().filter(|e| e["event"] == event).count();
+    assert_eq!(count("move"), counter.moves);
+    assert_eq!(count("round_end"), counter.rounds);
+    assert_eq!(count("deal"), counter.deals);
+    assert_eq!(count("game_over"), 1);
+    assert_eq!(events.last().unwrap()["event"], "game_over");
+}
+
+#[test]
+fn csv_has_a_row_per_player_and_round() {
+    let mut counter = Counter::default();
+    let mut csv = Csv::new(Vec::new());
+    game(&mut counter);
+    let state = game(&mut csv);
+    let out = String::from_utf8(csv.finish().unwrap()).unwrap();
+    let mut lines = out.lines();
+    assert_eq!(
+        lines.next(),
+        Some("game,round,player,points,penalty,bonus,tiles,total")
+    );
+    let rows: Vec<Vec<usize>> = lines
+        .map(|line| line.split(',').map(|cell| cell.parse().unwrap()).collect())
+        .collect();
+    assert_eq!(rows.len(), 2 * counter.rounds);
+    for (player, row) in rows[rows.len() - 2..].iter().enumerate() {
+        assert_eq!(row[..3], [1, counter.rounds, player + 1]);
+        assert_eq!(row[7], state.players[player].points);
+    }
+}
@@ -0,0 +1,108 @@
+#![cfg(feature = "onnx")]
+
+use azul::{
+    features::{BOARD, BOARDS, FEATURES},
+    onnx::OnnxEvaluation,
+    random_move, DeterministicGameState, Evaluation, State,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use std::{env, fs};
+
+// protocol buffer encoding, enough for a small ONNX model
+fn varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn number(out: &mut Vec<u8>, field: u64, value: u64) {
+    varint(out, field << 3);
+    varint(out, value);
+}
+
+fn bytes(out: &mut Vec<u8>, field: u64, value: &[u8]) {
+    varint(out, field << 3 | 2);
+    varint(out, value.len() as u64);
+    out.extend(value);
+}
+
+// float tensor value info with the given shape
+fn value_info(name: &str, shape: &[u64]) -> Vec<u8> {
+    let mut dimensions = Vec::new();
+    for dimension in shape {
+        let mut value = Vec::new();
+        number(&mut value, 1, *dimension);
+        bytes(&mut dimensions, 1, &value);
+    }
+    let mut tensor = Vec::new();
+    number(&mut tensor, 1, 1);
+    bytes(&mut tensor, 2, &dimensions);
+    let mut kind = Vec::new();
+    bytes(&mut kind, 1, &tensor);
+    let mut info = Vec::new();
+    bytes(&mut info, 1, name.as_bytes());
+    bytes(&mut info, 2, &kind);
+    info
+}
+
+// a model multiplying the features with the weights
+fn linear_model(weights: &[f32]) -> Vec<u8> {
+    let mut node = Vec::new();
+    bytes(&mut node, 1, b"x");
+    bytes(&mut node, 1, b"w");
+    bytes(&mut node, 2, b"y");
+    bytes(&mut node, 4, b"MatMul");
+    let mut initializer = Vec::new();
+    number(&mut initializer, 1, weights.len() as u64);
+    number(&mut initializer, 1, 1);
+    number(&mut initializer, 2, 1);
+    bytes(&mut initializer, 8, b"w");
+    let raw: Vec<u8> = weights.iter().flat_map(|w| w.to_le_bytes()).collect();
+    bytes(&mut initializer, 9, &raw);
+    let mut graph = Vec::new();
+    bytes(&mut graph, 1, &node);
+    bytes(&mut graph, 2, b"linear");
+    bytes(&mut graph, 5, &initializer);
+    bytes(&mut graph, 11, &value_info("x", &[1, weights.len() as u64]));
+    bytes(&mut graph, 12, &value_info("y", &[1, 1]));
+    let mut opset = Vec::new();
+    number(&mut opset, 2, 13);
+    let mut model = Vec::new();
+    number(&mut model, 1, 7);
+    bytes(&mut model, 7, &graph);
+    bytes(&mut model, 8, &opset);
+    model
+}
+
+#[test]
+fn model_evaluates_point_difference() {
+    // the points of the player and of the next one, scaled back to points
+    let mut weights = vec![0.0; FEATURES];
+    let points = BOARD - 1;
+    weights[BOARDS + points] = 100.0;
+    weights[BOARDS + BOARD + points] = -100.0;
+    let path = env::temp_dir().join(format!("azul-points-{}.onnx", std::process::id()));
+    fs::write(&path, linear_model(&weights)).unwrap();
+    let evaluation = OnnxEvaluation::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let mut rng = StdRng::seed_from_u64(2);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    while !state.result().is_over() {
+        state = random_move(&state, &mut rng);
+    }
+    let difference = state.players[0].points as i32 - state.players[1].points as i32;
+    assert_eq!(evaluation.evaulate(&state, 0), 100 * difference);
+    assert_eq!(evaluation.evaulate(&state, 1), -100 * difference);
+}
+
+#[test]
+fn bad_models_are_errors() {
+    let path = env::temp_dir().join(format!("azul-bad-{}.onnx", std::process::id()));
+    fs::write(&path, linear_model(&[1.0; 3])).unwrap();
+    assert!(OnnxEvaluation::load(&path).is_err());
+    fs::remove_file(&path).unwrap();
+}
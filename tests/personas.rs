@@ -0,0 +1,77 @@
+#![cfg(feature = "serde")]
+
+use azul::{
+    engine::{Engine, Spec},
+    personas::{self, Configured},
+    State, Weights,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+#[test]
+fn personas_configure_every_seat() {
+    let players = personas::parse(
+        r#"
+        [[player]]
+        name = "Ada"
+        engine = "minmax"
+        depth = 2
+
+        [player.weights]
+        floor = 1.5
+
+        [[player]]
+        engine = "fish:3"
+        movetime = 20
+
+        [[player]]
+        engine = "mcts:50"
+
+        [[player]]
+        name = "Cy"
+        engine = "human"
+        "#,
+    )
+    .unwrap();
+    let names: Vec<_> = players.iter().map(|persona| persona.name()).collect();
+    assert_eq!(names, ["Ada", "fish:3", "mcts:50", "Cy"]);
+    let engines: Vec<_> = players
+        .iter()
+        .map(|persona| persona.engine().unwrap())
+        .collect();
+    assert!(engines[3].is_none());
+    assert_eq!(engines[2], Some(Configured::Spec(Spec::Mcts(50))));
+    let Some(Configured::Search {
+        fish,
+        weights,
+        options,
+    }) = &engines[0]
+    else {
+        panic!("minmax with weights searches by itself");
+    };
+    assert!(!fish);
+    assert_eq!(options.depth, 2);
+    let weights = weights.as_ref().unwrap();
+    assert_eq!(weights.floor, 1.5);
+    assert_eq!(weights.points, Weights::default().points);
+    assert_eq!(engines[1].as_ref().unwrap().to_string(), "fish 20 ms");
+    // every engine plays
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    for engine in engines.iter().flatten() {
+        let m = engine.build().choose(&state, &mut rng);
+        assert!(state.legal_moves().contains(&m));
+    }
+}
+
+#[test]
+fn personas_that_make_no_sense_are_rejected() {
+    let parse = |text: &str| personas::parse(text).unwrap_err();
+    assert!(parse("[[player]]\nengine = \"random\"\ndepth = 3").contains("player 1"));
+    assert!(
+        parse("[[player]]\nengine = \"fish\"\n[player.weights]\nfloor = 2.0").contains("weights")
+    );
+    assert!(parse("[[player]]\nengine = \"human\"\nmovetime = 5").contains("human"));
+    assert!(parse("[[player]]\nengine = \"minmax\"\ncolour = \"red\"").contains("colour"));
+    assert!(parse("[[player]]\nengine = \"chess\"").contains("unknown engine"));
+}
@@ -0,0 +1,61 @@
+#![cfg(feature = "serde")]
+
+use azul::{
+    engine::{Opponent, Spec},
+    i18n::Language,
+    openings::Book,
+    play::Game,
+    BoardEvaluation, DeterministicGameState, History, RenderOptions, SearchOptions, State,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use std::io::Cursor;
+
+// the human in the first seat against a random engine
+fn game() -> Game<BoardEvaluation> {
+    let engine = Some(Spec::Random.build());
+    let evaluation = BoardEvaluation::default();
+    Game {
+        history: History::new(State::new(2)),
+        human: 0,
+        names: vec!["You".to_string(), "Engine".to_string()],
+        clock: None,
+        opponent: Opponent::new(engine, evaluation, SearchOptions::depth(1), false),
+        book: Book::default(),
+        render: RenderOptions::default(),
+        changes: false,
+        verbose: false,
+        language: Language::English,
+    }
+}
+
+#[test]
+fn humans_play_moves_typed_by_number() {
+    let mut game = game();
+    // the first legal move every time, and a line that is no move at all
+    let mut input = Cursor::new("nonsense\n".to_string() + &"1\n".repeat(200));
+    let mut moves = 0;
+    game.play(
+        StdRng::seed_from_u64(1),
+        &mut input,
+        |before, after, _, _| {
+            assert_eq!(before.moves + 1, after.moves);
+            moves += 1;
+        },
+    );
+    let state = game.history.current();
+    assert!(state.result().is_over());
+    assert_eq!(moves, state.moves);
+}
+
+#[test]
+fn undo_takes_back_the_last_move_and_the_reply() {
+    let mut game = game();
+    let mut input = Cursor::new("1\nundo\n");
+    game.play(StdRng::seed_from_u64(1), &mut input, |_, _, _, _| {});
+    // the game was dealt, and is back at the deal when the input ends
+    assert_eq!(game.history.current().moves, 0);
+    assert!(!game.history.current().needs_deal());
+    let mut input = Cursor::new("redo\n");
+    game.play(StdRng::seed_from_u64(1), &mut input, |_, _, _, _| {});
+    assert_eq!(game.history.current().moves, 2);
+}
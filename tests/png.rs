@@ -0,0 +1,51 @@
+#![cfg(feature = "png")]
+
+use azul::{encode_png, render_png, State};
+use rand::{rngs::StdRng, SeedableRng};
+use std::{env, fs};
+
+// width, height and RGB pixels of an image
+fn decode(image: &[u8]) -> (usize, usize, Vec<u8>) {
+    let mut reader = png::Decoder::new(image).read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    assert_eq!(info.color_type, png::ColorType::Rgb);
+    (info.width as usize, info.height as usize, pixels)
+}
+
+#[test]
+fn boards_are_drawn_side_by_side() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut widths = Vec::new();
+    for players in 2..=4 {
+        let mut state = State::new(players);
+        state.deal(&mut rng);
+        let (width, height, pixels) = decode(&encode_png(&state));
+        assert_eq!(pixels.len(), 3 * width * height);
+        widths.push(width);
+    }
+    assert!(widths.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+#[test]
+fn tiles_have_their_colors() {
+    let mut state = State::new(2);
+    state.deal(&mut StdRng::seed_from_u64(2));
+    let (width, _, pixels) = decode(&encode_png(&state));
+    // the first tile of the first factory, in the order of the notation
+    let first = state.to_notation().chars().find(char::is_ascii_alphabetic);
+    let (x, y) = (30, 30);
+    let pixel = &pixels[3 * (y * width + x)..3 * (y * width + x) + 3];
+    let expected: &[u8] = match first.unwrap() {
+        'B' => &[0x33, 0x33, 0x33],
+        'W' => &[0xee, 0xee, 0xee],
+        'A' => &[0x2a, 0x6e, 0xbb],
+        'Y' => &[0xf2, 0xc1, 0x2e],
+        _ => &[0xdd, 0x33, 0x33],
+    };
+    assert_eq!(pixel, expected);
+    let path = env::temp_dir().join("azul-board.png");
+    render_png(&state, &path).unwrap();
+    assert_eq!(fs::read(&path).unwrap(), encode_png(&state));
+    fs::remove_file(path).unwrap();
+}
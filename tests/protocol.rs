@@ -0,0 +1,102 @@
+use azul::{
+    protocol::{run, Protocol},
+    DeterministicGameState, Move, State,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+fn protocol() -> Protocol<StdRng> {
+    Protocol::new(StdRng::seed_from_u64(1))
+}
+
+// the current position of the engine, in notation
+fn shown(protocol: &mut Protocol<StdRng>) -> String {
+    let reply = protocol.handle("show").unwrap();
+    reply[0].strip_prefix("position ").unwrap().to_string()
+}
+
+fn shown_state(protocol: &mut Protocol<StdRng>) -> State {
+    State::from_notation(&shown(protocol)).unwrap()
+}
+
+fn is_info(reply: Option<Vec<String>>) -> bool {
+    matches!(&reply.unwrap()[..], [line] if line.starts_with("info string "))
+}
+
+#[test]
+fn the_engine_identifies_itself() {
+    let mut protocol = protocol();
+    let reply = protocol.handle("azuluci").unwrap();
+    assert_eq!(reply.last().unwrap(), "azuluciok");
+    assert!(reply[0].starts_with("id name "));
+    assert_eq!(protocol.handle("isready").unwrap(), ["readyok"]);
+    assert_eq!(protocol.handle("").unwrap(), Vec::<String>::new());
+    assert!(is_info(protocol.handle("uci")));
+    assert_eq!(protocol.handle("quit"), None);
+}
+
+#[test]
+fn positions_are_set_up_and_played_on() {
+    let mut protocol = protocol();
+    assert_eq!(shown_state(&mut protocol).players.len(), 2);
+    assert!(protocol.handle("newgame 3").unwrap().is_empty());
+    let state = shown_state(&mut protocol);
+    assert_eq!(state.players.len(), 3);
+    assert!(!state.needs_deal());
+    assert!(is_info(protocol.handle("newgame 5")));
+    assert!(is_info(protocol.handle("newgame many")));
+    // bad notation leaves the position as it was
+    assert!(is_info(protocol.handle("position - - -")));
+    assert_eq!(shown(&mut protocol), state.to_notation());
+    let notation = State::new(2).to_notation();
+    assert!(protocol
+        .handle(&format!("position {}", notation))
+        .unwrap()
+        .is_empty());
+    assert_eq!(shown(&mut protocol), notation);
+}
+
+#[test]
+fn moves_are_checked() {
+    let mut protocol = protocol();
+    let state = shown_state(&mut protocol);
+    let m = state.legal_moves()[0];
+    assert!(protocol.handle(&format!("move {}", m)).unwrap().is_empty());
+    assert_eq!(shown(&mut protocol), state.play(&m).to_notation());
+    // the factory was taken
+    assert!(is_info(protocol.handle(&format!("move {}", m))));
+    assert!(is_info(protocol.handle("move nonsense")));
+    assert_eq!(shown(&mut protocol), state.play(&m).to_notation());
+}
+
+#[test]
+fn go_searches_for_the_best_move() {
+    let mut protocol = protocol();
+    let state = shown_state(&mut protocol);
+    let reply = protocol.handle("go depth 1").unwrap();
+    assert!(reply[0].starts_with("info depth 1 "), "{}", reply[0]);
+    let best = Move::parse(reply[1].strip_prefix("bestmove ").unwrap()).unwrap();
+    assert!(state.legal_moves().contains(&best));
+    // the search doesn't move
+    assert_eq!(shown(&mut protocol), state.to_notation());
+    let reply = protocol.handle("go movetime 10").unwrap();
+    assert!(reply[1].starts_with("bestmove "));
+    assert!(is_info(protocol.handle("go depth deep")));
+    assert!(is_info(protocol.handle("go infinite")));
+}
+
+#[test]
+fn finished_games_have_no_best_move() {
+    let mut protocol = protocol();
+    let position = "position - - - 10:-/-/-/-/-:AYRBW/WAYRB/...../...../.....:- 10:-/-/-/-/-:AYRBW/WAYR./...../...../.....:- 10";
+    assert!(protocol.handle(position).unwrap().is_empty());
+    assert_eq!(protocol.handle("go depth 2").unwrap(), ["bestmove none"]);
+}
+
+#[test]
+fn run_answers_every_line_until_quit() {
+    let input = "isready\nnewgame 9\nquit\nisready\n".as_bytes();
+    let mut output = Vec::new();
+    run(StdRng::seed_from_u64(1), input, &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(output, "readyok\ninfo string bad number of players '9'\n");
+}
@@ -0,0 +1,170 @@
+use azul::{render_changes, render_state, Move, RenderOptions, State, Theme, Tile};
+use rand::{rngs::StdRng, SeedableRng};
+
+#[test]
+fn plain_rendering_is_ascii() {
+    let mut state = State::new(2);
+    state.deal(&mut StdRng::seed_from_u64(1));
+    let names = ["one", "two"];
+    let plain = render_state(&state, &names, &RenderOptions::plain());
+    assert!(plain.starts_with("round 1, picking\n"));
+    assert!(plain.is_ascii());
+    assert!(!plain.contains('\x1b'));
+    let colored = render_state(&state, &names, &RenderOptions::default());
+    assert!(colored.contains('\x1b'));
+}
+
+#[test]
+fn glyphs_draw_blocks_and_a_boxed_wall() {
+    let mut state = State::new(2);
+    state.deal(&mut StdRng::seed_from_u64(1));
+    let options = RenderOptions {
+        glyphs: true,
+        ..RenderOptions::default()
+    };
+    let rendered = render_state(&state, &["one", "two"], &options);
+    assert!(rendered.contains('█'));
+    assert_eq!(rendered.matches('┌').count(), 2);
+    assert_eq!(rendered.matches('┘').count(), 2);
+    // the blocks need colors to tell them apart
+    let options = RenderOptions {
+        color: false,
+        ..options
+    };
+    assert!(!render_state(&state, &["one", "two"], &options).contains('█'));
+}
+
+#[test]
+fn highlight_marks_the_rows_that_take_the_color() {
+    // one red tile on the wall, and two blue tiles in the second row
+    let state = State::from_notation(
+        "2R2A/4W - - 0:-/2A/-/-/-:..R../...../...../...../.....:- 0:-/-/-/-/-:...../...../...../...../.....:- 0",
+    )
+    .unwrap();
+    let options = RenderOptions {
+        highlight: Some(Tile::RED),
+        ..RenderOptions::plain()
+    };
+    let rendered = render_state(&state, &["one", "two"], &options);
+    let marks: Vec<_> = rendered.lines().filter(|line| line.contains('<')).collect();
+    // not the first row, with red on the wall, nor the second, with blue
+    assert_eq!(marks.len(), 3);
+    assert!(marks[0].ends_with("< room for 3"));
+    // the other player's board is left alone
+    assert!(rendered
+        .split("two")
+        .nth(1)
+        .is_some_and(|board| !board.contains('<')));
+    let options = RenderOptions {
+        highlight: Some(Tile::YELLOW),
+        ..options
+    };
+    assert!(render_state(&state, &["one", "two"], &options).contains("no yellow tiles to take"));
+}
+
+#[test]
+fn themes_change_how_tiles_are_told_apart() {
+    let mut state = State::new(2);
+    state.deal(&mut StdRng::seed_from_u64(1));
+    let names = ["one", "two"];
+    let render = |theme, unicode| {
+        let options = RenderOptions {
+            theme,
+            unicode,
+            ..RenderOptions::default()
+        };
+        render_state(&state, &names, &options)
+    };
+    let classic = render(Theme::Classic, true);
+    assert!(classic.contains("\x1b[90m"));
+    // the colorblind theme shows black as gray on dark terminals too
+    let colorblind = render(Theme::Colorblind, true);
+    assert!(colorblind.contains("\x1b[38;5;"));
+    assert!(!colorblind.contains("\x1b[90m"));
+    // only the empty wall cells are dimmed
+    let letters = render(Theme::Letters, true);
+    let undimmed = letters.replace("\x1b[2m", "").replace("\x1b[0m", "");
+    assert!(!undimmed.contains('\x1b'));
+    // filled symbols for tiles and outlines for the empty wall
+    let symbols = render(Theme::Symbols, true);
+    assert!(symbols.contains('●') && symbols.contains('★'));
+    assert!(symbols.contains('○') && symbols.contains('☆'));
+    assert!(!render(Theme::Symbols, false).contains('●'));
+    // the blocks of glyphs would hide the symbols
+    let options = RenderOptions {
+        glyphs: true,
+        theme: Theme::Symbols,
+        ..RenderOptions::default()
+    };
+    assert!(!render_state(&state, &names, &options).contains('█'));
+}
+
+#[test]
+fn themes_parse_from_their_names() {
+    for theme in [
+        Theme::Classic,
+        Theme::Colorblind,
+        Theme::Letters,
+        Theme::Symbols,
+    ] {
+        assert_eq!(theme.to_string().parse(), Ok(theme));
+    }
+    assert!("neon".parse::<Theme>().is_err());
+}
+
+#[test]
+fn changes_mark_what_the_last_move_did() {
+    let before = State::from_notation(
+        "2R2A/4W - - 0:-/-/-/-/-:...../...../...../...../.....:- 0:-/-/-/-/-:...../...../...../...../.....:- 0",
+    )
+    .unwrap();
+    let after = before.apply(&Move::parse("F1 R -> row2").unwrap()).unwrap();
+    let names = ["one", "two"];
+    // without colors only changes of points would show
+    let plain = RenderOptions::plain();
+    assert_eq!(
+        render_changes(&before, &after, &names, &[], &plain),
+        render_state(&after, &names, &plain)
+    );
+    let options = RenderOptions {
+        unicode: false,
+        ..RenderOptions::default()
+    };
+    let rendered = render_changes(&before, &after, &names, &[], &options);
+    let marked: Vec<_> = rendered.split("\x1b[7m").skip(1).collect();
+    // the factory, the azul tiles moved to the center, the player and the
+    // two red tiles in the row
+    assert_eq!(marked.len(), 6);
+    assert!(marked[0].starts_with("1\x1b[0m:"));
+    assert!(marked[1].starts_with("\x1b[34mA"));
+    assert!(marked[3].starts_with("one\x1b[0m"));
+    assert!(marked[4].starts_with("\x1b[31mR"));
+    assert!(!render_state(&after, &names, &options).contains("\x1b[7m"));
+}
+
+#[test]
+fn changes_show_the_points_scored_at_the_end_of_a_round() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    let names = ["one", "two"];
+    loop {
+        let m = state.legal_moves()[0];
+        let mut next = state.apply(&m).unwrap();
+        if next.needs_deal() {
+            next.deal(&mut rng);
+        }
+        if next.round > state.round {
+            let rendered = render_changes(&state, &next, &names, &[], &RenderOptions::plain());
+            for (before, after) in state.players.iter().zip(&next.players) {
+                let change = after.points as i64 - before.points as i64;
+                assert_eq!(
+                    rendered.contains(&format!("{} points ({:+})", after.points, change)),
+                    change != 0
+                );
+            }
+            break;
+        }
+        state = next;
+    }
+}
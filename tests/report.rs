@@ -0,0 +1,25 @@
+#![cfg(feature = "serde")]
+
+use azul::{random_move, report, DeterministicGameState, GameRecord, State};
+use rand::{rngs::StdRng, SeedableRng};
+
+#[test]
+fn report_has_every_move() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    let mut record = GameRecord::new(Some(7), &state);
+    while !state.result().is_over() {
+        let next = random_move(&state, &mut rng);
+        record.push(&state, &next);
+        state = next;
+    }
+    let html = report::render(&record, 1).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<svg"));
+    assert_eq!(html.matches("<h2>").count(), record.moves.len() + 1);
+    assert_eq!(
+        html.matches("class=\"evaluation\"").count(),
+        record.moves.len()
+    );
+}
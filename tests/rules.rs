@@ -0,0 +1,1082 @@
+use azul::{
+    board_evaluation::is_endgame,
+    clock::Clock,
+    describe_scoring,
+    engine::{self, Dealer, Engine, Greedy, Opponent, Random, Registered, Spec},
+    minmax::minmax,
+    perft::{perft, Chance},
+    ponder::{expected_position, Ponderer},
+    random_move, search_multipv, search_with_info, search_with_options, AzulError, BoardEvaluation,
+    Destination, DeterministicGameState, Evaluation, Fish, GameResult, History, Mcts, Move,
+    MoveDescription, MoveError, Origin, Phase, ScoreEvent, SearchOptions, State,
+    StochasticGameState, StopToken, Tile, WallVariant, Weights,
+};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use std::time::{Duration, Instant};
+
+#[test]
+fn tiles_are_conserved() {
+    for seed in 0..20 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut state = State::new(2);
+        state.deal(&mut rng);
+        for _ in 0..100 {
+            if state.result().is_over() {
+                break;
+            }
+            state = random_move(&state, &mut rng);
+            state.self_check();
+        }
+    }
+}
+
+#[test]
+fn score_events_explain_the_points() {
+    for seed in 0..10 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut state = State::new(2);
+        state.deal(&mut rng);
+        let mut rounds = 0;
+        while !state.result().is_over() {
+            let moves = state.legal_moves();
+            let m = moves.choose(&mut rng).unwrap();
+            let (mut next, events) = state.apply_scoring(m).unwrap();
+            assert!(next == state.apply(m).unwrap());
+            if !events.is_empty() {
+                rounds += 1;
+            }
+            for (player, events) in events.iter().enumerate() {
+                let points: i32 = events.iter().map(ScoreEvent::points).sum();
+                let before = state.players[player].points as i32;
+                assert_eq!(next.players[player].points as i32 - before, points);
+            }
+            if next.needs_deal() {
+                next.deal(&mut rng);
+            }
+            state = next;
+        }
+        assert!(rounds >= 5);
+    }
+}
+
+#[test]
+fn moves_update_the_zobrist_hash_of_the_position() {
+    for seed in 0..20 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut state = State::new(2);
+        state.deal(&mut rng);
+        while !state.result().is_over() {
+            let m = *state.legal_moves().choose(&mut rng).unwrap();
+            state = state.apply(&m).unwrap();
+            // the hash updated by the move is the hash of the new position
+            assert_eq!(state.zobrist(), state.compute_zobrist());
+            if state.needs_deal() {
+                state.deal(&mut rng);
+                assert_eq!(state.zobrist(), state.compute_zobrist());
+            }
+        }
+    }
+}
+
+#[test]
+fn undo_takes_back_the_replies_to_the_last_move_too() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut state = State::new(3);
+    state.deal(&mut rng);
+    let mut history = History::new(state.clone());
+    for _ in 0..5 {
+        state = random_move(&state, &mut rng);
+        history.push(state.clone());
+    }
+    // back to the moves of the second player, and at last the first position
+    let mut undone = Vec::new();
+    while history.undo_to(1) {
+        undone.push(history.current().moves);
+    }
+    assert_eq!(undone, [4, 1, 0]);
+    let mut redone = Vec::new();
+    while history.redo_to(1) {
+        redone.push(history.current().moves);
+    }
+    assert_eq!(redone, [1, 4, 5]);
+}
+
+#[test]
+fn undo_restores_the_position_before_the_move() {
+    for seed in 0..10 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut state = State::new(2 + seed as usize % 3);
+        state.deal(&mut rng);
+        let mut rounds = 0;
+        while !state.result().is_over() {
+            for m in state.legal_moves() {
+                let before = state.clone();
+                let next = state.apply(&m).unwrap();
+                let undo = state.apply_in_place(&m);
+                assert!(state == next);
+                assert_eq!(state.zobrist(), next.zobrist());
+                state.undo(undo);
+                assert!(state == before);
+                assert_eq!(state.zobrist(), before.zobrist());
+            }
+            let m = *state.legal_moves().choose(&mut rng).unwrap();
+            state.apply_in_place(&m);
+            if state.is_chance() {
+                rounds += 1;
+                state.deal(&mut rng);
+            }
+        }
+        // every move from the last position of each round scored the
+        // round, and was taken back too
+        assert!(rounds > 0);
+    }
+}
+
+// plain alpha-beta over every legal move, without a transposition table,
+// move ordering, principal variation search, reductions or pruning,
+// searching past the depth like a search does
+fn alpha_beta(
+    state: &State,
+    evaluation: &BoardEvaluation,
+    player: usize,
+    depth: usize,
+    mut alpha: i32,
+    mut beta: i32,
+) -> i32 {
+    match state.result() {
+        GameResult::Ongoing => {}
+        GameResult::Won { player: winner, .. } if winner == player => return i32::MAX,
+        GameResult::Draw { players, .. } if players.contains(&player) => {
+            return evaluation.contempt()
+        }
+        _ => return i32::MIN,
+    }
+    if depth == 0 && state.is_quiet() {
+        return evaluation.evaulate(state, player);
+    }
+    assert!(!state.is_chance(), "deals are sampled at random");
+    let maximizing = state.current_player() == player;
+    let mut best = if maximizing { i32::MIN } else { i32::MAX };
+    for m in state.legal_moves() {
+        let child = state.play(&m);
+        let value = alpha_beta(
+            &child,
+            evaluation,
+            player,
+            depth.saturating_sub(1),
+            alpha,
+            beta,
+        );
+        if maximizing {
+            best = best.max(value);
+            alpha = alpha.max(best);
+        } else {
+            best = best.min(value);
+            beta = beta.min(best);
+        }
+        if alpha >= beta {
+            break;
+        }
+    }
+    best
+}
+
+#[test]
+fn searches_find_the_value_of_plain_alpha_beta() {
+    for seed in 0..6 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut state = State::new(2 + seed as usize % 3);
+        state.deal(&mut rng);
+        let mut game = vec![state.clone()];
+        // the positions each round starts from
+        let mut deals = vec![0];
+        while !state.result().is_over() {
+            let m = *state.legal_moves().choose(&mut rng).unwrap();
+            state = state.play(&m);
+            if state.is_chance() {
+                state.deal(&mut rng);
+                deals.push(game.len());
+            }
+            game.push(state.clone());
+        }
+        // the first position of a round, whose end is too far away to be
+        // searched through, and positions in the last round, whose
+        // searches end with the game instead of a deal. Near the end the
+        // values of wins and losses fall outside any window
+        let last = *deals.last().unwrap();
+        let positions = [
+            &game[deals[seed as usize % deals.len()]],
+            &game[last + (game.len() - last) / 2],
+            &game[game.len() - 2],
+        ];
+        for state in positions {
+            let player = state.current_player();
+            for depth in 1..=3 {
+                // a time budget searches one level deeper at a time, each
+                // level after the first with an aspiration window
+                for time_budget in [None, Some(Duration::from_secs(3600))] {
+                    let options = SearchOptions {
+                        depth,
+                        time_budget,
+                        late_move_reductions: false,
+                        pruning: None,
+                        ..SearchOptions::default()
+                    };
+                    let mut evaluation = BoardEvaluation::default();
+                    let mut rng = StdRng::seed_from_u64(seed);
+                    let (_, info) = search_with_options(state, &mut evaluation, &mut rng, &options);
+                    let (low, high) = (i32::MIN, i32::MAX);
+                    let expected = alpha_beta(state, &evaluation, player, depth, low, high);
+                    assert_eq!(info.score, expected, "seed {} depth {}", seed, depth);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn free_wall_games_finish() {
+    for seed in 0..10 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut state = State::new_variant(2, WallVariant::Free);
+        state.deal(&mut rng);
+        while !state.result().is_over() {
+            state = random_move(&state, &mut rng);
+            state.self_check();
+            let parsed = State::from_notation(&state.to_notation()).unwrap();
+            assert!(parsed == state, "{}", state.to_notation());
+        }
+    }
+}
+
+#[test]
+fn free_wall_moves_choose_column() {
+    // the first row of player one is one tile short of complete
+    let state = State::from_notation(
+        "RRWB - - 0:-/1R/-/-/-:...../...../...../...../.....:- 0:-/-/-/-/-:...../...../...../...../.....:- 0 free",
+    )
+    .unwrap();
+    let columns: Vec<_> = state
+        .legal_moves()
+        .into_iter()
+        .filter(|m| m.tile == Tile::RED)
+        .filter_map(|m| match m.destination {
+            Destination::Wall(1, column) => Some(column),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(columns, [0, 1, 2, 3, 4]);
+    assert!(state.apply(&Move::parse("F1 R -> row2").unwrap()).is_err());
+}
+
+#[test]
+fn three_and_four_player_games_finish() {
+    for players in [3, 4] {
+        for seed in 0..5 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut dealer = Dealer::new(seed);
+            let mut state = State::new(players);
+            dealer.deal(&mut state);
+            assert_eq!(
+                state
+                    .to_notation()
+                    .split(' ')
+                    .next()
+                    .unwrap()
+                    .split('/')
+                    .count(),
+                2 * players + 1
+            );
+            let mut engine = Greedy {
+                evaluation: BoardEvaluation::default(),
+            };
+            while !state.result().is_over() {
+                state = match state.current_player() {
+                    0 => engine::play(&mut engine, &state, &mut rng, &mut dealer),
+                    _ => random_move(&state, &mut rng),
+                };
+                state.self_check();
+            }
+            assert_eq!(state.players.len(), players);
+        }
+    }
+}
+
+#[test]
+fn deals_do_not_depend_on_the_engines() {
+    // the factories of the second round
+    fn second_deal<E: Engine>(mut engine: E) -> String {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut dealer = Dealer::new(1);
+        let mut state = State::new(2);
+        dealer.deal(&mut state);
+        loop {
+            state = engine::play(&mut engine, &state, &mut rng, &mut dealer);
+            let factories = state.to_notation().split(' ').next().unwrap().to_string();
+            if !factories.contains('-') {
+                return factories;
+            }
+        }
+    }
+    let greedy = Greedy {
+        evaluation: BoardEvaluation::default(),
+    };
+    assert_eq!(second_deal(Random), second_deal(greedy));
+}
+
+#[test]
+fn rows_between_rounds_count_by_the_tiles_left() {
+    // every other red tile is in the lid, so the red row can't be completed
+    // in the next round while the black one most likely can
+    let position = |row: &str| {
+        State::from_notation(&format!(
+            "- - 19R 0:-/{}/-/-/-:...../...../...../...../.....:- 0:-/-/-/-/-:...../...../...../...../.....:- 10",
+            row
+        ))
+        .unwrap()
+    };
+    let (red, black) = (position("1R"), position("1B"));
+    assert!(red.needs_deal() && black.needs_deal());
+    let evaluation = BoardEvaluation::default();
+    let red = evaluation.evaulate(&red, 0);
+    let black = evaluation.evaulate(&black, 0);
+    assert_eq!(red, 0);
+    assert!(black > 90, "{}", black);
+}
+
+#[test]
+fn rows_the_tiles_left_cannot_complete_count_less() {
+    // the opponent needs two more azul tiles for the third row, which are
+    // left in the first of the positions but not in the second
+    let position = |factory: &str| {
+        State::from_notation(&format!(
+            "{} - - 0:-/-/-/-/-:...../...../...../...../.....:- 0:-/-/1A/-/-:...../...../...../...../.....:- 10",
+            factory
+        ))
+        .unwrap()
+    };
+    let evaluation = BoardEvaluation::default();
+    let left = evaluation.evaulate(&position("2AYB"), 0);
+    let taken = evaluation.evaulate(&position("2YRB"), 0);
+    assert!(taken > left, "{} <= {}", taken, left);
+}
+
+#[test]
+fn endgame_weights_apply_once_the_game_may_end() {
+    let position = |wall: &str| {
+        State::from_notation(&format!(
+            "2AYB - - 0:-/1W/-/-/-:{}/...../...../...../.....:- 0:-/-/1A/-/-:...../...../...../...../.....:- 10",
+            wall
+        ))
+        .unwrap()
+    };
+    let evaluate = |evaluation: BoardEvaluation, state: &State| evaluation.evaulate(state, 0);
+    for (wall, endgame) in [("AYR..", false), ("AYRB.", true)] {
+        let state = position(wall);
+        assert_eq!(is_endgame(&state), endgame);
+        let weights = match endgame {
+            true => Weights::endgame(),
+            false => Weights::default(),
+        };
+        assert_eq!(
+            evaluate(BoardEvaluation::default(), &state),
+            evaluate(BoardEvaluation::new(weights), &state)
+        );
+    }
+}
+
+#[test]
+fn ties_are_broken_by_complete_rows() {
+    let position = |second: &str| {
+        State::from_notation(&format!(
+            "- - - 10:-/-/-/-/-:AYRBW/WAYRB/...../...../.....:- 10:-/-/-/-/-:AYRBW/{}/...../...../.....:- 10",
+            second
+        ))
+        .unwrap()
+    };
+    assert_eq!(State::new(2).result(), GameResult::Ongoing);
+    // the second player has fewer complete rows...
+    let state = position("WAYR.");
+    assert_eq!(
+        state.result(),
+        GameResult::Won {
+            player: 0,
+            scores: vec![10, 10]
+        }
+    );
+    assert_eq!(state.winner(), Some(0));
+    assert_eq!(state.winners(), vec![0]);
+    // ...or as many, and the victory is shared
+    let state = position("WAYRB");
+    assert_eq!(
+        state.result(),
+        GameResult::Draw {
+            players: vec![0, 1],
+            scores: vec![10, 10]
+        }
+    );
+    assert_eq!(state.winner(), None);
+    assert_eq!(state.winners(), vec![0, 1]);
+}
+
+#[test]
+fn rounds_and_phases_follow_the_game() {
+    let mut rng = StdRng::seed_from_u64(4);
+    let mut state = State::new(2);
+    assert_eq!((state.round, state.phase()), (0, Phase::Dealing));
+    state.deal(&mut rng);
+    assert_eq!((state.round, state.phase()), (1, Phase::Picking));
+    while !state.result().is_over() {
+        let round = state.round;
+        let m = *state.legal_moves().choose(&mut rng).unwrap();
+        state = state.apply(&m).unwrap();
+        if state.phase() == Phase::Dealing {
+            state.deal(&mut rng);
+            assert_eq!(state.round, round + 1);
+        } else {
+            assert_eq!(state.round, round);
+        }
+        // the round is kept in notation
+        assert!(State::from_notation(&state.to_notation()).unwrap() == state);
+    }
+    assert_eq!(state.phase(), Phase::Scoring);
+    assert!(state.round >= 5);
+}
+
+#[test]
+fn draw_probabilities_are_distributions() {
+    let empty = "0:-/-/-/-/-:...../...../...../...../.....:-";
+    let expected = |distribution: &[f64]| -> f64 {
+        distribution
+            .iter()
+            .enumerate()
+            .map(|(count, p)| count as f64 * p)
+            .sum()
+    };
+    // a full bag deals 20 of the 100 tiles, a fifth of each color
+    let state = State::from_notation(&format!("- - - {} {} 0", empty, empty)).unwrap();
+    for distribution in state.draw_probabilities() {
+        assert!((distribution.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!((expected(&distribution) - 4.0).abs() < 1e-9);
+    }
+    // with 10 tiles left in the bag, it is all dealt and 10 tiles drawn
+    // from the lid
+    let state =
+        State::from_notation(&format!("- - 18B18W18A18Y18R {} {} 0", empty, empty)).unwrap();
+    for distribution in state.draw_probabilities() {
+        assert_eq!(&distribution[..2], &[0.0, 0.0]);
+        assert!((distribution.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!((expected(&distribution) - 4.0).abs() < 1e-9);
+    }
+}
+
+// four players with four tiles in each row of their walls and the rest on
+// their floors, but for 2B2Y2R in the bag and nothing in the lid
+fn nearly_empty_bag() -> State {
+    let wall = "AYRB./WAYR./BWAY./RBWA./YRBW.";
+    let player = |floor| format!("0:-/-/-/-/-:{}:{}", wall, floor);
+    let players: Vec<_> = ["4W", "4A", "2Y2R", "2B"].map(player).into();
+    State::from_notation(&format!("- - - {} 0", players.join(" "))).unwrap()
+}
+
+#[test]
+fn deals_from_small_bags_are_listed_with_their_chances() {
+    let state = nearly_empty_bag();
+    assert!(state.is_chance());
+    assert!(state.outcomes(5).is_none());
+    // four of the six tiles on the first factory, and the last two on the
+    // second
+    let outcomes = state.outcomes(100).unwrap();
+    assert_eq!(outcomes.len(), 6);
+    let total: f64 = outcomes.iter().map(|(p, _)| p).sum();
+    assert!((total - 1.0).abs() < 1e-9);
+    let notations: Vec<_> = outcomes
+        .iter()
+        .map(|(_, deal)| deal.to_notation())
+        .collect();
+    let mut rng = StdRng::seed_from_u64(3);
+    let samples = 6000;
+    let mut counts = vec![0; outcomes.len()];
+    for _ in 0..samples {
+        let notation = state.sample(&mut rng).to_notation();
+        counts[notations.iter().position(|n| *n == notation).unwrap()] += 1;
+    }
+    for ((p, _), count) in outcomes.iter().zip(counts) {
+        assert!((count as f64 / samples as f64 - p).abs() < 0.03);
+    }
+}
+
+#[test]
+fn searches_sample_chance_nodes_as_asked() {
+    // the last move of the round, so every move leads to a deal
+    let state = State::from_notation(
+        "-/-/-/-/- R - 0:-/-/-/-/-:...../...../...../...../.....:- 0:-/-/-/-/-:...../...../...../...../.....:- 9",
+    )
+    .unwrap();
+    let nodes = |chance_samples| {
+        let options = SearchOptions {
+            depth: 2,
+            chance_samples,
+            ..SearchOptions::default()
+        };
+        let mut evaluation = BoardEvaluation::default();
+        let mut rng = StdRng::seed_from_u64(1);
+        let (next, info) = search_with_options(&state, &mut evaluation, &mut rng, &options);
+        assert!(next.is_some());
+        assert_eq!(info.depth, 2);
+        info.nodes
+    };
+    assert!(nodes(4) < nodes(16));
+}
+
+#[test]
+fn fish_counts_the_lead_in_hundredths_of_a_point() {
+    // the complete first row of player two scores a point at the end of
+    // the round
+    let state = State::from_notation(
+        "- Y - 10:-/-/-/-/-:...../...../...../...../.....:- 12:1W/-/-/-/-:...../...../...../...../.....:- 0",
+    )
+    .unwrap();
+    let evaluation = Fish::new();
+    assert_eq!(evaluation.evaulate(&state, 0), -300);
+    assert_eq!(evaluation.evaulate(&state, 1), 300);
+}
+
+#[test]
+fn fish_caches_evaluations_in_a_fixed_number_of_slots() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    let options = SearchOptions {
+        depth: 3,
+        ..SearchOptions::default()
+    };
+    let search = |capacity| {
+        let mut evaluation = Fish::with_capacity(capacity);
+        let mut rng = StdRng::seed_from_u64(3);
+        search_with_options(&state, &mut evaluation, &mut rng, &options).1
+    };
+    // iterative deepening evaluates leaves again, answered from the cache
+    let (large, small) = (search(1 << 16), search(1));
+    assert!(large.cache_hits > small.cache_hits);
+    assert!(large.cache_hits <= large.evaluations);
+    // ...with the values evaluating them again would give
+    assert_eq!((large.score, large.pv), (small.score, small.pv));
+}
+
+#[test]
+fn fish_engines_keep_their_cache_from_move_to_move() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    let spec = Spec::Fish {
+        depth: 2,
+        reductions: true,
+    };
+    let Registered::Fish(mut engine) = spec.build() else {
+        panic!("{} is built as minmax with fish", spec);
+    };
+    engine.choose(&state, &mut rng);
+    let first = engine.evaluation.cache_hits();
+    // the second search finds the leaves of the first in the cache
+    engine.choose(&state, &mut rng);
+    assert!(engine.evaluation.cache_hits() - first > first);
+}
+
+#[test]
+fn late_move_reductions_search_fewer_nodes() {
+    let mut rng = StdRng::seed_from_u64(4);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    for _ in 0..4 {
+        state = random_move(&state, &mut rng);
+    }
+    let search = |late_move_reductions| {
+        let options = SearchOptions {
+            depth: 4,
+            chance_samples: 1,
+            late_move_reductions,
+            ..SearchOptions::default()
+        };
+        let mut evaluation = BoardEvaluation::default();
+        let mut rng = StdRng::seed_from_u64(5);
+        search_with_options(&state, &mut evaluation, &mut rng, &options).1
+    };
+    let (reduced, full) = (search(true), search(false));
+    assert!(reduced.nodes < full.nodes);
+    assert_eq!(full.researches, 0);
+    assert_eq!(reduced.depth, 4);
+    assert!(state.legal_moves().contains(&reduced.pv[0]));
+}
+
+#[test]
+fn searches_are_reproducible() {
+    let state = State::from_notation(
+        "-/-/-/-/- R - 0:-/-/-/-/-:...../...../...../...../.....:- 0:-/-/-/-/-:...../...../...../...../.....:- 9",
+    )
+    .unwrap();
+    // searches stay reproducible from the seed with either way of sampling
+    for common_random_numbers in [false, true] {
+        let options = SearchOptions {
+            depth: 2,
+            chance_samples: 4,
+            common_random_numbers,
+            ..SearchOptions::default()
+        };
+        let search = || {
+            let mut evaluation = BoardEvaluation::default();
+            let mut rng = StdRng::seed_from_u64(3);
+            let (_, info) = search_with_options(&state, &mut evaluation, &mut rng, &options);
+            (info.score, info.pv, info.nodes)
+        };
+        assert_eq!(search(), search());
+    }
+}
+
+#[test]
+fn search_plays_the_move_it_found() {
+    for seed in 0..5 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut state = State::new(2);
+        state.deal(&mut rng);
+        for _ in 0..seed {
+            state = random_move(&state, &mut rng);
+        }
+        let mut evaluation = BoardEvaluation::default();
+        let (played, info) = search_with_info(&state, &mut evaluation, &mut rng, 2);
+        assert!(state.legal_moves().contains(&played.unwrap()));
+        assert_eq!(played.as_ref(), info.pv.first());
+    }
+}
+
+#[test]
+fn determinized_searches_play_the_move_they_agree_on() {
+    // the last tile of the round, so the search looks into the next deal
+    let state = State::from_notation(
+        "-/-/-/-/- R - 0:-/-/-/-/-:...../...../...../...../.....:- 0:-/-/-/-/-:...../...../...../...../.....:- 9",
+    )
+    .unwrap();
+    let options = |determinizations| SearchOptions {
+        determinizations,
+        ..SearchOptions::depth(2)
+    };
+    let search = |options: &SearchOptions| {
+        let mut evaluation = BoardEvaluation::default();
+        let mut rng = StdRng::seed_from_u64(9);
+        search_with_options(&state, &mut evaluation, &mut rng, options)
+    };
+    let (played, info) = search(&options(5));
+    assert_eq!(played.as_ref(), info.pv.first());
+    let (_, again) = search(&options(5));
+    assert_eq!(
+        (again.score, again.pv, again.nodes),
+        (info.score, info.pv.clone(), info.nodes)
+    );
+    // each determinization is a search with one sample per chance node
+    let single = SearchOptions {
+        chance_samples: 1,
+        ..options(1)
+    };
+    assert!(info.nodes > search(&single).1.nodes);
+}
+
+#[test]
+fn stopped_searches_still_move() {
+    let mut rng = StdRng::seed_from_u64(6);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    let stop = StopToken::new();
+    stop.stop();
+    let options = SearchOptions {
+        stop: Some(stop),
+        ..SearchOptions::depth(4)
+    };
+    let mut evaluation = BoardEvaluation::default();
+    let (played, info) = search_with_options(&state, &mut evaluation, &mut rng, &options);
+    assert_eq!(info.depth, 0);
+    assert_eq!(info.pv, vec![played.unwrap()]);
+}
+
+#[test]
+fn multipv_lines_are_different_moves_best_first() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    let mut evaluation = BoardEvaluation::default();
+    let options = SearchOptions::depth(2);
+    let lines = search_multipv(&state, &mut evaluation, &mut rng, &options, 5);
+    assert_eq!(lines.len(), 5);
+    let moves = state.legal_moves();
+    for (index, line) in lines.iter().enumerate() {
+        assert!(moves.contains(&line.pv[0]));
+        assert!(lines[..index].iter().all(|other| other.pv[0] != line.pv[0]));
+    }
+    assert!(lines.windows(2).all(|pair| pair[0].score >= pair[1].score));
+    // more lines than moves gives every move
+    let all = search_multipv(&state, &mut evaluation, &mut rng, &options, 1000);
+    assert_eq!(all.len(), moves.len());
+}
+
+#[test]
+fn pondering_searches_the_expected_position() {
+    let mut rng = StdRng::seed_from_u64(4);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    let options = SearchOptions::depth(2);
+    let mut engine = Ponderer::new(BoardEvaluation::default());
+    let (_, info) = engine.search(&state, &mut rng, &options);
+    let expected = expected_position(&state, &info.pv).unwrap();
+    assert_eq!(expected.current_player(), state.current_player());
+    // the first depth always completes, and answers a search as deep
+    let shallow = SearchOptions::depth(1);
+    engine.ponder(expected.clone(), &shallow, 5);
+    let (_, pondered) = engine.search(&expected, &mut rng, &shallow);
+    assert_eq!(pondered.nodes, 1);
+    // without pondering the search starts over
+    let (_, fresh) = engine.search(&expected, &mut rng, &shallow);
+    assert!(fresh.nodes > 1);
+    assert_eq!(fresh.pv.first(), pondered.pv.first());
+}
+
+#[test]
+fn equal_factories_are_searched_once() {
+    let state = State::from_notation(
+        "2BW/2BW/AYRR/- - - 0:-/-/-/-/-:...../...../...../...../.....:- 0:-/-/-/-/-:...../...../...../...../.....:- 0",
+    )
+    .unwrap();
+    let moves = state.legal_moves();
+    let distinct = state.distinct_moves();
+    assert!(distinct.iter().all(|m| moves.contains(m)));
+    assert!(distinct.iter().all(|m| m.origin != Origin::Factory(1)));
+    assert_eq!(
+        distinct.len(),
+        moves
+            .iter()
+            .filter(|m| m.origin != Origin::Factory(1))
+            .count()
+    );
+}
+
+#[test]
+fn factory_order_does_not_change_the_hash() {
+    let position = |factories: &str| {
+        State::from_notation(&format!(
+            "{} - - 0:-/-/-/-/-:...../...../...../...../.....:- 0:-/-/-/-/-:...../...../...../...../.....:- 0",
+            factories
+        ))
+        .unwrap()
+    };
+    let state = position("2BW/AYRR/2BW/WAYR/BBBB");
+    let permuted = position("AYRR/2BW/BBBB/2BW/WAYR");
+    assert_eq!(state.zobrist(), permuted.zobrist());
+    assert_ne!(
+        state.zobrist(),
+        position("2BW/AYRR/2BW/WAYR/AAAA").zobrist()
+    );
+    // and stays the same as moves are made from either
+    let m = |notation| Move::parse(notation).unwrap();
+    let state = state.apply(&m("F1 B -> row2")).unwrap();
+    let permuted = permuted.apply(&m("F4 B -> row2")).unwrap();
+    state.self_check();
+    permuted.self_check();
+    assert_eq!(state.zobrist(), permuted.zobrist());
+}
+
+#[test]
+fn complete_rows_are_pending_until_the_round_ends() {
+    let state = State::from_notation(
+        "BBWW/AYRR/- Y - 0:-/1B/-/-/-:...../...../...../...../.....:- 0:-/-/-/-/-:...../...../...../...../.....:- 0",
+    )
+    .unwrap();
+    assert_eq!(state.players[0].pending_points(), 0);
+    // both rows are scored together, the second next to the first
+    let play = |state: State, m: &str| state.apply(&Move::parse(m).unwrap()).unwrap();
+    let state = play(state, "F1 B -> row2");
+    let state = play(state, "F2 A -> row1");
+    let state = play(state, "C W -> row1");
+    state.self_check();
+    assert_eq!(state.players[0].pending_points(), 3);
+    assert_eq!(state.players[0].points, 0);
+    let state = play(state, "C Y -> row2");
+    let state = play(state, "C R -> row3");
+    assert!(state.needs_deal());
+    assert_eq!(state.players[0].pending_points(), 0);
+    assert_eq!(state.players[0].points, 3 - 2);
+}
+
+#[test]
+fn floor_lines_overflow_into_the_lid() {
+    // every row of the wall has a red tile, so they can only go to the floor
+    let state = State::from_notation(
+        "- 9R - 20:-/-/-/-/-:..R../...R./....R/R..../.R...:- 0:-/-/-/-/-:...../...../...../...../.....:- 0",
+    )
+    .unwrap();
+    let (state, events) = state
+        .apply_scoring(&Move::parse("C R -> floor").unwrap())
+        .unwrap();
+    // seven tiles fill the floor line and the other two go to the lid, as
+    // do the seven once the round is scored
+    assert_eq!(events[0], vec![ScoreEvent::Floor { penalty: 14 }]);
+    assert_eq!(state.players[0].points, 20 - 14);
+    assert!(state.to_notation().starts_with("- - 9R "));
+    state.self_check();
+}
+
+#[test]
+fn bonuses_are_scored_once_when_the_game_ends() {
+    // the first column and every azul tile are on the wall of player one,
+    // and the first row is one white tile short
+    let position = |pending: &str| {
+        State::from_notation(&format!(
+            "- Y - 10:{}/-/-/-/-:AYRB./WA.../B.A../R..A./Y...A:- 10:-/-/-/-/-:...../...../...../...../.....:- 1",
+            pending
+        ))
+        .unwrap()
+    };
+    let m = Move::parse("C Y -> row1").unwrap();
+    // without a complete row the game goes on, and there's no bonus yet
+    let (state, events) = position("-").apply_scoring(&m).unwrap();
+    assert!(state.needs_deal());
+    assert!(events[0].is_empty());
+    assert_eq!(state.players[0].points, 10);
+    // completing the first row ends the game, with every bonus once
+    let (state, events) = position("1W").apply_scoring(&m).unwrap();
+    assert_eq!(
+        events[0],
+        vec![
+            ScoreEvent::Placed {
+                row: 0,
+                column: 4,
+                tile: Tile::WHITE,
+                points: 5
+            },
+            ScoreEvent::RowBonus { row: 0 },
+            ScoreEvent::ColumnBonus { column: 0 },
+            ScoreEvent::ColorBonus { tile: Tile::AZUL },
+        ]
+    );
+    assert_eq!(state.players[0].points, 10 + 5 + 2 + 7 + 10);
+    assert_eq!(state.phase(), Phase::Scoring);
+    assert_eq!(state.winner(), Some(0));
+    // ...which searches know even with no depth left to evaluate it
+    let mut rng = StdRng::seed_from_u64(0);
+    for (player, value) in [(0, i32::MAX), (1, i32::MIN)] {
+        let mut evaluation = BoardEvaluation::default();
+        let (_, score) = minmax(
+            &state,
+            &mut evaluation,
+            &mut rng,
+            player,
+            0,
+            i32::MIN,
+            i32::MAX,
+        );
+        assert_eq!(score, value);
+    }
+}
+
+#[test]
+fn opponents_search_unless_an_engine_is_given() {
+    let mut rng = StdRng::seed_from_u64(8);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    let evaluation = BoardEvaluation::default();
+    let mut opponent = Opponent::new(None, evaluation, SearchOptions::depth(2), false);
+    let (m, info) = opponent.choose(&state, None, &mut rng);
+    let info = info.unwrap();
+    assert_eq!((info.pv[0], info.depth), (m, 2));
+    // with a clock, as deep as the time allows
+    let clock = Clock::new("1+0".parse().unwrap(), 2);
+    let (m, info) = opponent.choose(&state, Some(&clock), &mut rng);
+    assert!(state.legal_moves().contains(&m));
+    assert!(info.unwrap().depth >= 1);
+    let evaluation = BoardEvaluation::default();
+    let mut opponent = Opponent::new(
+        Some(Spec::Random.build()),
+        evaluation,
+        SearchOptions::depth(2),
+        false,
+    );
+    let (m, info) = opponent.choose(&state, None, &mut rng);
+    assert!(state.legal_moves().contains(&m));
+    assert!(info.is_none());
+}
+
+#[test]
+fn complete_rows_without_room_on_the_wall_are_rejected() {
+    // the first pattern row is full of azul, which is already on the wall
+    let empty = "-/-/-/-/-:...../...../...../...../.....:-";
+    let notation = format!(
+        "- - - 0:A/-/-/-/-:A..../...../...../...../.....:- 0:{} 0",
+        empty
+    );
+    let error = State::from_notation(&notation).err().unwrap();
+    assert!(error.to_string().contains("already taken"), "{}", error);
+}
+
+#[test]
+fn descriptions_must_match_the_move() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    let m = state.legal_moves()[0];
+    let description = state.describe(&m);
+    assert!(state.apply_description(&description).unwrap() == state.apply(&m).unwrap());
+    let other = |description| state.apply_description(&description).err();
+    assert_eq!(
+        other(MoveDescription {
+            player: 1,
+            ..description
+        }),
+        Some(AzulError::Move(MoveError::NotYourTurn))
+    );
+    assert_eq!(
+        other(MoveDescription {
+            count: description.count + 1,
+            ..description
+        }),
+        Some(AzulError::Move(MoveError::WrongCount))
+    );
+}
+
+#[test]
+fn moves_ending_the_round_are_described_with_the_scoring() {
+    let mut rng = StdRng::seed_from_u64(8);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    loop {
+        let m = *state.legal_moves().choose(&mut rng).unwrap();
+        let (mut next, events) = state.apply_scoring(&m).unwrap();
+        if !next.needs_deal() {
+            assert!(describe_scoring(&state, &next).unwrap().1.is_empty());
+            state = next;
+            continue;
+        }
+        // the rows are already cleared and new factories dealt
+        next.deal(&mut rng);
+        let (description, scoring) = describe_scoring(&state, &next).unwrap();
+        assert_eq!(description, state.describe(&m));
+        assert_eq!(scoring, events);
+        assert!(!scoring.is_empty());
+        break;
+    }
+}
+
+#[test]
+fn moves_dropping_tiles_needlessly_are_pruned() {
+    let state = State::from_notation(
+        "4B/AYRR/- - - 0:-/-/-/-/-:...../...../...../...../.....:- 0:-/-/-/-/-:...../...../...../...../.....:- 0",
+    )
+    .unwrap();
+    let mut moves = state.legal_moves();
+    // tiles may always be thrown on the floor, even when they fit on a row
+    let floor = Move {
+        origin: Origin::Factory(1),
+        tile: Tile::RED,
+        destination: Destination::Floor,
+    };
+    assert!(moves.contains(&floor));
+    state.apply(&floor).unwrap();
+    state.prune_moves(&mut moves, 2);
+    assert!(!moves.contains(&floor));
+    // four black tiles drop three on the first row and two on the second
+    let black: Vec<_> = moves
+        .iter()
+        .filter(|m| m.tile == Tile::BLACK)
+        .map(|m| m.destination)
+        .collect();
+    assert_eq!(
+        black,
+        [
+            Destination::Row(2),
+            Destination::Row(3),
+            Destination::Row(4)
+        ]
+    );
+    // ...and two red tiles drop at most one anywhere
+    assert_eq!(moves.iter().filter(|m| m.tile == Tile::RED).count(), 5);
+    let options = SearchOptions {
+        depth: 3,
+        ..SearchOptions::default()
+    };
+    let mut rng = StdRng::seed_from_u64(6);
+    let (_, info) =
+        search_with_options(&state, &mut BoardEvaluation::default(), &mut rng, &options);
+    assert!(info.pruned > 0);
+}
+
+#[test]
+fn mcts_plays_legal_moves_within_its_budget() {
+    let mut rng = StdRng::seed_from_u64(8);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    let iterations = Mcts {
+        iterations: 50,
+        ..Mcts::default()
+    };
+    let m = iterations.search(&state, &mut rng).unwrap();
+    assert!(state.legal_moves().contains(&m));
+    // far more playouts than fit in the time
+    let timed = Mcts {
+        iterations: usize::MAX,
+        budget: Some(Duration::from_millis(50)),
+        ..Mcts::default()
+    };
+    let start = Instant::now();
+    let m = timed.search(&state, &mut rng).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert!(state.legal_moves().contains(&m));
+    // nothing to play once the game is over
+    let over = State::from_notation(
+        "- - - 10:-/-/-/-/-:AYRBW/WAYRB/...../...../.....:- 10:-/-/-/-/-:AYRBW/...../...../...../.....:- 10",
+    )
+    .unwrap();
+    assert_eq!(timed.search(&over, &mut rng), None);
+}
+
+#[test]
+fn perft_counts_the_move_sequences() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    assert_eq!(perft(&state, 1, Chance::Sample, &mut rng), [96]);
+    assert_eq!(perft(&state, 2, Chance::Sample, &mut rng), [96, 8640]);
+    // no deal within two moves, so listing the deals changes nothing
+    assert_eq!(
+        perft(&state, 2, Chance::Enumerate(100), &mut rng),
+        [96, 8640]
+    );
+    // every deal is counted, or a random one when there are too many
+    let state = nearly_empty_bag();
+    let outcomes = state.outcomes(100).unwrap();
+    let moves: u64 = outcomes
+        .iter()
+        .map(|(_, deal)| deal.legal_moves().len() as u64)
+        .sum();
+    assert_eq!(perft(&state, 1, Chance::Enumerate(100), &mut rng), [moves]);
+    let sampled = perft(&state, 1, Chance::Enumerate(5), &mut rng)[0];
+    assert!(outcomes
+        .iter()
+        .any(|(_, deal)| deal.legal_moves().len() as u64 == sampled));
+}
+
+#[test]
+fn short_factories_are_dealt_once_the_tiles_run_out() {
+    let mut rng = StdRng::seed_from_u64(4);
+    let mut state = nearly_empty_bag();
+    state.deal(&mut rng);
+    state.self_check();
+    // the six tiles left fill the first factory and half the second, and
+    // the other seven stay empty
+    let notation = state.to_notation();
+    let factories: Vec<_> = notation.split(' ').next().unwrap().split('/').collect();
+    assert_eq!(factories.len(), 9);
+    assert!(factories[..2].iter().all(|factory| *factory != "-"));
+    assert!(factories[2..].iter().all(|factory| *factory == "-"));
+    // the game goes on from the tiles thrown in the lid
+    let round = state.round;
+    while state.round < round + 3 && !state.result().is_over() {
+        state = random_move(&state, &mut rng);
+        state.self_check();
+    }
+}
@@ -0,0 +1,73 @@
+use azul::{
+    engine::{Engine, Random, Temperature, Tempered},
+    features::FEATURES,
+    selfplay, Move, State,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{collections::HashSet, env, fs};
+
+// the shape in the header of a .npy file, checked against the file size
+fn shape(bytes: &[u8]) -> String {
+    assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+    let length = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    assert_eq!((10 + length) % 64, 0);
+    let header = std::str::from_utf8(&bytes[10..10 + length]).unwrap();
+    let shape = header.split("'shape': (").nth(1).unwrap();
+    let shape = &shape[..shape.find(')').unwrap()];
+    let size: usize = shape
+        .split(',')
+        .filter(|dimension| !dimension.trim().is_empty())
+        .map(|dimension| dimension.trim().parse::<usize>().unwrap())
+        .product();
+    assert_eq!(bytes.len(), 10 + length + 4 * size);
+    shape.to_string()
+}
+
+#[test]
+fn selfplay_writes_a_sample_per_move() {
+    let directory = env::temp_dir().join(format!("azul-selfplay-{}", std::process::id()));
+    let mut rng = StdRng::seed_from_u64(3);
+    let samples = selfplay::run(&mut Random, 2, &directory, &mut rng, |_| {}).unwrap();
+    assert!(samples > 0);
+    let read = |name: &str| fs::read(directory.join(name)).unwrap();
+    assert_eq!(
+        shape(&read("features.npy")),
+        format!("{}, {}", samples, FEATURES)
+    );
+    assert_eq!(shape(&read("moves.npy")), format!("{}, 4", samples));
+    assert_eq!(shape(&read("results.npy")), format!("{}, 2", samples));
+    assert_eq!(shape(&read("games.npy")), format!("{},", samples));
+    fs::remove_dir_all(directory).unwrap();
+}
+
+// always the first legal move
+struct First;
+
+impl Engine for First {
+    fn choose<R: Rng>(&mut self, state: &State, _rng: &mut R) -> Move {
+        state.legal_moves()[0]
+    }
+}
+
+#[test]
+fn temperature_varies_the_first_moves() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    let first = state.legal_moves()[0];
+    let mut engine = Tempered {
+        engine: First,
+        temperature: Temperature::default(),
+    };
+    assert_eq!(engine.choose(&state, &mut rng), first);
+    engine.temperature = Temperature {
+        temperature: 5.0,
+        plies: 1,
+    };
+    let moves: HashSet<_> = (0..50).map(|_| engine.choose(&state, &mut rng)).collect();
+    assert!(moves.len() > 1);
+    // and only the first plies
+    let next = state.apply(&first).unwrap();
+    let reply = next.legal_moves()[0];
+    assert_eq!(engine.choose(&next, &mut rng), reply);
+}
@@ -0,0 +1,231 @@
+#![cfg(feature = "serde")]
+
+use azul::{
+    clock::Clock,
+    engine::{Spec, Temperature},
+    game_log::GameLog,
+    random_move,
+    rating::Ratings,
+    tournament::{self, Adjudication},
+    Destination, GameRecord, History, Move, Origin, SavedGame, State, Tile, WallVariant,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use serde_json::json;
+use std::time::Duration;
+
+// a state some moves into the game
+fn midgame(seed: u64, moves: usize) -> State {
+    variant_midgame(seed, moves, WallVariant::Colored)
+}
+
+fn variant_midgame(seed: u64, moves: usize, variant: WallVariant) -> State {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut state = State::new_variant(2, variant);
+    state.deal(&mut rng);
+    for _ in 0..moves {
+        state = random_move(&state, &mut rng);
+    }
+    state
+}
+
+#[test]
+fn state_round_trip() {
+    for moves in [0, 5, 10, 20, 40] {
+        let state = midgame(moves as u64, moves);
+        let json = serde_json::to_string(&state).unwrap();
+        let parsed: State = serde_json::from_str(&json).unwrap();
+        assert!(parsed == state);
+        assert_eq!(parsed.zobrist(), state.zobrist());
+        parsed.self_check();
+    }
+}
+
+#[test]
+fn states_missing_tiles_are_rejected() {
+    let mut json = serde_json::to_value(midgame(3, 5)).unwrap();
+    json["bag"]["red"] = 0.into();
+    let error = serde_json::from_value::<State>(json).err().unwrap();
+    assert!(error.to_string().contains("red tiles, expected 20"));
+}
+
+#[test]
+fn states_with_too_many_factories_are_rejected() {
+    let mut json = serde_json::to_value(midgame(3, 5)).unwrap();
+    let empty = json!({"black": 0, "white": 0, "azul": 0, "yellow": 0, "red": 0});
+    json["factories"] = vec![empty; 10].into();
+    let error = serde_json::from_value::<State>(json).err().unwrap();
+    assert!(error
+        .to_string()
+        .contains("10 factories, expected at most 9"));
+}
+
+#[test]
+fn free_wall_round_trip() {
+    for moves in [10, 20, 40] {
+        let state = variant_midgame(moves as u64, moves, WallVariant::Free);
+        let json = serde_json::to_string(&state).unwrap();
+        let parsed: State = serde_json::from_str(&json).unwrap();
+        assert!(parsed == state);
+        assert_eq!(parsed.variant, WallVariant::Free);
+    }
+}
+
+#[test]
+fn state_schema() {
+    let json = serde_json::to_value(State::new(2)).unwrap();
+    let fields = [
+        "bag",
+        "factories",
+        "center",
+        "lid",
+        "players",
+        "moves",
+        "round",
+    ];
+    let object = json.as_object().unwrap();
+    assert_eq!(object.len(), fields.len());
+    for field in fields {
+        assert!(object.contains_key(field), "missing {}", field);
+    }
+    assert_eq!(json["bag"]["azul"], 20);
+    let player = json["players"][0].as_object().unwrap();
+    for field in ["rows", "points", "wall", "floor"] {
+        assert!(player.contains_key(field), "missing {}", field);
+    }
+}
+
+#[test]
+fn move_round_trip() {
+    let state = midgame(1, 3);
+    for m in state.legal_moves() {
+        let json = serde_json::to_string(&m).unwrap();
+        assert_eq!(serde_json::from_str::<Move>(&json).unwrap(), m);
+    }
+    let m = Move {
+        origin: Origin::Factory(2),
+        tile: Tile::RED,
+        destination: Destination::Floor,
+    };
+    assert_eq!(
+        serde_json::to_string(&m).unwrap(),
+        r#"{"origin":{"factory":2},"tile":"red","destination":"floor"}"#
+    );
+}
+
+#[test]
+fn saved_games_continue_with_the_positions_and_clocks() {
+    let mut rng = StdRng::seed_from_u64(6);
+    let mut state = State::new(3);
+    state.deal(&mut rng);
+    let mut history = History::new(state.clone());
+    for _ in 0..10 {
+        state = random_move(&state, &mut rng);
+        history.push(state.clone());
+    }
+    let remaining = vec![
+        Duration::from_secs(290),
+        Duration::from_secs(301),
+        Duration::from_millis(1500),
+    ];
+    let clock = Clock::with_remaining("5+3".parse().unwrap(), remaining);
+    let saved = SavedGame::new(&history, 2, 7, Some(&clock));
+    assert_eq!(saved.time_control.as_deref(), Some("5+3"));
+    assert_eq!(saved.clocks, vec![290_000, 301_000, 1_500]);
+    let (restored, restored_clock) = saved.restore().unwrap();
+    assert!(restored.states() == history.states());
+    assert_eq!(restored_clock.unwrap().remaining(), clock.remaining());
+    // the seat of the human and the clocks must fit the game
+    let seat = SavedGame {
+        human: 3,
+        ..saved.clone()
+    };
+    assert!(seat.restore().is_err());
+    let clocks = SavedGame {
+        clocks: vec![1000],
+        ..saved
+    };
+    assert!(clocks.restore().is_err());
+}
+
+#[test]
+fn ratings_count_every_game_of_a_match() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let rules = Adjudication::default();
+    let summary = tournament::run(
+        &Spec::Greedy,
+        &Spec::Random,
+        4,
+        &rules,
+        &Temperature::default(),
+        &mut rng,
+        &mut (),
+        |_, _| {},
+    );
+    let mut ratings = Ratings::default();
+    ratings.record("greedy", "random", &summary);
+    let (greedy, random) = (ratings.get("greedy"), ratings.get("random"));
+    assert_eq!((greedy.games(), random.games()), (4, 4));
+    assert_eq!((greedy.wins, random.losses), (summary.wins, summary.wins));
+    let table = ratings.to_string();
+    assert!(table.starts_with("   elo  games  engine"));
+    assert_eq!(table.lines().count(), 3);
+}
+
+#[test]
+fn log_has_a_line_per_move() {
+    let before = midgame(4, 3);
+    let mut rng = StdRng::seed_from_u64(4);
+    let after = random_move(&before, &mut rng);
+    let mut out = Vec::new();
+    let mut log = GameLog::new(&mut out);
+    assert!(log
+        .push(&before, &after, Some(150), Duration::from_millis(20))
+        .unwrap());
+    assert!(!log.push(&before, &before, None, Duration::ZERO).unwrap());
+    let text = String::from_utf8(out).unwrap();
+    assert_eq!(text.lines().count(), 1);
+    let line: serde_json::Value = serde_json::from_str(text.trim_end()).unwrap();
+    assert_eq!(line["ply"], 3);
+    assert_eq!(line["evaluation"], 150);
+    assert_eq!(line["time_ms"], 20.0);
+    assert!(line.get("scoring").is_none());
+    assert_eq!(line["position"], before.to_notation());
+    let state: State = serde_json::from_value(line["state"].clone()).unwrap();
+    assert!(state == before);
+    let m: Move = line["move"].as_str().unwrap().parse().unwrap();
+    assert_eq!(before.apply(&m).unwrap().to_notation(), after.to_notation());
+}
+
+#[test]
+fn saved_game_round_trip() {
+    let mut rng = StdRng::seed_from_u64(4);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    let mut record = GameRecord::new(None, &state);
+    for _ in 0..12 {
+        let next = random_move(&state, &mut rng);
+        record.push(&state, &next);
+        state = next;
+    }
+    let saved = SavedGame {
+        record,
+        human: 1,
+        seed: 5,
+        time_control: Some("5+3".to_string()),
+        clocks: vec![290_000, 301_500],
+    };
+    let json = serde_json::to_string(&saved).unwrap();
+    let parsed: SavedGame = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, saved);
+    let states = parsed.record.replay().unwrap();
+    assert!(states.last() == Some(&state));
+    // games without clocks leave them out
+    let untimed = SavedGame {
+        time_control: None,
+        clocks: Vec::new(),
+        ..saved
+    };
+    let json = serde_json::to_string(&untimed).unwrap();
+    assert!(!json.contains("clocks"));
+    assert_eq!(serde_json::from_str::<SavedGame>(&json).unwrap(), untimed);
+}
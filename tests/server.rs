@@ -0,0 +1,111 @@
+#![cfg(feature = "server")]
+
+use azul::server::Server;
+use serde_json::Value;
+
+// the legal moves of a game, as the server lists them
+fn legal_moves(game: &Value) -> Vec<String> {
+    let moves = game["legal_moves"].as_array().unwrap();
+    moves
+        .iter()
+        .map(|m| m.as_str().unwrap().to_string())
+        .collect()
+}
+
+fn post_move(server: &mut Server, m: &str) -> (u16, Value) {
+    let body = serde_json::json!({ "move": m }).to_string();
+    server.handle("POST", "/games/0/moves", &body)
+}
+
+#[test]
+fn games_are_created_and_fetched() {
+    let mut server = Server::new(1);
+    let (status, game) = server.handle("POST", "/games", r#"{"players": 3}"#);
+    assert_eq!(status, 201);
+    assert_eq!(game["id"], 0);
+    assert_eq!(game["current_player"], 0);
+    assert!(game["winner"].is_null());
+    assert!(game["clocks"].is_null());
+    assert!(!legal_moves(&game).is_empty());
+    // an empty body makes a game for two
+    let (status, game) = server.handle("POST", "/games", "");
+    assert_eq!(status, 201);
+    assert_eq!(game["id"], 1);
+    let (status, fetched) = server.handle("GET", "/games/1", "");
+    assert_eq!(status, 200);
+    assert_eq!(fetched, game);
+    // with a time control, the clocks are shown
+    let (status, game) = server.handle("POST", "/games", r#"{"tc": "5+3"}"#);
+    assert_eq!(status, 201);
+    assert_eq!(game["clocks"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn moves_are_played_and_dealt() {
+    let mut server = Server::new(1);
+    let (_, game) = server.handle("POST", "/games", "");
+    let m = &legal_moves(&game)[0];
+    let (status, game) = post_move(&mut server, m);
+    assert_eq!(status, 200);
+    assert_eq!(game["current_player"], 1);
+    assert!(!legal_moves(&game).contains(m));
+    // the engine answers, and says what it played
+    let (status, game) = server.handle("POST", "/games/0/engine", r#"{"movetime": 10}"#);
+    assert_eq!(status, 200);
+    assert_eq!(game["current_player"], 0);
+    assert!(game["move"].is_string());
+}
+
+#[test]
+fn bad_requests_are_refused() {
+    let mut server = Server::new(1);
+    let (status, _) = server.handle("POST", "/games", r#"{"players": 5}"#);
+    assert_eq!(status, 400);
+    let (status, _) = server.handle("POST", "/games", r#"{"tc": "soon"}"#);
+    assert_eq!(status, 400);
+    let (status, body) = server.handle("POST", "/games", "players");
+    assert_eq!(status, 400);
+    assert!(body["error"].is_string());
+    let (_, game) = server.handle("POST", "/games", "");
+    let (status, _) = post_move(&mut server, "nonsense");
+    assert_eq!(status, 400);
+    let (status, _) = server.handle("POST", "/games/0/moves", r#"{"m": "F1 B row1"}"#);
+    assert_eq!(status, 400);
+    // a move that was legal, but not any longer once the factory is taken
+    let m = &legal_moves(&game)[0];
+    assert_eq!(post_move(&mut server, m).0, 200);
+    let (status, body) = post_move(&mut server, m);
+    assert_eq!(status, 400);
+    assert!(body["error"].is_string());
+    let (_, after) = server.handle("GET", "/games/0", "");
+    assert_eq!(after["current_player"], 1);
+}
+
+#[test]
+fn unknown_games_and_endpoints_are_not_found() {
+    let mut server = Server::new(1);
+    assert_eq!(server.handle("GET", "/games/0", "").0, 404);
+    assert_eq!(server.handle("GET", "/games/zero", "").0, 404);
+    server.handle("POST", "/games", "");
+    assert_eq!(server.handle("GET", "/games/1", "").0, 404);
+    assert_eq!(server.handle("DELETE", "/games/0", "").0, 404);
+    assert_eq!(server.handle("GET", "/players", "").0, 404);
+}
+
+#[test]
+fn finished_games_take_no_more_moves() {
+    let mut server = Server::new(1);
+    let (_, mut game) = server.handle("POST", "/games", "");
+    while game["winners"].as_array().unwrap().is_empty() {
+        let m = legal_moves(&game)[0].clone();
+        let (status, next) = post_move(&mut server, &m);
+        assert_eq!(status, 200);
+        game = next;
+    }
+    assert!(legal_moves(&game).is_empty());
+    let (status, body) = post_move(&mut server, "F1 B row1");
+    assert_eq!(status, 400);
+    assert_eq!(body["error"], "the game is over");
+    let (status, _) = server.handle("POST", "/games/0/engine", "");
+    assert_eq!(status, 400);
+}
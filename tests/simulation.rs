@@ -0,0 +1,34 @@
+use azul::{engine::Spec, simulate};
+
+#[test]
+fn simulations_rotate_the_seats() {
+    let engines = [Spec::Greedy, Spec::Random, Spec::Random];
+    let report = simulate(&engines, 6, 1);
+    assert_eq!(report.games.len(), 6);
+    for (index, game) in report.games.iter().enumerate() {
+        assert_eq!(game.seats[0], index % 3);
+        assert!(game.winner.is_some());
+    }
+    assert_eq!(report.wins.iter().sum::<usize>(), 6);
+    assert!((report.seat_wins.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    // greedy beats random players
+    assert!(report.average_points[0] > report.average_points[1]);
+    assert!(report.average_moves > 0.0);
+}
+
+#[test]
+fn simulations_are_seeded() {
+    let engines = [Spec::Random, Spec::Random];
+    assert_eq!(simulate(&engines, 3, 7), simulate(&engines, 3, 7));
+    assert_ne!(simulate(&engines, 3, 7), simulate(&engines, 3, 8));
+}
+
+#[test]
+fn higher_levels_play_better() {
+    assert_eq!("level:3".parse(), Ok(Spec::Level(3)));
+    assert_eq!(Spec::Level(3).to_string(), "level:3");
+    assert!("level:0".parse::<Spec>().is_err());
+    assert!("level:9".parse::<Spec>().is_err());
+    let report = simulate(&[Spec::Level(1), Spec::Level(5)], 6, 1);
+    assert!(report.average_points[1] > report.average_points[0]);
+}
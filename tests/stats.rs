@@ -0,0 +1,41 @@
+#![cfg(feature = "serde")]
+
+use azul::{random_move, stats::Stats, DeterministicGameState, GameRecord, GameResult, State};
+use rand::{rngs::StdRng, SeedableRng};
+
+// a recorded game of random moves
+fn record(seed: u64) -> (GameRecord, State) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    let mut record = GameRecord::new(Some(seed), &state);
+    while !state.result().is_over() {
+        let next = random_move(&state, &mut rng);
+        record.push(&state, &next);
+        state = next;
+    }
+    (record, state)
+}
+
+#[test]
+fn stats_count_every_game() {
+    let mut stats = Stats::default();
+    let mut first_player_wins = 0;
+    for seed in 0..5 {
+        let (record, last) = record(seed);
+        stats.add(&record).unwrap();
+        if let GameResult::Won { player: 0, .. } = last.result() {
+            first_player_wins += 1;
+        }
+    }
+    assert_eq!(stats.games, 5);
+    assert_eq!(stats.scores.values().sum::<usize>(), 10);
+    // every player has a penalty, maybe none, every round
+    assert_eq!(stats.penalties.values().sum::<usize>(), 2 * stats.rounds);
+    assert!(stats.average_rounds() >= 5.0);
+    assert!((stats.pick_frequencies().iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    assert_eq!(stats.first_player_wins, first_player_wins);
+    let csv = stats.to_csv();
+    assert!(csv.starts_with("table,key,value\n"));
+    assert!(csv.contains("games,games,5\n"));
+}
@@ -0,0 +1,141 @@
+use azul::{
+    engine::{Random, Spec, Temperature},
+    tournament::{self, play_adjudicated, Adjudication, Ending, Schedule},
+    DeterministicGameState,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use std::cmp::Ordering;
+
+#[test]
+fn adjudication_decides_games_early() {
+    let mut rng = StdRng::seed_from_u64(1);
+    // anything is far enough ahead
+    let rules = Adjudication {
+        adjudicate: Some(0),
+        adjudicate_moves: 1,
+        ..Adjudication::default()
+    };
+    let (state, ending) = play_adjudicated(&mut [Random, Random], &mut rng, &rules, &mut ());
+    assert!(matches!(ending, Ending::Adjudicated(_)));
+    assert_eq!(state.moves, 0);
+    let (state, ending) = play_adjudicated(
+        &mut [Random, Random],
+        &mut rng,
+        &Adjudication::default(),
+        &mut (),
+    );
+    assert_eq!(ending, Ending::Played);
+    assert!(state.result().is_over());
+}
+
+#[test]
+fn resignations_count_as_losses() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let rules = Adjudication {
+        resign: Some(500),
+        resign_moves: 1,
+        ..Adjudication::default()
+    };
+    let summary = tournament::run(
+        &Spec::Greedy,
+        &Spec::Random,
+        6,
+        &rules,
+        &Temperature::default(),
+        &mut rng,
+        &mut (),
+        |_, _| {},
+    );
+    assert_eq!(summary.games(), 6);
+    assert!(summary.resignations > 0);
+    assert_eq!(summary.adjudications, 0);
+    assert_eq!(summary.wins + summary.draws + summary.losses, 6);
+    let wins = summary
+        .results()
+        .iter()
+        .filter(|result| **result == Ordering::Greater);
+    assert_eq!(wins.count(), summary.wins);
+}
+
+#[test]
+fn round_robins_pair_every_engine() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let engines = [Spec::Random, Spec::Greedy, Spec::Random];
+    let table = tournament::run_tournament(
+        &engines,
+        "round-robin".parse().unwrap(),
+        2,
+        &Adjudication::default(),
+        &Temperature::default(),
+        &mut rng,
+        &mut (),
+        |_, _, _, _| {},
+    );
+    assert_eq!(table.matches().count(), 3);
+    for (a, b, summary) in table.matches() {
+        assert_eq!(summary.games(), 2);
+        let swapped = table.summary(b, a).unwrap();
+        assert_eq!(swapped.wins, summary.losses);
+        assert_eq!(
+            swapped.points()[0],
+            (summary.points()[0].1, summary.points()[0].0)
+        );
+    }
+    let total: f64 = (0..3).map(|engine| table.score(engine)).sum();
+    assert_eq!(total, 6.0);
+    assert_eq!(table.standings()[0], 1);
+    assert!(table.to_string().lines().nth(1).unwrap().contains("greedy"));
+}
+
+#[test]
+fn swiss_rounds_avoid_rematches_and_give_byes() {
+    assert_eq!("swiss:2".parse(), Ok(Schedule::Swiss(2)));
+    assert!("swiss".parse::<Schedule>().is_err());
+    let mut rng = StdRng::seed_from_u64(4);
+    let engines = [Spec::Random, Spec::Random, Spec::Random];
+    let table = tournament::run_tournament(
+        &engines,
+        Schedule::Swiss(3),
+        1,
+        &Adjudication::default(),
+        &Temperature::default(),
+        &mut rng,
+        &mut (),
+        |_, _, _, _| {},
+    );
+    // a bye each, and the three other engines met once
+    assert_eq!(table.matches().count(), 3);
+    assert!(table.matches().all(|(_, _, summary)| summary.games() == 1));
+    let total: f64 = (0..3).map(|engine| table.score(engine)).sum();
+    assert_eq!(total, 6.0);
+}
+
+#[test]
+fn match_tables_show_both_engines() {
+    let mut rng = StdRng::seed_from_u64(4);
+    let rules = Adjudication::default();
+    let summary = tournament::run(
+        &Spec::Greedy,
+        &Spec::Random,
+        2,
+        &rules,
+        &Temperature::default(),
+        &mut rng,
+        &mut (),
+        |_, _| {},
+    );
+    let table = tournament::format_match("greedy", "random", &summary, &rules);
+    let lines: Vec<_> = table.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert!(lines[1].starts_with("greedy"));
+    assert!(lines[2].starts_with("random"));
+    assert!(lines[3].starts_with("score of greedy: "));
+    // games can't be cut short by these rules, so none are counted
+    assert!(!table.contains("resigned"));
+    let rules = Adjudication {
+        resign: Some(30),
+        ..rules
+    };
+    let table = tournament::format_match("greedy", "random", &summary, &rules);
+    assert!(table.ends_with("0 resigned, 0 adjudicated"));
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "serde")]
+
+use azul::{
+    random_move,
+    tune::{self, Spsa},
+    DeterministicGameState, Evaluation, LinearEval, State,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use std::{env, fs};
+
+#[test]
+fn default_weights_count_the_lead() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let mut state = State::new(2);
+    state.deal(&mut rng);
+    while !state.result().is_over() {
+        state = random_move(&state, &mut rng);
+    }
+    let lead = state.players[0].points as i32 - state.players[1].points as i32;
+    let evaluation = LinearEval::default();
+    assert_eq!(evaluation.evaulate(&state, 0), 100 * lead);
+    assert_eq!(evaluation.evaulate(&state, 1), -100 * lead);
+}
+
+#[test]
+fn tuning_saves_every_iteration() {
+    let path = env::temp_dir().join(format!("azul-weights-{}.json", std::process::id()));
+    let spsa = Spsa {
+        iterations: 2,
+        games: 1,
+        ..Spsa::default()
+    };
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut saved = 0;
+    let tuned = tune::tune(LinearEval::default(), &spsa, &mut rng, |_, weights| {
+        weights.save(&path).unwrap();
+        saved += 1;
+    });
+    assert_eq!(saved, 2);
+    assert!(tuned.weights.iter().all(|weight| weight.is_finite()));
+    assert_eq!(LinearEval::load(&path).unwrap(), tuned);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(LinearEval::load(&path).unwrap(), LinearEval::default());
+}
+
+#[test]
+fn saved_tuning_picks_up_from_the_file() {
+    let path = env::temp_dir().join(format!("azul-saved-{}.json", std::process::id()));
+    let spsa = Spsa {
+        iterations: 1,
+        games: 1,
+        ..Spsa::default()
+    };
+    let mut rng = StdRng::seed_from_u64(2);
+    let mut iterations = 0;
+    let first = tune::tune_saved(&path, &spsa, &mut rng, |_| iterations += 1).unwrap();
+    assert_eq!(iterations, 1);
+    assert_eq!(LinearEval::load(&path).unwrap(), first);
+    // tuning again starts from the saved weights, as tuning on would
+    let mut rng = StdRng::seed_from_u64(3);
+    let second = tune::tune_saved(&path, &spsa, &mut rng, |_| {}).unwrap();
+    let mut rng = StdRng::seed_from_u64(3);
+    let expected = tune::tune(first, &spsa, &mut rng, |_, _| {});
+    assert_eq!(second, expected);
+    fs::remove_file(&path).unwrap();
+}
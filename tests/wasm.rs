@@ -0,0 +1,45 @@
+#![cfg(feature = "wasm")]
+
+// the API as plain Rust; errors need a JavaScript host to be thrown in
+
+use azul::wasm::Game;
+
+#[test]
+fn games_are_dealt_from_the_seed() {
+    let game = Game::new(3, 7).unwrap();
+    let again = Game::new(3, 7).unwrap();
+    assert_eq!(game.notation(), again.notation());
+    assert_eq!(game.current_player(), 0);
+    assert!(!game.legal_moves().is_empty());
+    assert_eq!(game.winner(), None);
+    assert!(game.winners().is_empty());
+    let state: serde_json::Value = serde_json::from_str(&game.state()).unwrap();
+    assert_eq!(state["players"].as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn moves_found_by_the_engine_are_played() {
+    let mut game = Game::new(2, 1).unwrap();
+    let m = game.legal_moves()[0].clone();
+    game.apply(&m).unwrap();
+    assert_eq!(game.current_player(), 1);
+    assert!(!game.legal_moves().contains(&m));
+    let best = game.search(2).unwrap();
+    assert!(game.legal_moves().contains(&best));
+    game.apply(&best).unwrap();
+    assert_eq!(game.current_player(), 0);
+    // a game picked up from its notation is the same game
+    let copy = Game::from_notation(&game.notation(), 1).unwrap();
+    assert_eq!(copy.notation(), game.notation());
+    assert_eq!(copy.legal_moves(), game.legal_moves());
+}
+
+#[test]
+fn finished_games_have_winners_and_no_moves() {
+    let notation = "- - - 10:-/-/-/-/-:AYRBW/WAYRB/...../...../.....:- 10:-/-/-/-/-:AYRBW/WAYRB/...../...../.....:- 10";
+    let mut game = Game::from_notation(notation, 1).unwrap();
+    assert_eq!(game.winner(), None);
+    assert_eq!(game.winners(), vec![0, 1]);
+    assert!(game.legal_moves().is_empty());
+    assert_eq!(game.search(2), None);
+}
@@ -0,0 +1,78 @@
+#![cfg(feature = "serde")]
+
+use azul::{
+    engine::{self, Dealer, Random},
+    game_log::GameLog,
+    observer::JsonLines,
+    watch::{tail, Spectator},
+    DeterministicGameState, State,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use std::{io::Cursor, time::Duration};
+
+// the positions of a random game after every move and deal, with its log
+// and event stream
+fn game() -> (Vec<State>, Vec<u8>, Vec<u8>) {
+    let mut rng = StdRng::seed_from_u64(5);
+    let mut dealer = Dealer::new(6);
+    let mut log = Vec::new();
+    let mut events = JsonLines::new(Vec::new());
+    let mut state = State::new(3);
+    dealer.deal(&mut state);
+    let mut positions = Vec::new();
+    while !state.result().is_over() {
+        let next = engine::play_observed(&mut Random, &state, &mut rng, &mut dealer, &mut events);
+        GameLog::new(&mut log)
+            .push(&state, &next, None, Duration::ZERO)
+            .unwrap();
+        positions.push(next.clone());
+        state = next;
+    }
+    (positions, log, events.finish().unwrap())
+}
+
+// the positions a spectator sees following the lines
+fn watch(lines: Vec<u8>) -> Vec<State> {
+    let mut spectator = Spectator::new();
+    let mut seen = Vec::new();
+    tail(Cursor::new(lines), Duration::ZERO, |line| {
+        if spectator.update_line(line).unwrap() {
+            seen.push(spectator.state().unwrap().clone());
+        }
+        !spectator.is_over()
+    })
+    .unwrap();
+    seen
+}
+
+#[test]
+fn spectators_follow_logs_and_event_streams() {
+    let (positions, log, events) = game();
+    let last = positions.last().unwrap();
+    let logged = watch(log);
+    assert_eq!(logged.len(), positions.len());
+    assert!(logged.last().unwrap() == last);
+    // the event stream also shows every deal as it's made
+    let streamed = watch(events);
+    assert!(streamed.len() > positions.len());
+    for position in &positions {
+        assert!(streamed.iter().any(|state| state == position));
+    }
+    assert!(streamed.last().unwrap() == last);
+}
+
+#[test]
+fn spectators_report_bad_lines() {
+    let mut spectator = Spectator::new();
+    assert_eq!(spectator.update_line(""), Ok(false));
+    assert!(spectator
+        .update_line("{\"position\": \"nonsense\"}")
+        .is_err());
+    assert!(spectator.update_line("not json").is_err());
+    let message = r#"{"type": "error", "message": "it's not your turn"}"#;
+    assert_eq!(
+        spectator.update_line(message),
+        Err("it's not your turn".to_string())
+    );
+    assert!(spectator.state().is_none());
+}